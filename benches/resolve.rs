@@ -0,0 +1,112 @@
+//! Profile resolution benchmarks.
+//!
+//! wayscope is a binary crate, so the config and profile modules are
+//! compiled in directly. Run with `cargo bench`.
+
+#![allow(dead_code)]
+
+#[path = "../src/config.rs"]
+mod config;
+#[path = "../src/profile.rs"]
+mod profile;
+
+use std::fmt::Write;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use config::{Config, MonitorsConfig, ProfilesConfig};
+
+/// Number of profiles in the fixture; well above any real config.
+const PROFILES: usize = 200;
+
+const MONITORS: &str = r#"
+monitors:
+  main:
+    width: 2560
+    height: 1440
+    refreshRate: 165
+    vrr: true
+    hdr: true
+    primary: true
+  tv:
+    width: 3840
+    height: 2160
+    refreshRate: 120
+    hdr: true
+  deck:
+    width: 1280
+    height: 800
+    refreshRate: 90
+"#;
+
+/// A large config touching every resolution path: typed fields, deprecated
+/// option renames, many options and environment variables, globbed unsets,
+/// and alias chains.
+fn fixture() -> Config {
+    let mut yaml = String::from("aliases:\n");
+    for i in 0..PROFILES / 10 {
+        writeln!(yaml, "  a{i}: profile-{i}").unwrap();
+        writeln!(yaml, "  b{i}: a{i}").unwrap();
+    }
+
+    yaml.push_str("profiles:\n");
+    for i in 0..PROFILES {
+        let monitor = ["main", "tv", "deck"][i % 3];
+        writeln!(yaml, "  profile-{i}:").unwrap();
+        writeln!(yaml, "    monitor: {monitor}").unwrap();
+        writeln!(yaml, "    useHDR: {}", i % 2 == 0).unwrap();
+        writeln!(yaml, "    rotation: {}", [0, 90, 180, 270][i % 4]).unwrap();
+        writeln!(yaml, "    rotationSwapsResolution: true").unwrap();
+        writeln!(yaml, "    sdrNits: 203").unwrap();
+        writeln!(yaml, "    options:").unwrap();
+        writeln!(yaml, "      fsr-sharpness: {}", i % 20).unwrap();
+        writeln!(yaml, "      filter: fsr").unwrap();
+        writeln!(yaml, "      nested-width: 1920").unwrap();
+        writeln!(yaml, "      nested-height: 1080").unwrap();
+        writeln!(yaml, "      framerate-limit: {}", 30 + i % 120).unwrap();
+        for j in 0..10 {
+            writeln!(yaml, "      custom-flag-{j}: true").unwrap();
+        }
+        writeln!(yaml, "    environment:").unwrap();
+        for j in 0..20 {
+            writeln!(yaml, "      VAR_{j}: value-{i}-{j}").unwrap();
+        }
+        writeln!(yaml, "    unset: [\"SDL_*\", DISPLAY, \"*_DEBUG\"]").unwrap();
+    }
+
+    Config {
+        monitors: serde_yaml::from_str::<MonitorsConfig>(MONITORS).unwrap(),
+        profiles: serde_yaml::from_str::<ProfilesConfig>(&yaml).unwrap(),
+    }
+}
+
+fn resolve(c: &mut Criterion) {
+    let config = fixture();
+
+    c.bench_function("resolve_profile", |b| {
+        b.iter(|| config.resolve_profile(black_box("profile-100")).unwrap())
+    });
+    c.bench_function("resolve_profile_via_alias", |b| {
+        b.iter(|| config.resolve_profile(black_box("b10")).unwrap())
+    });
+    c.bench_function("resolve_all_profiles", |b| {
+        b.iter(|| {
+            for (name, _) in config.list_profiles(false) {
+                black_box(config.resolve_profile(&name).unwrap());
+            }
+        })
+    });
+}
+
+fn environment(c: &mut Criterion) {
+    let config = fixture();
+    let resolved = config.resolve_profile("profile-100").unwrap();
+
+    c.bench_function("environment", |b| {
+        b.iter(|| black_box(&resolved).environment())
+    });
+}
+
+criterion_group!(benches, resolve, environment);
+criterion_main!(benches);
@@ -102,7 +102,14 @@
               lockFile = ./Cargo.lock;
             };
 
-            inherit nativeBuildInputs;
+            nativeBuildInputs = nativeBuildInputs ++ [ pkgs.installShellFiles ];
+
+            postInstall = ''
+              installShellCompletion --cmd wayscope \
+                --bash <($out/bin/wayscope completions bash) \
+                --fish <($out/bin/wayscope completions fish) \
+                --zsh <($out/bin/wayscope completions zsh)
+            '';
 
             meta = with pkgs.lib; {
               description = "Profile-based gamescope wrapper for gaming on Linux";
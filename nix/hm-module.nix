@@ -27,9 +27,6 @@ let
         gamescope-wsi = pkgs.gamescope-wsi or null;
       };
 
-  # Binary path based on useGit setting
-  defaultBinary = lib.getExe gamescopePackages.gamescope;
-
   # Check if mix.nix monitors are available
   hasSystemMonitors = config ? monitors && config.monitors != [ ];
 
@@ -44,11 +41,10 @@ let
         inherit (mon)
           width
           height
+          refreshRate
           vrr
           hdr
           ;
-        refreshRate =
-          if builtins.isFloat mon.refreshRate then builtins.floor mon.refreshRate else mon.refreshRate;
         primary = if mon.primary then true else null;
       };
     }) (lib.filter (m: m.enabled) config.monitors)
@@ -73,30 +69,86 @@ let
           vrr
           hdr
           ;
+        connector = mon.connector or null;
+        connectors = mon.connectors or null;
+        preferOutput = mon.preferOutput or null;
+        iccProfile = mon.iccProfile or null;
+        model = mon.model or null;
         primary = if mon.primary or false then true else null;
       }
     ) effectiveMonitors;
   };
 
   # Generate config.yaml content
-  profilesConfig = {
-    profiles = lib.mapAttrs (
-      _: prof:
-      lib.filterAttrs (_: v: v != null && v != { } && v != [ ]) {
-        inherit (prof)
-          monitor
-          useHDR
-          useWSI
-          ;
-        # Convert package to binary path for YAML
-        # Nix uses "package" (types.package), YAML uses "binary" (path string)
-        binary = if prof.package != null then lib.getExe prof.package else defaultBinary;
-        options = if prof.options == { } then null else prof.options;
-        environment = if prof.environment == { } then null else prof.environment;
-        unset = if prof.unset == [ ] then null else prof.unset;
-      }
-    ) cfg.profiles;
-  };
+  profilesConfig =
+    lib.optionalAttrs (cfg.aliases != { }) { inherit (cfg) aliases; }
+    // lib.optionalAttrs (cfg.defaultProfile != null) { inherit (cfg) defaultProfile; }
+    // {
+      profiles = lib.mapAttrs (
+        _: prof:
+        lib.filterAttrs (_: v: v != null && v != { } && v != [ ]) {
+          inherit (prof)
+            monitor
+            useHDR
+            useWSI
+            allowHDRWithoutWSI
+            vrr
+            immediateFlipsWithVRR
+            touchMode
+            tablet
+            rotation
+            rotationSwapsResolution
+            disableColorManagement
+            envFile
+            quietChild
+            notifyOnExit
+            logSessions
+            skipGamescope
+            wrapperTarget
+            systemdScope
+            envPriority
+            xwaylandCount
+            drmMode
+            supersample
+            filter
+            windowMode
+            hideCursorDelay
+            cursorScaleHeight
+            sdrNits
+            hdrMaxNits
+            windowTitle
+            locale
+            timezone
+            protonVersion
+            compatDataPath
+            compatClientInstallPath
+            gamescopeLog
+            preHook
+            postHook
+            preTimeout
+            postTimeout
+            hookFailure
+            ;
+          # Convert package to binary path for YAML
+          # Nix uses "package" (types.package), YAML uses "binary" (path string)
+          # Left out otherwise, so WAYSCOPE_GAMESCOPE_BIN and then the gamescope
+          # installed below (from useGit) apply
+          binary = if prof.package != null then lib.getExe prof.package else null;
+          options = if prof.options == { } then null else prof.options;
+          optionsWhenHdr = if prof.optionsWhenHdr == { } then null else prof.optionsWhenHdr;
+          optionsWhenVrr = if prof.optionsWhenVrr == { } then null else prof.optionsWhenVrr;
+          environment = if prof.environment == { } then null else prof.environment;
+          unset = if prof.unset == [ ] then null else prof.unset;
+          gameEnv = if prof.gameEnv == { } then null else prof.gameEnv;
+          gameUnset = if prof.gameUnset == [ ] then null else prof.gameUnset;
+          disableBaseEnv = if prof.disableBaseEnv == [ ] then null else prof.disableBaseEnv;
+          compose = if prof.compose == [ ] then null else prof.compose;
+          wrapper = if prof.wrapper == [ ] then null else prof.wrapper;
+          rlimits = if prof.rlimits == { } then null else prof.rlimits;
+          scopeProperties = if prof.scopeProperties == { } then null else prof.scopeProperties;
+        }
+      ) cfg.profiles;
+    };
 
   # Create a wrapper script for an application
   mkWrapper =
@@ -115,6 +167,7 @@ let
     oneOf [
       bool
       int
+      float
       str
     ];
 in
@@ -172,9 +225,9 @@ in
             };
 
             refreshRate = lib.mkOption {
-              type = lib.types.int;
-              example = 165;
-              description = "Refresh rate in Hz.";
+              type = lib.types.number;
+              example = 59.94;
+              description = "Refresh rate in Hz; fractional rates are rounded for gamescope.";
             };
 
             vrr = lib.mkOption {
@@ -194,6 +247,44 @@ in
               default = false;
               description = "Use this monitor when profile doesn't specify one.";
             };
+
+            connector = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "DP-1";
+              description = "Connector name as reported by `wayscope detect`. Passed to gamescope as --prefer-output.";
+            };
+
+            connectors = lib.mkOption {
+              type = lib.types.nullOr (lib.types.nonEmptyListOf lib.types.str);
+              default = null;
+              example = [
+                "DP-1"
+                "eDP-1"
+              ];
+              description = "Connectors to try in order, instead of connector. Passed as --prefer-output a,b; gamescope only falls back between them on the drm backend.";
+            };
+
+            preferOutput = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "HDMI-A-1";
+              description = "Output name gamescope expects. If null, derived from connector.";
+            };
+
+            iccProfile = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "~/.local/share/icc/main.icc";
+              description = "ICC profile for the display. Shown by `show` and `monitors`, but not applied: gamescope has no option to load one.";
+            };
+
+            model = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "Dell U2720Q";
+              description = "The physical monitor, as reported by `wayscope detect`. Informational only; shown by `monitors`.";
+            };
           };
         }
       );
@@ -226,6 +317,13 @@ in
       type = lib.types.attrsOf (
         lib.types.submodule {
           options = {
+            compose = lib.mkOption {
+              type = lib.types.listOf lib.types.str;
+              default = [ ];
+              example = [ "base" "hdr" "tv" ];
+              description = "Profiles merged under this one, left to right; later entries and this profile's own settings win.";
+            };
+
             monitor = lib.mkOption {
               type = lib.types.nullOr lib.types.str;
               default = null;
@@ -239,7 +337,8 @@ in
               example = lib.literalExpression "pkgs.gamescope";
               description = ''
                 Gamescope package to use for this profile.
-                If null (default), uses gamescope from useGit setting (gamescope_git or stable).
+                If null (default), no binary is written: WAYSCOPE_GAMESCOPE_BIN wins if set,
+                otherwise the gamescope installed from the useGit setting (gamescope_git or stable).
               '';
             };
 
@@ -255,6 +354,291 @@ in
               description = "Enable Gamescope WSI layer. Defaults to true.";
             };
 
+            allowHDRWithoutWSI = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Silence the warning for profiles that enable HDR with useWSI = false.";
+            };
+
+            vrr = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Turn adaptive sync on or off. If null, follows the monitor's vrr; turning it on requires a VRR monitor.";
+            };
+
+            immediateFlipsWithVRR = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Keep (true) or drop (false) immediate-flips when adaptive-sync is on. If null, both stay, and wayscope warns when the profile turns both on itself.";
+            };
+
+            touchMode = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 0 4);
+              default = null;
+              description = "Gamescope default touch mode (0=hover, 1=left, 2=right, 3=middle, 4=passthrough).";
+            };
+
+            tablet = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Shorthand for touchMode = 4 (touch passthrough).";
+            };
+
+            rotation = lib.mkOption {
+              type = lib.types.nullOr (lib.types.enum [ 0 90 180 270 ]);
+              default = null;
+              description = "Display rotation in degrees clockwise (maps to --force-orientation).";
+            };
+
+            rotationSwapsResolution = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Swap monitor-derived output width/height when rotated 90 or 270 degrees.";
+            };
+
+            disableColorManagement = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Disable gamescope color management (troubleshooting; undermines HDR).";
+            };
+
+            quietChild = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Redirect the child command's stdout/stderr to /dev/null.";
+            };
+
+            notifyOnExit = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Send a desktop notification when the session exits (wayscope stays resident instead of exec'ing).";
+            };
+
+            logSessions = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Append start (and, when wayscope stays resident, end) records to the session log shown by `wayscope sessions`.";
+            };
+
+            skipGamescope = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Run the command directly with the profile environment instead of through gamescope (override with --no-skip-gamescope).";
+            };
+
+            rlimits = lib.mkOption {
+              type = lib.types.attrsOf (lib.types.either lib.types.ints.unsigned (lib.types.enum [ "unlimited" ]));
+              default = { };
+              example = {
+                nofile = 524288;
+                memlock = "unlimited";
+              };
+              description = "Resource limits set before launch (as, core, cpu, data, fsize, memlock, nice, nofile, nproc, rtprio, stack).";
+            };
+
+            systemdScope = lib.mkOption {
+              type = lib.types.nullOr lib.types.bool;
+              default = null;
+              description = "Launch inside a transient systemd-run --user --scope. Defaults to on when scopeProperties is set.";
+            };
+
+            scopeProperties = lib.mkOption {
+              type = lib.types.attrsOf lib.types.str;
+              default = { };
+              example = {
+                MemoryMax = "16G";
+                CPUWeight = "200";
+              };
+              description = "Unit properties for the systemd scope, passed as -p Key=Value.";
+            };
+
+            wrapper = lib.mkOption {
+              type = lib.types.listOf lib.types.str;
+              default = [ ];
+              example = [ "gamemoderun" ];
+              description = "Command prepended to the launch.";
+            };
+
+            wrapperTarget = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "gamescope"
+                  "child"
+                ]
+              );
+              default = null;
+              example = "child";
+              description = "Whether the wrapper wraps gamescope itself (default) or only the child command.";
+            };
+
+            xwaylandCount = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 1 4);
+              default = null;
+              description = "Number of Xwayland servers gamescope starts (--xwayland-count).";
+            };
+
+            drmMode = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "cvt"
+                  "fixed"
+                ]
+              );
+              default = null;
+              example = "cvt";
+              description = "Have gamescope generate a DRM mode (--generate-drm-mode); only applies to the drm backend.";
+            };
+
+            supersample = lib.mkOption {
+              type = lib.types.nullOr lib.types.float;
+              default = null;
+              example = 1.5;
+              description = "Render at this multiple of the output resolution and downscale (1.0-2.0). Sets nested-width/height and defaults filter to linear.";
+            };
+
+            filter = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "linear"
+                  "nearest"
+                  "fsr"
+                  "nis"
+                  "pixel"
+                ]
+              );
+              default = null;
+              example = "fsr";
+              description = "Upscaling filter (--filter). options.sharpness only applies to fsr and nis.";
+            };
+
+            windowMode = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "fullscreen"
+                  "borderless"
+                  "windowed"
+                ]
+              );
+              default = null;
+              example = "borderless";
+              description = "How gamescope's window is shown. Sets options.fullscreen and options.borderless together so they can't conflict.";
+            };
+
+            hideCursorDelay = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 0 60000);
+              default = null;
+              example = 3000;
+              description = "Milliseconds of inactivity before the cursor hides (--hide-cursor-delay).";
+            };
+
+            cursorScaleHeight = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 1 16384);
+              default = null;
+              example = 1080;
+              description = "Output height the cursor size is scaled against (--cursor-scale-height).";
+            };
+
+            sdrNits = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 1 10000);
+              default = null;
+              description = "SDR content brightness in HDR mode (--hdr-sdr-content-nits).";
+            };
+
+            hdrMaxNits = lib.mkOption {
+              type = lib.types.nullOr (lib.types.ints.between 1 10000);
+              default = null;
+              description = "Peak brightness for inverse tone mapping (--hdr-itm-target-nits).";
+            };
+
+            windowTitle = lib.mkOption {
+              type = lib.types.nullOr lib.types.nonEmptyStr;
+              default = null;
+              example = "\${GAME} via wayscope";
+              description = "Gamescope window title. \${VAR} references expand from the profile environment.";
+            };
+
+            locale = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "ja_JP.UTF-8";
+              description = "Locale for the game, exported as LANG and LC_ALL.";
+            };
+
+            timezone = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "Asia/Tokyo";
+              description = "Timezone for the game, exported as TZ.";
+            };
+
+            protonVersion = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "GE-Proton9-20";
+              description = "Proton version, exported as PROTON_VERSION.";
+            };
+
+            compatDataPath = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "~/Games/prefixes/\${GAME}";
+              description = "Proton prefix directory, exported as STEAM_COMPAT_DATA_PATH. ~ and \${VAR} are expanded.";
+            };
+
+            compatClientInstallPath = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "~/.steam/steam";
+              description = "Steam install directory, exported as STEAM_COMPAT_CLIENT_INSTALL_PATH. ~ and \${VAR} are expanded.";
+            };
+
+            gamescopeLog = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "silent"
+                  "error"
+                  "warning"
+                  "info"
+                  "debug"
+                ]
+              );
+              default = null;
+              example = "debug";
+              description = "gamescope's internal log level, exported as GAMESCOPE_LOG.";
+            };
+
+            preHook = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "pkill -STOP picom";
+              description = "Shell command run (sh -c) before the launch.";
+            };
+
+            postHook = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "pkill -CONT picom";
+              description = "Shell command run after the session exits. wayscope stays resident instead of exec'ing.";
+            };
+
+            preTimeout = lib.mkOption {
+              type = lib.types.nullOr lib.types.ints.positive;
+              default = null;
+              description = "Seconds preHook may run before it is killed.";
+            };
+
+            postTimeout = lib.mkOption {
+              type = lib.types.nullOr lib.types.ints.positive;
+              default = null;
+              description = "Seconds postHook may run before it is killed.";
+            };
+
+            hookFailure = lib.mkOption {
+              type = lib.types.nullOr (lib.types.enum [ "abort" "warn" ]);
+              default = null;
+              description = "What a failed or timed-out hook does: abort (preHook stops the launch, postHook fails wayscope's exit) or warn. Defaults to abort.";
+            };
+
             options = lib.mkOption {
               type = lib.types.attrsOf optionValueType;
               default = { };
@@ -271,6 +655,24 @@ in
               '';
             };
 
+            optionsWhenHdr = lib.mkOption {
+              type = lib.types.attrsOf optionValueType;
+              default = { };
+              example = {
+                hdr-sdr-content-nits = 203;
+              };
+              description = "Options merged over `options` only when the monitor supports HDR.";
+            };
+
+            optionsWhenVrr = lib.mkOption {
+              type = lib.types.attrsOf optionValueType;
+              default = { };
+              example = {
+                framerate-limit = 0;
+              };
+              description = "Options merged over `options` only when the monitor supports VRR.";
+            };
+
             environment = lib.mkOption {
               type = lib.types.attrsOf lib.types.str;
               default = { };
@@ -281,6 +683,38 @@ in
               description = "Additional environment variables for games using this profile.";
             };
 
+            envPriority = lib.mkOption {
+              type = lib.types.nullOr (
+                lib.types.enum [
+                  "user"
+                  "base"
+                ]
+              );
+              default = null;
+              example = "base";
+              description = "Whether the profile's environment (default) or wayscope's default variables win when both set the same variable.";
+            };
+
+            disableBaseEnv = lib.mkOption {
+              type = lib.types.listOf (
+                lib.types.enum [
+                  "amd"
+                  "wayland"
+                  "proton"
+                ]
+              );
+              default = [ ];
+              example = [ "amd" ];
+              description = "Groups of wayscope's default variables to leave out: `amd` (RADV) on NVIDIA/Intel, `proton` for native games, `wayland` for X11 clients.";
+            };
+
+            envFile = lib.mkOption {
+              type = lib.types.nullOr lib.types.str;
+              default = null;
+              example = "~/games/common.env";
+              description = "Dotenv-style file loaded before `environment` (inline values win).";
+            };
+
             unset = lib.mkOption {
               type = lib.types.listOf lib.types.str;
               default = [ ];
@@ -288,8 +722,25 @@ in
               description = ''
                 Environment variables to remove (unset) for this profile.
                 These remove variables from both wayscope defaults AND parent process environment.
+                Entries may use `*` as a glob, e.g. "SDL_*".
               '';
             };
+
+            gameEnv = lib.mkOption {
+              type = lib.types.attrsOf lib.types.str;
+              default = { };
+              example = {
+                MANGOHUD = "1";
+              };
+              description = "Environment variables set only for the game, not for gamescope itself.";
+            };
+
+            gameUnset = lib.mkOption {
+              type = lib.types.listOf lib.types.str;
+              default = [ ];
+              example = [ "LD_PRELOAD" ];
+              description = "Environment variables removed only for the game (exact names, no globs).";
+            };
           };
         }
       );
@@ -305,10 +756,10 @@ in
           };
           performance = {
             useHDR = false;
+            filter = "fsr";
             options = {
               nested-width = 1920;
               nested-height = 1080;
-              filter = "fsr";
             };
           };
           wayland-only = {
@@ -326,6 +777,25 @@ in
       description = "Gaming profile definitions.";
     };
 
+    aliases = lib.mkOption {
+      type = lib.types.attrsOf lib.types.str;
+      default = { };
+      example = {
+        perf = "performance";
+      };
+      description = "Short names mapping to profile names (or other aliases).";
+    };
+
+    defaultProfile = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "hdr";
+      description = ''
+        Profile used by `wayscope run` when neither `-p` nor WAYSCOPE_PROFILE is set.
+        If null, the profile named "default" is used.
+      '';
+    };
+
     # =========================================================================
     # Wrapper Configuration
     # =========================================================================
@@ -3,9 +3,12 @@
 //! Uses clap's derive macros for declarative argument parsing.
 //! The CLI supports three main commands: run (default), list, and show.
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use std::path::PathBuf;
 
+use crate::config::BaseEnvGroup;
+
 /// Profile-based gamescope wrapper for gaming on Linux.
 ///
 /// Wayscope simplifies running games through gamescope by providing
@@ -27,10 +30,70 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
 
+    /// Resolve relative --monitors/--config paths against the config directory
+    ///
+    /// By default, relative paths are resolved against the current working
+    /// directory. With this flag they resolve against ~/.config/wayscope.
+    #[arg(long, global = true)]
+    pub config_relative_to_config_dir: bool,
+
+    /// How to use WAYSCOPE_CONFIG_PATH when it lists several directories
+    ///
+    /// 'first' reads each file from the first directory that has it;
+    /// 'merge' layers every match, earlier directories winning.
+    #[arg(long, global = true, value_name = "MODE", default_value = "first")]
+    pub config_path_mode: ConfigPathMode,
+
+    /// Layer .wayscope/ in the current directory over the user's config
+    ///
+    /// Off by default: profiles can run commands (preHook, wrapper, ...),
+    /// so only enable it in directories you trust.
+    #[arg(long, global = true)]
+    pub local_config: bool,
+
+    /// Treat config warnings as errors
+    ///
+    /// Fails on issues in the profile definition itself, such as deprecated
+    /// option names or a typed field duplicated in 'options'.
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Write a step-by-step log of profile resolution to PATH
+    ///
+    /// Records alias and compose steps, the monitor, each option every
+    /// stage sets, conditional blocks, interpolations, environment stages
+    /// and unsets. Meant for bug reports about resolution; the file is
+    /// written even when resolution fails. It holds environment and envFile
+    /// values verbatim, tokens included, so check it before sharing.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub trace: Option<PathBuf>,
+
+    /// When to use colored output
+    ///
+    /// 'auto' colors only when writing to a terminal and NO_COLOR is unset;
+    /// 'always' forces color even when piped (e.g. into 'less -R').
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorWhen,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// `--config-path-mode` choices.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ConfigPathMode {
+    First,
+    Merge,
+}
+
+/// `--color` choices.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorWhen {
+    Always,
+    Auto,
+    Never,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Initialize configuration files with examples
@@ -42,6 +105,12 @@ pub enum Commands {
         /// Overwrite existing configuration files
         #[arg(short, long)]
         force: bool,
+
+        /// Print the templates to stdout instead of writing them
+        ///
+        /// Nothing is created or overwritten, so --force has no effect.
+        #[arg(long, alias = "stdout")]
+        preview: bool,
     },
 
     /// Run a command through gamescope with the specified profile
@@ -54,8 +123,18 @@ pub enum Commands {
     /// List all available profiles
     ///
     /// Shows each profile's name, target monitor, and key settings.
+    /// Profiles that fail to resolve are listed with their error.
     #[command(name = "list", alias = "ls")]
-    List,
+    List {
+        /// Leave out profiles that fail to resolve instead of listing the error
+        #[arg(long)]
+        skip_broken: bool,
+
+        /// Print every profile as flat 'profile.key=value' lines (see 'show --flat');
+        /// a broken profile gets a 'profile.error=...' line
+        #[arg(long)]
+        flat: bool,
+    },
 
     /// Show detailed information about a profile
     ///
@@ -65,13 +144,265 @@ pub enum Commands {
     Show {
         /// Profile name to inspect
         profile: String,
+
+        /// Explain whether the DISABLE_HDR_WSI=1 workaround applies and why
+        #[arg(long)]
+        explain_workaround: bool,
+
+        /// Print the environment as one shell-quoted KEY=val line
+        #[arg(long)]
+        env_inline: bool,
+
+        /// Show which unset entries match variables in the current environment
+        ///
+        /// Lists each inherited variable (and its value) that launching this
+        /// profile from this shell would remove.
+        #[arg(long)]
+        print_unset: bool,
+
+        /// Group options by category (resolution, performance, HDR, ...)
+        ///
+        /// Only affects this listing; gamescope still receives options in
+        /// alphabetical order.
+        #[arg(long)]
+        group: bool,
+
+        /// Only list options that differ from the monitor defaults
+        ///
+        /// Hides values the profile inherits unchanged, leaving what it
+        /// actually changes.
+        #[arg(long)]
+        overrides_only: bool,
+
+        /// Print the resolved profile as JSON for 'run --from-resolved'
+        ///
+        /// Lets launchers resolve once and launch many times without
+        /// re-reading the config.
+        #[arg(long, conflicts_with_all = [
+            "explain_workaround",
+            "env_inline",
+            "print_unset",
+            "group",
+            "overrides_only",
+        ])]
+        dump_resolved: bool,
+
+        /// Print only the gamescope command line this profile launches
+        ///
+        /// Shows the launch as 'run --no-child' would start it.
+        #[arg(long, conflicts_with = "dump_resolved")]
+        command: bool,
+
+        /// With --command, prefix 'env -u UNSET ... KEY=val ...' so the line
+        /// reproduces the launch exactly when pasted into a shell
+        #[arg(long, requires = "command")]
+        with_env: bool,
+
+        /// Print the environment merge stage by stage
+        ///
+        /// Shows what the base variables, 'environment', typed fields,
+        /// WSI/HDR, the session marker and 'unset' each add, override or
+        /// remove, ending with the final environment.
+        #[arg(long, conflicts_with_all = ["dump_resolved", "command"])]
+        explain_order: bool,
+
+        /// Print the resolved profile as flat 'key=value' lines
+        ///
+        /// Keys are 'setting.<name>', 'options.<flag>', 'env.<VAR>' (as
+        /// accepted by 'get') and 'unset[N]', for grep and awk.
+        #[arg(long, conflicts_with_all = ["dump_resolved", "command", "explain_order"])]
+        flat: bool,
+    },
+
+    /// Print a single resolved value from a profile
+    ///
+    /// Keys are namespaced: 'options.<flag>', 'env.<VAR>', or
+    /// 'setting.<name>' (monitor, hdr, wsi, binary). Prints plain text
+    /// for scripting and exits non-zero when the key is not set.
+    #[command(name = "get")]
+    Get {
+        /// Profile name to resolve
+        profile: String,
+        /// Namespaced key, e.g. options.output-width
+        key: String,
+    },
+
+    /// Remove or repair profiles that reference missing monitors
+    ///
+    /// Prompts for each broken profile to remove it or fall back to the
+    /// primary monitor, then rewrites config.yaml (comments are not kept).
+    /// Profiles that inherit the monitor through compose are checked too,
+    /// and a profile that others still compose is kept.
+    #[command(name = "prune")]
+    Prune {
+        /// Remove broken profiles without prompting
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Back up both config files and replace them with the defaults
+    ///
+    /// For recovering from a broken config: unlike init --force, the
+    /// current files are always kept as timestamped .bak files next to
+    /// them. Asks first unless --yes is given.
+    #[command(name = "reset")]
+    Reset {
+        /// Reset without prompting (required without a terminal)
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Copy profiles from another config file into yours
+    ///
+    /// Copies the named profiles (or all of them) from FILE into
+    /// config.yaml, after checking the result loads. A name that's already
+    /// taken stops the import unless --force or --rename is given.
+    /// Comments in config.yaml are not kept.
+    #[command(name = "import")]
+    Import {
+        /// Config file to copy profiles from
+        file: PathBuf,
+
+        /// Profiles (or aliases) to copy; all when omitted
+        profiles: Vec<String>,
+
+        /// Replace existing profiles with the same name
+        #[arg(short, long, conflicts_with = "rename")]
+        force: bool,
+
+        /// Import clashing profiles as NAME-2, NAME-3, ... instead
+        #[arg(long)]
+        rename: bool,
     },
 
     /// List available monitors
     ///
     /// Shows configured monitors and their capabilities.
     #[command(name = "monitors")]
-    Monitors,
+    Monitors {
+        /// Compare two monitors and print their differences
+        #[arg(long, num_args = 2, value_names = ["A", "B"])]
+        diff: Option<Vec<String>>,
+
+        /// List monitors no profile targets and that aren't the primary
+        #[arg(long, alias = "list-unused-monitors", conflicts_with = "diff")]
+        unused: bool,
+
+        /// Print the gamescope options a monitor alone produces
+        ///
+        /// These are the defaults every profile on this monitor starts
+        /// from, before its own options and typed fields are applied.
+        #[arg(long, value_name = "MONITOR", conflicts_with_all = ["diff", "unused"])]
+        dump_defaults: Option<String>,
+
+        /// Show how each monitor differs from a plain one
+        ///
+        /// Compares against 60 Hz with no VRR, HDR, primary or connector,
+        /// to check that vrr/hdr are turned on where you meant to.
+        #[arg(
+            long,
+            alias = "dump-diff-from-defaults",
+            conflicts_with_all = ["diff", "unused", "dump_defaults"]
+        )]
+        changed: bool,
+    },
+
+    /// Detect connected outputs
+    ///
+    /// Queries the system (wlr-randr or DRM sysfs) for connected displays
+    /// and their capabilities, useful when writing monitors.yaml. The
+    /// monitor model is read from the EDID (or from wlr-randr).
+    #[command(name = "detect")]
+    Detect {
+        /// Only show capabilities for this output (e.g. DP-1)
+        output: Option<String>,
+
+        /// Print the outputs as monitors.yaml entries instead
+        ///
+        /// Each monitor is named after its connector and records the
+        /// detected model; the active output becomes the primary.
+        #[arg(long, conflicts_with = "output")]
+        yaml: bool,
+    },
+
+    /// Show recent sessions from the session log
+    ///
+    /// Lists launches logged via 'logSessions' or 'run --session-log',
+    /// oldest first, with their duration and exit status when known.
+    #[command(name = "sessions")]
+    Sessions {
+        /// Number of sessions to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+    },
+
+    /// Write a profile's launch as a standalone shell script
+    ///
+    /// The script applies the profile's rlimits with ulimit, sets its
+    /// environment and execs gamescope with the resolved arguments, so it
+    /// runs without wayscope installed. It is written executable (0755).
+    #[command(name = "export-script")]
+    ExportScript {
+        /// Profile name to export
+        profile: String,
+
+        /// Where to write the script [default: <profile>.sh]
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Overwrite an existing file
+        #[arg(short, long)]
+        force: bool,
+
+        /// Command to run inside gamescope, after '--'
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+
+    /// Point out settings that load fine but have no effect
+    ///
+    /// Lists options that repeat the monitor defaults, unset entries that
+    /// match no variable, environment entries that repeat wayscope's base
+    /// variables, and profiles identical to another one or resolving to
+    /// the same command, environment and limits.
+    #[command(name = "lint")]
+    Lint,
+
+    /// Check the system for common launch problems
+    ///
+    /// Checks the file descriptor limit Proton's esync needs and that each
+    /// monitor's iccProfile exists.
+    #[command(name = "doctor")]
+    Doctor,
+
+    /// Print a shell completion script
+    ///
+    /// Fish and zsh scripts also complete profile and monitor names from
+    /// the live config, e.g. 'wayscope completions fish | source'.
+    #[command(name = "completions")]
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+
+    /// Print version information
+    ///
+    /// With --verbose, also reports the gamescope version and path, the
+    /// session type, and config directories; useful for bug reports.
+    #[command(name = "version")]
+    Version {
+        /// Include gamescope, session, and config details
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Print profile and alias names, one per line (used by completions)
+    #[command(name = "__complete-profiles", hide = true)]
+    CompleteProfiles,
+
+    /// Print monitor names, one per line (used by completions)
+    #[command(name = "__complete-monitors", hide = true)]
+    CompleteMonitors,
 }
 
 #[derive(Parser)]
@@ -81,8 +412,18 @@ pub struct RunArgs {
     /// Selects which configuration profile to apply. Profiles define
     /// HDR, WSI, and gamescope options. Use 'wayscope list' to see
     /// available profiles.
-    #[arg(short, long, default_value = "default")]
-    pub profile: String,
+    ///
+    /// When omitted: $WAYSCOPE_PROFILE, then the config's defaultProfile,
+    /// then 'default'.
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    /// Launch from a 'show --dump-resolved' JSON file
+    ///
+    /// Skips loading and resolving the config entirely; the file is the
+    /// profile. Command-line overrides like --windowed still apply.
+    #[arg(long, value_name = "FILE", conflicts_with = "profile")]
+    pub from_resolved: Option<PathBuf>,
 
     /// Skip gamescope wrapper, run command directly
     ///
@@ -91,17 +432,229 @@ pub struct RunArgs {
     /// Useful for debugging, testing, or running non-game commands in
     /// a configured environment. Still applies all environment setup
     /// (RADV, Wayland, HDR vars, WSI, etc.) defined by the profile.
-    #[arg(short = 's', long)]
+    /// Overrides the profile's 'skipGamescope' setting.
+    #[arg(short = 's', long, overrides_with = "no_skip_gamescope")]
     pub skip_gamescope: bool,
 
+    /// Run through gamescope even if the profile sets 'skipGamescope'
+    #[arg(long, overrides_with = "skip_gamescope")]
+    pub no_skip_gamescope: bool,
+
+    /// Force HDR on for this launch, regardless of profile and monitor
+    #[arg(long, conflicts_with = "no_hdr")]
+    pub hdr: bool,
+
+    /// Force HDR off for this launch
+    #[arg(long)]
+    pub no_hdr: bool,
+
+    /// Force the gamescope WSI layer on for this launch
+    #[arg(long, conflicts_with = "no_wsi")]
+    pub wsi: bool,
+
+    /// Force the gamescope WSI layer off for this launch
+    #[arg(long)]
+    pub no_wsi: bool,
+
+    /// Replay the last launch: same resolved profile and command
+    ///
+    /// Reads the record 'run' saves just before each launch, without
+    /// loading the config. Command-line overrides like --windowed still apply.
+    #[arg(long, conflicts_with_all = ["profile", "from_resolved", "command", "shell", "no_child"])]
+    pub last: bool,
+
+    /// Launch gamescope even when already inside a gamescope session
+    ///
+    /// By default wayscope runs the command directly when it detects it is
+    /// already inside gamescope; this forces gamescope-in-gamescope.
+    #[arg(long)]
+    pub force_nested: bool,
+
+    /// Show the resolved command and ask before executing
+    ///
+    /// Prompts 'Proceed? [y/N]' and aborts on anything but yes.
+    /// Requires an interactive terminal.
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Print the launch as one runnable shell line instead of executing
+    ///
+    /// Emits only 'env -u ... VAR=val ... gamescope ... -- <command>',
+    /// quoted so it can be pasted into a terminal to reproduce the launch.
+    #[arg(long, conflicts_with = "confirm")]
+    pub echo_only: bool,
+
+    /// Print only the child command, as gamescope would run it after '--'
+    ///
+    /// For launchers that start gamescope themselves: the quoted line
+    /// includes the HDR workaround prefix and any child-side wrapper, but
+    /// neither the gamescope binary, its arguments, nor the environment.
+    #[arg(
+        long,
+        conflicts_with_all = ["confirm", "echo_only", "no_child", "notify", "diag_bundle", "detach"]
+    )]
+    pub print_child_only: bool,
+
+    /// Show the launch and check it without executing anything
+    ///
+    /// Prints the usual profile, environment and command, then checks that
+    /// the child command (and gamescope) resolve to executables in PATH,
+    /// reporting their full paths. Exits with an error if one doesn't.
+    #[arg(
+        long,
+        conflicts_with_all = ["confirm", "echo_only", "print_child_only", "detach", "watch"]
+    )]
+    pub dry_run: bool,
+
+    /// Run gamescope windowed for this session
+    ///
+    /// Forces 'windowMode: windowed' (neither fullscreen nor borderless)
+    /// regardless of the profile.
+    #[arg(short, long)]
+    pub windowed: bool,
+
+    /// Start gamescope without a child command
+    ///
+    /// Runs gamescope as a bare compositor so apps can be launched
+    /// into it manually. Cannot be combined with a command.
+    #[arg(long, conflicts_with_all = ["command", "shell"])]
+    pub no_child: bool,
+
+    /// Run the child as a shell string via 'sh -c'
+    ///
+    /// Enables shell features like '&&' and pipes, e.g.
+    /// --shell "steam -gamepadui && echo done". Cannot be combined
+    /// with a trailing command.
+    #[arg(long, value_name = "STRING", conflicts_with = "command")]
+    pub shell: Option<String>,
+
+    /// Silence the child command's stdout/stderr
+    ///
+    /// Overrides the profile's 'quietChild' setting. Gamescope's own
+    /// output is kept; the child's exit code is unaffected.
+    #[arg(short, long)]
+    pub quiet_child: bool,
+
+    /// Send a desktop notification when the session exits
+    ///
+    /// Overrides the profile's 'notifyOnExit' setting. wayscope then spawns
+    /// and waits for the session instead of replacing itself via exec,
+    /// staying resident for its duration. The notification names the
+    /// profile, how it exited and the (shortened) command. Requires
+    /// notify-send.
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Log this launch to the session log ('wayscope sessions')
+    ///
+    /// Like the profile's 'logSessions'. The start is always logged; the
+    /// end only when wayscope waits for the session (--notify,
+    /// --diag-bundle, --watch) instead of replacing itself via exec.
+    #[arg(long)]
+    pub session_log: bool,
+
+    /// Run the session in its own transient systemd user scope
+    ///
+    /// Prefixes the launch with 'systemd-run --user --scope' (plus the
+    /// profile's 'scopeProperties'), like the profile's 'systemdScope'.
+    #[arg(long)]
+    pub systemd_scope: bool,
+
+    /// Leave a group of wayscope's base variables out
+    ///
+    /// Adds to the profile's 'disableBaseEnv'. Repeat the flag or separate
+    /// groups with commas, e.g. '--no-base-env amd,proton'.
+    #[arg(long, value_name = "GROUP", value_delimiter = ',')]
+    pub no_base_env: Vec<BaseEnvGroup>,
+
+    /// Write a diagnostic bundle into DIR if the launch fails
+    ///
+    /// Captures the resolved profile, full environment, command line,
+    /// versions, and config files for bug reports. Values of variables
+    /// named like *TOKEN*, *KEY*, *SECRET* or *PASSWORD* are redacted, but
+    /// the copied config files are not. Like --notify, wayscope then spawns
+    /// and waits for the session instead of using exec.
+    #[arg(long, value_name = "DIR", conflicts_with = "echo_only")]
+    pub diag_bundle: Option<PathBuf>,
+
+    /// Start the session in the background and return immediately
+    ///
+    /// Instead of replacing wayscope via exec, the launch is started in a
+    /// session of its own (setsid) so it survives the terminal closing, and
+    /// wayscope prints its PID and exits. stdin is /dev/null; output goes
+    /// to --log, or is discarded.
+    #[arg(long, conflicts_with_all = ["echo_only", "notify", "diag_bundle"])]
+    pub detach: bool,
+
+    /// Relaunch the session whenever the config changes it
+    ///
+    /// For tuning options: wayscope stays resident, watches the config
+    /// files, and when a change alters the resolved launch, stops the
+    /// session and starts it again. Ctrl-C stops the session and exits.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "last", "from_resolved", "confirm", "echo_only", "print_child_only",
+            "notify", "diag_bundle", "detach",
+        ]
+    )]
+    pub watch: bool,
+
+    /// Choose the monitor for this run from a list
+    ///
+    /// Overrides the profile's monitor (and the primary) for this launch.
+    /// Without it, the list is only offered when the profile names no
+    /// monitor and none is primary, and only on a terminal; otherwise
+    /// that's an error.
+    #[arg(long, conflicts_with_all = ["last", "from_resolved", "watch"])]
+    pub pick_monitor: bool,
+
+    /// Append the detached session's stdout/stderr to FILE
+    #[arg(long, value_name = "FILE", requires = "detach")]
+    pub log: Option<PathBuf>,
+
     /// Command to run inside gamescope
     ///
     /// This is typically a game launcher like 'steam' or 'heroic'.
     /// All arguments after the command are passed through.
-    #[arg(required = true, trailing_var_arg = true)]
+    #[arg(required_unless_present_any = ["no_child", "shell", "last"], trailing_var_arg = true)]
     pub command: Vec<String>,
 }
 
+impl RunArgs {
+    /// The CLI's skip-gamescope choice, if either flag was given (last wins).
+    pub fn skip_gamescope_override(&self) -> Option<bool> {
+        forced(self.skip_gamescope, self.no_skip_gamescope)
+    }
+
+    /// `Some(true)` for `--hdr`, `Some(false)` for `--no-hdr`.
+    pub fn hdr_override(&self) -> Option<bool> {
+        forced(self.hdr, self.no_hdr)
+    }
+
+    /// `Some(true)` for `--wsi`, `Some(false)` for `--no-wsi`.
+    pub fn wsi_override(&self) -> Option<bool> {
+        forced(self.wsi, self.no_wsi)
+    }
+
+    /// The child argv: `sh -c <string>` for `--shell`, else the trailing command.
+    pub fn child_command(&self) -> Vec<String> {
+        match &self.shell {
+            Some(script) => vec!["sh".to_string(), "-c".to_string(), script.clone()],
+            None => self.command.clone(),
+        }
+    }
+}
+
+/// Collapses an `--x`/`--no-x` flag pair into the forced value, if any.
+fn forced(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,7 +665,7 @@ mod tests {
         let cli = Cli::try_parse_from(["wayscope", "run", "steam"]).unwrap();
         match cli.command {
             Commands::Run(args) => {
-                assert_eq!(args.profile, "default");
+                assert_eq!(args.profile, None);
                 assert_eq!(args.command, vec!["steam"]);
             }
             _ => panic!("Expected Run command"),
@@ -124,7 +677,7 @@ mod tests {
         let cli = Cli::try_parse_from(["wayscope", "run", "-p", "autohdr", "heroic"]).unwrap();
         match cli.command {
             Commands::Run(args) => {
-                assert_eq!(args.profile, "autohdr");
+                assert_eq!(args.profile.as_deref(), Some("autohdr"));
                 assert_eq!(args.command, vec!["heroic"]);
             }
             _ => panic!("Expected Run command"),
@@ -143,25 +696,358 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_run_detach() {
+        let cli = Cli::try_parse_from([
+            "wayscope",
+            "run",
+            "--detach",
+            "--log",
+            "/tmp/s.log",
+            "steam",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.detach);
+                assert_eq!(args.log, Some(PathBuf::from("/tmp/s.log")));
+            }
+            _ => panic!("Expected Run command"),
+        }
+        assert!(Cli::try_parse_from(["wayscope", "run", "--log", "/tmp/s.log", "steam"]).is_err());
+        assert!(Cli::try_parse_from(["wayscope", "run", "--detach", "--notify", "steam"]).is_err());
+    }
+
+    #[test]
+    fn test_run_hdr_wsi_overrides() {
+        let run = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Commands::Run(args) => (args.hdr_override(), args.wsi_override()),
+            _ => panic!("Expected Run command"),
+        };
+        assert_eq!(run(&["wayscope", "run", "steam"]), (None, None));
+        assert_eq!(
+            run(&["wayscope", "run", "--hdr", "--no-wsi", "steam"]),
+            (Some(true), Some(false))
+        );
+        assert_eq!(
+            run(&["wayscope", "run", "--no-hdr", "--wsi", "steam"]),
+            (Some(false), Some(true))
+        );
+        assert!(Cli::try_parse_from(["wayscope", "run", "--hdr", "--no-hdr", "steam"]).is_err());
+        assert!(Cli::try_parse_from(["wayscope", "run", "--wsi", "--no-wsi", "steam"]).is_err());
+    }
+
     #[test]
     fn test_list_command() {
         let cli = Cli::try_parse_from(["wayscope", "list"]).unwrap();
-        assert!(matches!(cli.command, Commands::List));
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                skip_broken: false,
+                flat: false
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["wayscope", "ls", "--skip-broken"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::List {
+                skip_broken: true,
+                ..
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["wayscope", "list", "--flat"]).unwrap();
+        assert!(matches!(cli.command, Commands::List { flat: true, .. }));
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr", "--flat"]).unwrap();
+        assert!(matches!(cli.command, Commands::Show { flat: true, .. }));
+        assert!(Cli::try_parse_from(["wayscope", "show", "hdr", "--flat", "--command"]).is_err());
+    }
+
+    #[test]
+    fn test_show_dump_resolved_conflicts() {
+        for flag in [
+            "--explain-workaround",
+            "--env-inline",
+            "--print-unset",
+            "--group",
+            "--overrides-only",
+            "--command",
+            "--explain-order",
+            "--flat",
+        ] {
+            let args = ["wayscope", "show", "hdr", "--dump-resolved", flag];
+            assert!(Cli::try_parse_from(args).is_err(), "{}", flag);
+        }
     }
 
     #[test]
     fn test_show_command() {
         let cli = Cli::try_parse_from(["wayscope", "show", "autohdr"]).unwrap();
         match cli.command {
-            Commands::Show { profile } => assert_eq!(profile, "autohdr"),
+            Commands::Show {
+                profile,
+                explain_workaround,
+                env_inline,
+                print_unset,
+                group,
+                overrides_only,
+                dump_resolved,
+                command,
+                with_env,
+                explain_order,
+                flat,
+            } => {
+                assert_eq!(profile, "autohdr");
+                assert!(!explain_workaround);
+                assert!(!env_inline);
+                assert!(!print_unset);
+                assert!(!group);
+                assert!(!overrides_only);
+                assert!(!dump_resolved);
+                assert!(!command);
+                assert!(!with_env);
+                assert!(!explain_order);
+                assert!(!flat);
+            }
+            _ => panic!("Expected Show command"),
+        }
+    }
+
+    #[test]
+    fn test_get_command() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "get", "default", "options.output-width"]).unwrap();
+        match cli.command {
+            Commands::Get { profile, key } => {
+                assert_eq!(profile, "default");
+                assert_eq!(key, "options.output-width");
+            }
+            _ => panic!("Expected Get command"),
+        }
+    }
+
+    #[test]
+    fn test_show_explain_order() {
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr", "--explain-order"]).unwrap();
+        match cli.command {
+            Commands::Show { explain_order, .. } => assert!(explain_order),
             _ => panic!("Expected Show command"),
         }
+        assert!(
+            Cli::try_parse_from(["wayscope", "show", "hdr", "--explain-order", "--command"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_show_explain_workaround() {
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr", "--explain-workaround"]).unwrap();
+        match cli.command {
+            Commands::Show {
+                explain_workaround, ..
+            } => assert!(explain_workaround),
+            _ => panic!("Expected Show command"),
+        }
+    }
+
+    #[test]
+    fn test_show_env_inline() {
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr", "--env-inline"]).unwrap();
+        match cli.command {
+            Commands::Show { env_inline, .. } => assert!(env_inline),
+            _ => panic!("Expected Show command"),
+        }
+    }
+
+    #[test]
+    fn test_show_command_with_env() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "show", "hdr", "--command", "--with-env"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Show {
+                command: true,
+                with_env: true,
+                ..
+            }
+        ));
+        assert!(Cli::try_parse_from(["wayscope", "show", "hdr", "--with-env"]).is_err());
+    }
+
+    #[test]
+    fn test_prune_command() {
+        let cli = Cli::try_parse_from(["wayscope", "prune", "--yes"]).unwrap();
+        assert!(matches!(cli.command, Commands::Prune { yes: true }));
+    }
+
+    #[test]
+    fn test_reset_command() {
+        let cli = Cli::try_parse_from(["wayscope", "reset"]).unwrap();
+        assert!(matches!(cli.command, Commands::Reset { yes: false }));
+        let cli = Cli::try_parse_from(["wayscope", "reset", "-y"]).unwrap();
+        assert!(matches!(cli.command, Commands::Reset { yes: true }));
+    }
+
+    #[test]
+    fn test_export_script_command() {
+        let cli = Cli::try_parse_from([
+            "wayscope",
+            "export-script",
+            "hdr",
+            "-o",
+            "hdr.sh",
+            "--",
+            "steam",
+            "-bigpicture",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::ExportScript {
+                profile,
+                output,
+                force,
+                command,
+            } => {
+                assert_eq!(profile, "hdr");
+                assert_eq!(output, Some(PathBuf::from("hdr.sh")));
+                assert!(!force);
+                assert_eq!(command, vec!["steam", "-bigpicture"]);
+            }
+            _ => panic!("expected export-script"),
+        }
+        // The command must follow '--'
+        assert!(Cli::try_parse_from(["wayscope", "export-script", "hdr", "steam"]).is_err());
+    }
+
+    #[test]
+    fn test_import_command() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "import", "friend.yaml", "hdr", "couch"]).unwrap();
+        match cli.command {
+            Commands::Import {
+                file,
+                profiles,
+                force,
+                rename,
+            } => {
+                assert_eq!(file, PathBuf::from("friend.yaml"));
+                assert_eq!(profiles, vec!["hdr", "couch"]);
+                assert!(!force && !rename);
+            }
+            _ => panic!("Expected Import command"),
+        }
+        assert!(
+            Cli::try_parse_from(["wayscope", "import", "f.yaml", "--force", "--rename"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sessions_command() {
+        let cli = Cli::try_parse_from(["wayscope", "sessions"]).unwrap();
+        assert!(matches!(cli.command, Commands::Sessions { count: 20 }));
+        let cli = Cli::try_parse_from(["wayscope", "sessions", "-n", "5"]).unwrap();
+        assert!(matches!(cli.command, Commands::Sessions { count: 5 }));
+
+        let cli = Cli::try_parse_from(["wayscope", "run", "--session-log", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.session_log),
+            _ => panic!("Expected Run command"),
+        }
     }
 
     #[test]
     fn test_monitors_command() {
         let cli = Cli::try_parse_from(["wayscope", "monitors"]).unwrap();
-        assert!(matches!(cli.command, Commands::Monitors));
+        assert!(matches!(
+            cli.command,
+            Commands::Monitors {
+                diff: None,
+                unused: false,
+                dump_defaults: None,
+                changed: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_monitors_changed() {
+        for flag in ["--changed", "--dump-diff-from-defaults"] {
+            let cli = Cli::try_parse_from(["wayscope", "monitors", flag]).unwrap();
+            assert!(matches!(
+                cli.command,
+                Commands::Monitors { changed: true, .. }
+            ));
+        }
+        assert!(Cli::try_parse_from(["wayscope", "monitors", "--changed", "--unused"]).is_err());
+    }
+
+    #[test]
+    fn test_monitors_dump_defaults() {
+        let cli = Cli::try_parse_from(["wayscope", "monitors", "--dump-defaults", "main"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Monitors { dump_defaults: Some(ref name), .. } if name == "main"
+        ));
+        assert!(Cli::try_parse_from([
+            "wayscope",
+            "monitors",
+            "--unused",
+            "--dump-defaults",
+            "main"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_monitors_diff() {
+        let cli = Cli::try_parse_from(["wayscope", "monitors", "--diff", "main", "tv"]).unwrap();
+        match cli.command {
+            Commands::Monitors { diff, .. } => {
+                assert_eq!(diff, Some(vec!["main".to_string(), "tv".to_string()]))
+            }
+            _ => panic!("Expected Monitors command"),
+        }
+        assert!(Cli::try_parse_from(["wayscope", "monitors", "--diff", "main"]).is_err());
+    }
+
+    #[test]
+    fn test_monitors_unused() {
+        for flag in ["--unused", "--list-unused-monitors"] {
+            let cli = Cli::try_parse_from(["wayscope", "monitors", flag]).unwrap();
+            assert!(matches!(
+                cli.command,
+                Commands::Monitors { unused: true, .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_version_command() {
+        let cli = Cli::try_parse_from(["wayscope", "version", "--verbose"]).unwrap();
+        assert!(matches!(cli.command, Commands::Version { verbose: true }));
+    }
+
+    #[test]
+    fn test_detect_command() {
+        let cli = Cli::try_parse_from(["wayscope", "detect"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Detect {
+                output: None,
+                yaml: false
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["wayscope", "detect", "DP-1"]).unwrap();
+        match cli.command {
+            Commands::Detect { output, .. } => assert_eq!(output.as_deref(), Some("DP-1")),
+            _ => panic!("Expected Detect command"),
+        }
+
+        let cli = Cli::try_parse_from(["wayscope", "detect", "--yaml"]).unwrap();
+        assert!(matches!(cli.command, Commands::Detect { yaml: true, .. }));
+        assert!(Cli::try_parse_from(["wayscope", "detect", "DP-1", "--yaml"]).is_err());
     }
 
     #[test]
@@ -177,13 +1063,48 @@ mod tests {
         .unwrap();
         assert_eq!(cli.monitors, Some(PathBuf::from("/custom/monitors.yaml")));
         assert_eq!(cli.config, Some(PathBuf::from("/custom/config.yaml")));
+        assert!(!cli.config_relative_to_config_dir);
+    }
+
+    #[test]
+    fn test_config_relative_to_config_dir() {
+        let cli = Cli::try_parse_from([
+            "wayscope",
+            "list",
+            "-c",
+            "gaming.yaml",
+            "--config-relative-to-config-dir",
+        ])
+        .unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("gaming.yaml")));
+        assert!(cli.config_relative_to_config_dir);
+    }
+
+    #[test]
+    fn test_config_path_mode() {
+        let cli = Cli::try_parse_from(["wayscope", "list"]).unwrap();
+        assert_eq!(cli.config_path_mode, ConfigPathMode::First);
+        let cli = Cli::try_parse_from(["wayscope", "list", "--config-path-mode", "merge"]).unwrap();
+        assert_eq!(cli.config_path_mode, ConfigPathMode::Merge);
+        assert!(Cli::try_parse_from(["wayscope", "list", "--config-path-mode", "all"]).is_err());
+    }
+
+    #[test]
+    fn test_local_config_flag() {
+        let cli = Cli::try_parse_from(["wayscope", "list"]).unwrap();
+        assert!(!cli.local_config);
+        let cli = Cli::try_parse_from(["wayscope", "list", "--local-config"]).unwrap();
+        assert!(cli.local_config);
     }
 
     #[test]
     fn test_init_command() {
         let cli = Cli::try_parse_from(["wayscope", "init"]).unwrap();
         match cli.command {
-            Commands::Init { force } => assert!(!force),
+            Commands::Init { force, preview } => {
+                assert!(!force);
+                assert!(!preview);
+            }
             _ => panic!("Expected Init command"),
         }
     }
@@ -192,11 +1113,22 @@ mod tests {
     fn test_init_command_force() {
         let cli = Cli::try_parse_from(["wayscope", "init", "--force"]).unwrap();
         match cli.command {
-            Commands::Init { force } => assert!(force),
+            Commands::Init { force, .. } => assert!(force),
             _ => panic!("Expected Init command"),
         }
     }
 
+    #[test]
+    fn test_init_command_preview() {
+        for flag in ["--preview", "--stdout"] {
+            let cli = Cli::try_parse_from(["wayscope", "init", flag]).unwrap();
+            match cli.command {
+                Commands::Init { preview, .. } => assert!(preview),
+                _ => panic!("Expected Init command"),
+            }
+        }
+    }
+
     #[test]
     fn test_run_with_skip_gamescope_short() {
         let cli = Cli::try_parse_from(["wayscope", "run", "-s", "bash"]).unwrap();
@@ -227,7 +1159,7 @@ mod tests {
             .unwrap();
         match cli.command {
             Commands::Run(args) => {
-                assert_eq!(args.profile, "hdr");
+                assert_eq!(args.profile.as_deref(), Some("hdr"));
                 assert!(args.skip_gamescope);
                 assert_eq!(args.command, vec!["env"]);
             }
@@ -235,6 +1167,265 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_run_with_confirm() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--confirm", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.confirm);
+                assert_eq!(args.command, vec!["steam"]);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_windowed() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "-w", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.windowed),
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_last() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--last", "-w"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.last);
+                assert!(args.windowed);
+            }
+            _ => panic!("Expected Run command"),
+        }
+
+        assert!(Cli::try_parse_from(["wayscope", "run", "--last", "steam"]).is_err());
+        assert!(Cli::try_parse_from(["wayscope", "run", "--last", "-p", "hdr"]).is_err());
+    }
+
+    #[test]
+    fn test_run_with_no_child() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--no-child"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.no_child);
+                assert!(args.command.is_empty());
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_no_child_conflicts_with_command() {
+        assert!(Cli::try_parse_from(["wayscope", "run", "--no-child", "steam"]).is_err());
+        assert!(Cli::try_parse_from(["wayscope", "run"]).is_err());
+    }
+
+    #[test]
+    fn test_run_with_quiet_child() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "-q", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.quiet_child),
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_echo_only_conflicts_with_confirm() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--echo-only", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.echo_only),
+            _ => panic!("Expected Run command"),
+        }
+        assert!(
+            Cli::try_parse_from(["wayscope", "run", "--echo-only", "--confirm", "steam"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_run_systemd_scope() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(!args.systemd_scope),
+            _ => panic!("Expected Run command"),
+        }
+        let cli = Cli::try_parse_from(["wayscope", "run", "--systemd-scope", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.systemd_scope);
+                assert_eq!(args.command, vec!["steam"]);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_no_base_env() {
+        let cli = Cli::try_parse_from([
+            "wayscope",
+            "run",
+            "--no-base-env",
+            "amd,proton",
+            "--no-base-env",
+            "wayland",
+            "steam",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Run(args) => assert_eq!(
+                args.no_base_env,
+                vec![
+                    BaseEnvGroup::Amd,
+                    BaseEnvGroup::Proton,
+                    BaseEnvGroup::Wayland
+                ]
+            ),
+            _ => panic!("Expected Run command"),
+        }
+        assert!(
+            Cli::try_parse_from(["wayscope", "run", "--no-base-env", "nvidia", "steam"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_run_dry_run() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--dry-run", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.dry_run),
+            _ => panic!("Expected Run command"),
+        }
+        for other in ["--confirm", "--echo-only", "--detach", "--watch"] {
+            assert!(
+                Cli::try_parse_from(["wayscope", "run", "--dry-run", other, "steam"]).is_err(),
+                "{}",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_pick_monitor() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--pick-monitor", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.pick_monitor),
+            _ => panic!("Expected Run command"),
+        }
+        for other in ["--last", "--watch"] {
+            assert!(
+                Cli::try_parse_from(["wayscope", "run", "--pick-monitor", other, "steam"]).is_err(),
+                "{}",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_watch() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "run", "--watch", "-p", "hdr", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.watch),
+            _ => panic!("Expected Run command"),
+        }
+        for other in ["--last", "--echo-only", "--detach", "--notify"] {
+            assert!(
+                Cli::try_parse_from(["wayscope", "run", "--watch", other, "steam"]).is_err(),
+                "{}",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_print_child_only() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--print-child-only", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.print_child_only),
+            _ => panic!("Expected Run command"),
+        }
+        for other in ["--echo-only", "--no-child", "--detach"] {
+            assert!(
+                Cli::try_parse_from(["wayscope", "run", "--print-child-only", other, "steam"])
+                    .is_err(),
+                "{}",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_flag() {
+        let cli = Cli::try_parse_from(["wayscope", "list"]).unwrap();
+        assert_eq!(cli.color, ColorWhen::Auto);
+        let cli = Cli::try_parse_from(["wayscope", "list", "--color", "always"]).unwrap();
+        assert_eq!(cli.color, ColorWhen::Always);
+        assert!(Cli::try_parse_from(["wayscope", "--color", "sometimes", "list"]).is_err());
+    }
+
+    #[test]
+    fn test_strict_is_global() {
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr", "--strict"]).unwrap();
+        assert!(cli.strict);
+    }
+
+    #[test]
+    fn test_trace_is_global() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "show", "hdr", "--trace", "/tmp/trace.log"]).unwrap();
+        assert_eq!(cli.trace, Some(PathBuf::from("/tmp/trace.log")));
+        let cli = Cli::try_parse_from(["wayscope", "show", "hdr"]).unwrap();
+        assert_eq!(cli.trace, None);
+    }
+
+    #[test]
+    fn test_run_with_notify() {
+        let cli = Cli::try_parse_from(["wayscope", "run", "--notify", "steam"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => assert!(args.notify),
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_shell_string() {
+        let cli =
+            Cli::try_parse_from(["wayscope", "run", "--shell", "steam && echo done"]).unwrap();
+        match cli.command {
+            Commands::Run(args) => {
+                assert!(args.command.is_empty());
+                assert_eq!(args.child_command(), vec!["sh", "-c", "steam && echo done"]);
+            }
+            _ => panic!("Expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_shell_conflicts_with_command() {
+        assert!(Cli::try_parse_from(["wayscope", "run", "--shell", "steam", "heroic"]).is_err());
+        assert!(
+            Cli::try_parse_from(["wayscope", "run", "--shell", "steam", "--no-child"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_skip_gamescope_override() {
+        let parse = |args: &[&str]| match Cli::try_parse_from(args).unwrap().command {
+            Commands::Run(args) => args.skip_gamescope_override(),
+            _ => panic!("Expected Run command"),
+        };
+        assert_eq!(parse(&["wayscope", "run", "steam"]), None);
+        assert_eq!(parse(&["wayscope", "run", "-s", "steam"]), Some(true));
+        assert_eq!(
+            parse(&["wayscope", "run", "--no-skip-gamescope", "steam"]),
+            Some(false)
+        );
+        // The later flag wins
+        assert_eq!(
+            parse(&["wayscope", "run", "-s", "--no-skip-gamescope", "steam"]),
+            Some(false)
+        );
+    }
+
     #[test]
     fn test_skip_gamescope_defaults_to_false() {
         let cli = Cli::try_parse_from(["wayscope", "run", "bash"]).unwrap();
@@ -2,15 +2,21 @@
 //!
 //! Constructs the gamescope command line from a resolved profile,
 //! including all options, HDR flags, and environment variables.
-//! Uses `exec` to replace the current process with gamescope.
+//! Uses `exec` to replace the current process with gamescope, spawns and
+//! waits when an exit notification is requested, or spawns without waiting
+//! for `run --detach`.
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
 use std::os::unix::process::CommandExt;
-use std::process::Command;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
 
 use anyhow::{Context, Result};
 
-use crate::config::OptionValue;
-use crate::profile::ResolvedProfile;
+use crate::config::{OptionValue, RlimitValue, WrapperTarget};
+use crate::profile::{ResolvedProfile, SESSION_DIRECT, SESSION_GAMESCOPE};
 
 #[derive(Debug)]
 pub struct GamescopeCommand {
@@ -20,29 +26,256 @@ pub struct GamescopeCommand {
     /// Environment variable names to remove from inherited parent environment.
     pub unset: Vec<String>,
     pub child: Vec<String>,
+    /// `gameUnset`: names removed only for the child, via `env -u`.
+    pub game_unset: Vec<String>,
+    /// `gameEnv` as `KEY=value`: set only for the child, via `env`.
+    pub game_env: Vec<String>,
     pub needs_workaround: bool,
+    /// Redirect the child's stdout/stderr to /dev/null (gamescope's own output is kept).
+    pub quiet_child: bool,
+    /// Wrapper command (e.g. `gamemoderun`) and which side of `--` it goes on.
+    pub wrapper: Vec<String>,
+    pub wrapper_target: WrapperTarget,
+    /// `systemd-run --user --scope ...` prefix; empty without `systemdScope`.
+    pub scope: Vec<String>,
+}
+
+/// Shell wrapper that silences the child while `exec`-ing it, so the child's
+/// exit code still propagates through gamescope without a spawn/wait layer.
+const QUIET_WRAPPER: [&str; 4] = ["sh", "-c", "exec \"$@\" >/dev/null 2>&1", "sh"];
+
+/// A launch split into its ordered parts:
+///
+/// `[scope] [wrapper] binary args -- [quiet] [env] [wrapper] child`
+///
+/// The systemd scope, if any, is outermost so the whole session shares one
+/// cgroup. The wrapper lands on exactly one side of `--`, per its target. The
+/// child's `env` (the workaround variable, `gameUnset` and `gameEnv`) sits
+/// outside a child-side wrapper so the wrapper inherits it too. A bare
+/// compositor (no child) gets no separator and nothing after it.
+struct Segments<'a> {
+    scope: &'a [String],
+    wrapper: &'a [String],
+    wrapper_target: WrapperTarget,
+    binary: &'a str,
+    args: &'a [String],
+    quiet_child: bool,
+    workaround: bool,
+    game_unset: &'a [String],
+    game_env: &'a [String],
+    child: &'a [String],
+}
+
+impl<'a> Segments<'a> {
+    /// Everything after `--`; empty for a bare compositor.
+    fn child_side(&self) -> Vec<&'a str> {
+        if self.child.is_empty() {
+            return Vec::new();
+        }
+
+        let mut argv = Vec::with_capacity(self.child.len() + self.wrapper.len() + 6);
+        if self.quiet_child {
+            argv.extend(QUIET_WRAPPER);
+        }
+        if self.workaround || !self.game_unset.is_empty() || !self.game_env.is_empty() {
+            argv.push("env");
+            // `env` wants its -u options first; gameEnv comes last so it wins
+            for name in self.game_unset {
+                argv.extend(["-u", name.as_str()]);
+            }
+            if self.workaround {
+                argv.push("DISABLE_HDR_WSI=1");
+            }
+            argv.extend(self.game_env.iter().map(String::as_str));
+        }
+        if self.wrapper_target == WrapperTarget::Child {
+            argv.extend(self.wrapper.iter().map(String::as_str));
+        }
+        argv.extend(self.child.iter().map(String::as_str));
+        argv
+    }
+
+    fn assemble(&self) -> Vec<&'a str> {
+        let mut argv = Vec::with_capacity(self.args.len() + self.child.len() + 8);
+        argv.extend(self.scope.iter().map(String::as_str));
+        if self.wrapper_target == WrapperTarget::Gamescope {
+            argv.extend(self.wrapper.iter().map(String::as_str));
+        }
+        argv.push(self.binary);
+        argv.extend(self.args.iter().map(String::as_str));
+
+        let child_side = self.child_side();
+        if !child_side.is_empty() {
+            argv.push("--");
+            argv.extend(child_side);
+        }
+        argv
+    }
 }
 
 impl GamescopeCommand {
+    fn segments(&self) -> Segments<'_> {
+        Segments {
+            scope: &self.scope,
+            wrapper: &self.wrapper,
+            wrapper_target: self.wrapper_target,
+            binary: &self.binary,
+            args: &self.args,
+            quiet_child: self.quiet_child,
+            workaround: self.needs_workaround,
+            game_unset: &self.game_unset,
+            game_env: &self.game_env,
+            child: &self.child,
+        }
+    }
+
+    /// Full argv: wrapper (if it wraps gamescope), binary, gamescope args,
+    /// then `--` and the child if any.
+    fn argv(&self) -> Vec<&str> {
+        self.segments().assemble()
+    }
+
     /// Formats the command for display (e.g., logging or dry-run output).
     pub fn display(&self) -> String {
-        // Simple implementation: this runs once per execution, not in a hot path.
-        // Using format! and join is clearer than manual capacity pre-allocation.
-        let args_str = self.args.join(" ");
-        let child_str = self.child.join(" ");
-        let workaround = if self.needs_workaround {
-            " env DISABLE_HDR_WSI=1"
-        } else {
-            ""
-        };
+        quote_join(self.argv())
+    }
 
-        format!(
-            "{} {} --{} {}",
-            self.binary, args_str, workaround, child_str
-        )
+    /// Only what follows `--` (quiet wrapper, workaround prefix, child-side
+    /// wrapper and child), for launchers that start gamescope themselves.
+    pub fn child_line(&self) -> String {
+        quote_join(self.segments().child_side())
+    }
+
+    /// A pasteable line reproducing the launch, environment included.
+    pub fn runnable_line(&self) -> String {
+        runnable_line(&self.env, &self.unset, self.argv())
+    }
+
+    /// Builds the `Command` that launches gamescope with the child after `--`.
+    pub fn to_command(&self) -> Command {
+        let argv = self.argv();
+        let mut command = Command::new(argv[0]);
+
+        apply_env_to_command(&mut command, &self.env, &self.unset);
+        command.args(&argv[1..]);
+
+        command
     }
 }
 
+/// Formats `argv` as `env -u UNSET... VAR=val... argv...`, shell-quoted.
+///
+/// Mirrors `apply_env_to_command`: unset wins over a set variable of the
+/// same name, so such variables are only emitted as `-u`.
+pub fn runnable_line<'a>(
+    env: &'a [(String, String)],
+    unset: &'a [String],
+    argv: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut words: Vec<Cow<'a, str>> = vec![Cow::Borrowed("env")];
+    for name in unset {
+        words.push(Cow::Borrowed("-u"));
+        words.push(shell_quote(name));
+    }
+    for (key, value) in env.iter().filter(|(k, _)| !unset.contains(k)) {
+        words.push(Cow::Owned(
+            shell_quote(&format!("{}={}", key, value)).into_owned(),
+        ));
+    }
+    words.extend(argv.into_iter().map(shell_quote));
+    words.join(" ")
+}
+
+/// Formats `env` as one shell-quoted `KEY=val KEY=val` line.
+pub fn inline_env(env: &[(String, String)]) -> String {
+    let assignments: Vec<String> = env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    quote_join(assignments.iter().map(String::as_str))
+}
+
+/// Shell-quotes each argument and joins them with spaces.
+pub fn quote_join<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    args.into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quotes an argument for POSIX shells so displayed commands can be pasted back.
+pub fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
+/// Category for grouping gamescope options in `show --group`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptionGroup {
+    Resolution,
+    Performance,
+    Hdr,
+    Input,
+    Misc,
+}
+
+impl OptionGroup {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Resolution => "Resolution",
+            Self::Performance => "Performance",
+            Self::Hdr => "HDR",
+            Self::Input => "Input",
+            Self::Misc => "Misc",
+        }
+    }
+}
+
+/// Known gamescope flags by category; anything unlisted is `Misc`.
+const OPTION_GROUPS: &[(&str, OptionGroup)] = &[
+    ("output-width", OptionGroup::Resolution),
+    ("output-height", OptionGroup::Resolution),
+    ("nested-width", OptionGroup::Resolution),
+    ("nested-height", OptionGroup::Resolution),
+    ("max-scale", OptionGroup::Resolution),
+    ("scaler", OptionGroup::Resolution),
+    ("filter", OptionGroup::Resolution),
+    ("sharpness", OptionGroup::Resolution),
+    ("fullscreen", OptionGroup::Resolution),
+    ("borderless", OptionGroup::Resolution),
+    ("force-orientation", OptionGroup::Resolution),
+    ("nested-refresh", OptionGroup::Performance),
+    ("nested-unfocused-refresh", OptionGroup::Performance),
+    ("framerate-limit", OptionGroup::Performance),
+    ("adaptive-sync", OptionGroup::Performance),
+    ("immediate-flips", OptionGroup::Performance),
+    ("rt", OptionGroup::Performance),
+    ("force-composition", OptionGroup::Performance),
+    ("fade-out-duration", OptionGroup::Performance),
+    ("xwayland-count", OptionGroup::Performance),
+    ("sdr-gamut-wideness", OptionGroup::Hdr),
+    ("disable-color-management", OptionGroup::Hdr),
+    ("default-touch-mode", OptionGroup::Input),
+    ("cursor", OptionGroup::Input),
+    ("cursor-scale-height", OptionGroup::Input),
+    ("hide-cursor-delay", OptionGroup::Input),
+    ("force-grab-cursor", OptionGroup::Input),
+    ("grab", OptionGroup::Input),
+    ("mouse-sensitivity", OptionGroup::Input),
+];
+
+/// Classifies a gamescope flag (without `--`) for grouped display.
+pub fn option_group(flag: &str) -> OptionGroup {
+    if flag.starts_with("hdr-") {
+        return OptionGroup::Hdr;
+    }
+    OPTION_GROUPS
+        .iter()
+        .find(|(name, _)| *name == flag)
+        .map_or(OptionGroup::Misc, |(_, group)| *group)
+}
+
 pub fn build(profile: &ResolvedProfile, child_cmd: &[String]) -> GamescopeCommand {
     let mut args = build_args(profile);
 
@@ -56,12 +289,66 @@ pub fn build(profile: &ResolvedProfile, child_cmd: &[String]) -> GamescopeComman
         binary: profile.binary.clone(),
         args,
         env: profile.environment(),
-        unset: profile.unset_vars.clone(),
+        unset: profile.unset_names(),
         child: child_cmd.to_vec(),
+        game_unset: profile.game_unset.clone(),
+        game_env: game_env_args(profile),
         needs_workaround: profile.needs_hdr_workaround(),
+        quiet_child: profile.quiet_child,
+        wrapper: profile.wrapper.clone(),
+        wrapper_target: profile.wrapper_target,
+        scope: scope_argv(profile),
     }
 }
 
+/// The child argv for a launch without gamescope: the scope, the child's
+/// `env` prefix and the wrapper (whatever its target) followed by the child.
+pub fn direct_argv(profile: &ResolvedProfile, child_cmd: &[String]) -> Vec<String> {
+    if child_cmd.is_empty() {
+        return Vec::new();
+    }
+    let mut argv = scope_argv(profile);
+    let game_env = game_env_args(profile);
+    if !profile.game_unset.is_empty() || !game_env.is_empty() {
+        argv.push("env".to_string());
+        for name in &profile.game_unset {
+            argv.extend(["-u".to_string(), name.clone()]);
+        }
+        argv.extend(game_env);
+    }
+    argv.extend(profile.wrapper.iter().chain(child_cmd).cloned());
+    argv
+}
+
+/// `gameEnv` as `env` assignments, sorted by name.
+fn game_env_args(profile: &ResolvedProfile) -> Vec<String> {
+    profile
+        .game_env
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect()
+}
+
+/// Runs the launch in its own transient user scope (cgroup).
+pub const SYSTEMD_RUN: &str = "systemd-run";
+
+/// `systemd-run --user --scope -p Key=Value ...`, or nothing without
+/// `systemdScope`.
+pub fn scope_argv(profile: &ResolvedProfile) -> Vec<String> {
+    if !profile.systemd_scope {
+        return Vec::new();
+    }
+    let mut argv: Vec<String> = [SYSTEMD_RUN, "--user", "--scope"]
+        .map(String::from)
+        .to_vec();
+    for (key, value) in &profile.scope_properties {
+        argv.push("-p".to_string());
+        argv.push(format!("{}={}", key, value));
+    }
+    argv.push("--".to_string());
+    argv
+}
+
 fn build_args(profile: &ResolvedProfile) -> Vec<String> {
     let mut args = Vec::with_capacity(profile.options.len() * 2);
 
@@ -76,6 +363,10 @@ fn build_args(profile: &ResolvedProfile) -> Vec<String> {
                 args.push(format!("--{}", key));
                 args.push(n.to_string());
             }
+            OptionValue::Float(x) => {
+                args.push(format!("--{}", key));
+                args.push(x.to_string());
+            }
             OptionValue::String(s) => {
                 args.push(format!("--{}", key));
                 args.push(s.clone());
@@ -101,21 +392,32 @@ fn apply_env_to_command(command: &mut Command, env: &[(String, String)], unset:
 
 /// Replaces the current process with gamescope (does not return on success).
 pub fn exec(cmd: GamescopeCommand) -> Result<()> {
-    let mut command = Command::new(&cmd.binary);
-
-    apply_env_to_command(&mut command, &cmd.env, &cmd.unset);
+    let err = cmd.to_command().exec();
+    Err(err).context("Failed to execute gamescope")
+}
 
-    command.args(&cmd.args);
-    command.arg("--");
+/// Whether wayscope is already running inside a gamescope session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nesting {
+    None,
+    /// Inside a gamescope session launched by wayscope.
+    Wayscope,
+    /// Inside a gamescope wayscope didn't start (e.g. Steam's gaming mode).
+    Foreign,
+}
 
-    if cmd.needs_workaround {
-        command.args(["env", "DISABLE_HDR_WSI=1"]);
+/// Classifies the current session from the `WAYSCOPE_SESSION` marker and
+/// whether `GAMESCOPE_WAYLAND_DISPLAY` is set.
+///
+/// A `direct` marker means the display variable came from wayscope's own
+/// environment for a skipped-gamescope launch, not from a real session.
+pub fn nesting(marker: Option<&str>, gamescope_display: bool) -> Nesting {
+    match marker {
+        Some(SESSION_GAMESCOPE) => Nesting::Wayscope,
+        Some(SESSION_DIRECT) => Nesting::None,
+        _ if gamescope_display => Nesting::Foreign,
+        _ => Nesting::None,
     }
-
-    command.args(&cmd.child);
-
-    let err = command.exec();
-    Err(err).context("Failed to execute gamescope")
 }
 
 /// Bypass gamescope, run command directly (used when already inside gamescope).
@@ -131,16 +433,17 @@ pub fn exec_direct(child_cmd: &[String]) -> Result<()> {
     Err(err).context("Failed to execute command")
 }
 
-/// Run command directly with profile environment variables applied.
+/// Builds the child command with profile environment variables applied.
 ///
 /// Used when skipping gamescope (via --skip-gamescope flag) while preserving
 /// all profile environment setup (RADV, Wayland, HDR vars, WSI, etc.).
 /// Environment handling is delegated to `apply_env_to_command`.
-pub fn exec_direct_with_env(
+pub fn direct_command(
     child_cmd: &[String],
     env: &[(String, String)],
     unset: &[String],
-) -> Result<()> {
+    quiet: bool,
+) -> Result<Command> {
     if child_cmd.is_empty() {
         anyhow::bail!("No command provided");
     }
@@ -148,14 +451,272 @@ pub fn exec_direct_with_env(
     let mut command = Command::new(&child_cmd[0]);
     apply_env_to_command(&mut command, env, unset);
     command.args(&child_cmd[1..]);
+    if quiet {
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+    }
 
-    let err = command.exec();
+    Ok(command)
+}
+
+/// Run command directly with profile environment variables applied.
+pub fn exec_direct_with_env(
+    child_cmd: &[String],
+    env: &[(String, String)],
+    unset: &[String],
+    quiet: bool,
+) -> Result<()> {
+    let err = direct_command(child_cmd, env, unset, quiet)?.exec();
     Err(err).context("Failed to execute command")
 }
 
+/// Runs `command` as a child and waits for it.
+///
+/// This is the spawn-mode counterpart to `exec`: wayscope stays resident
+/// as the parent so it has a point to act after exit (notify, write a
+/// diagnostic bundle). The cost is an extra process for the whole session,
+/// and if wayscope itself is killed nothing runs after exit.
+pub fn spawn(mut command: Command) -> Result<ExitStatus> {
+    command.status().context("Failed to execute command")
+}
+
+/// Longest name the kernel keeps for `PR_SET_NAME`, without the NUL.
+const PROCESS_NAME_MAX: usize = 15;
+
+/// `wayscope:<profile>`, cut to what the kernel keeps.
+fn process_name(profile: &str) -> String {
+    let mut name = format!("wayscope:{}", profile);
+    let mut end = name.len().min(PROCESS_NAME_MAX);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name.truncate(end);
+    name
+}
+
+/// Renames wayscope to `wayscope:<profile>` while it stays resident in a
+/// spawn mode, so `ps -o comm`, `top` and `pgrep` tell sessions apart. The
+/// command line (`ps aux`) is unchanged, and an exec'd child gets its own name.
+pub fn set_process_name(profile: &str) {
+    // Profile names can't hold a NUL, but if one did, keeping the name is fine
+    let Ok(name) = std::ffi::CString::new(process_name(profile)) else {
+        return;
+    };
+    // SAFETY: PR_SET_NAME reads a NUL-terminated string of at most 16 bytes
+    unsafe {
+        libc::prctl(libc::PR_SET_NAME, name.as_ptr());
+    }
+}
+
+/// Sends SIGTERM to a spawned launch so gamescope can shut down cleanly.
+pub fn terminate(child: &Child) {
+    // A pid from a live `Child` always fits; an exited one makes this a no-op
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill has no memory-safety preconditions
+        unsafe {
+            libc::kill(pid, libc::SIGTERM);
+        }
+    }
+}
+
+#[cfg(target_env = "gnu")]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(target_env = "gnu"))]
+type Resource = libc::c_int;
+
+/// Maps an `rlimits` name (see `config::RLIMIT_NAMES`) to its resource.
+fn rlimit_resource(name: &str) -> Option<Resource> {
+    Some(match name {
+        "as" => libc::RLIMIT_AS,
+        "core" => libc::RLIMIT_CORE,
+        "cpu" => libc::RLIMIT_CPU,
+        "data" => libc::RLIMIT_DATA,
+        "fsize" => libc::RLIMIT_FSIZE,
+        "memlock" => libc::RLIMIT_MEMLOCK,
+        "nice" => libc::RLIMIT_NICE,
+        "nofile" => libc::RLIMIT_NOFILE,
+        "nproc" => libc::RLIMIT_NPROC,
+        "rtprio" => libc::RLIMIT_RTPRIO,
+        "stack" => libc::RLIMIT_STACK,
+        _ => return None,
+    })
+}
+
+fn getrlimit(resource: Resource) -> std::io::Result<libc::rlimit> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `limit` is a valid, writable rlimit for the duration of the call
+    if unsafe { libc::getrlimit(resource, &mut limit) } == 0 {
+        Ok(limit)
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+fn setrlimit(resource: Resource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of the call
+    if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Current (soft, hard) values of an `rlimits` name for this process.
+pub fn current_rlimit(name: &str) -> Option<(RlimitValue, RlimitValue)> {
+    let limit = getrlimit(rlimit_resource(name)?).ok()?;
+    Some((
+        RlimitValue::from_raw(limit.rlim_cur),
+        RlimitValue::from_raw(limit.rlim_max),
+    ))
+}
+
+/// Sets the profile's resource limits on wayscope itself, so gamescope and
+/// the child inherit them through exec/spawn.
+///
+/// The soft limit becomes the requested value, raising the hard limit when
+/// needed. Raising a hard limit takes privileges (CAP_SYS_RESOURCE) or a
+/// higher limit from limits.conf; without them the soft limit is capped at
+/// the hard limit and a warning is returned instead of failing the launch.
+pub fn apply_rlimits(rlimits: &BTreeMap<String, RlimitValue>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (name, value) in rlimits {
+        let Some(resource) = rlimit_resource(name) else {
+            warnings.push(format!("Unknown rlimit '{}', skipping", name));
+            continue;
+        };
+        let requested = match value {
+            RlimitValue::Limit(n) => *n,
+            RlimitValue::Unlimited => libc::RLIM_INFINITY,
+        };
+        let hard = match getrlimit(resource) {
+            Ok(current) => current.rlim_max,
+            Err(e) => {
+                warnings.push(format!("Failed to read rlimit {}: {}", name, e));
+                continue;
+            }
+        };
+
+        let result = match setrlimit(resource, requested, hard.max(requested)) {
+            Err(e) if requested > hard && e.kind() == std::io::ErrorKind::PermissionDenied => {
+                let capped = RlimitValue::from_raw(hard);
+                warnings.push(format!(
+                    "Not permitted to raise the {} hard limit to {}; using {} (raise it in \
+                     limits.conf or run with CAP_SYS_RESOURCE)",
+                    name, value, capped
+                ));
+                setrlimit(resource, hard, hard)
+            }
+            result => result,
+        };
+        if let Err(e) = result {
+            warnings.push(format!("Failed to set rlimit {} to {}: {}", name, value, e));
+        }
+    }
+    warnings
+}
+
+/// Starts `command` in the background and returns its PID without waiting.
+///
+/// The child starts a new session with `setsid`, leaving the terminal's
+/// session, so neither a closing terminal's SIGHUP nor job control reaches
+/// it, and once wayscope exits it is reparented to init, as with a double
+/// fork. stdin reads from /dev/null; stdout and stderr are appended to
+/// `log`, or discarded without one.
+pub fn detach(mut command: Command, log: Option<&Path>) -> Result<u32> {
+    let (stdout, stderr) = match log {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log: {}", path.display()))?;
+            let clone = file
+                .try_clone()
+                .with_context(|| format!("Failed to open log: {}", path.display()))?;
+            (Stdio::from(clone), Stdio::from(file))
+        }
+        None => (Stdio::null(), Stdio::null()),
+    };
+    command.stdin(Stdio::null()).stdout(stdout).stderr(stderr);
+    // SAFETY: setsid is async-signal-safe and touches no memory; a fresh
+    // child is never a group leader, so it can't fail
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let child = command.spawn().context("Failed to execute command")?;
+    Ok(child.id())
+}
+
+/// Longest child command line shown in an exit notification, in characters.
+const NOTIFY_COMMAND_MAX: usize = 80;
+
+/// Sends the desktop notification for a finished session.
+pub fn notify_exit(profile_name: &str, child: &[String], status: ExitStatus) {
+    notify(
+        "wayscope session ended",
+        &exit_body(profile_name, child, status),
+    );
+}
+
+/// `describe_exit`, then the child's command line (cut to
+/// `NOTIFY_COMMAND_MAX`) on its own line when there is one.
+fn exit_body(profile_name: &str, child: &[String], status: ExitStatus) -> String {
+    let summary = describe_exit(profile_name, status);
+    if child.is_empty() {
+        return summary;
+    }
+    let line = quote_join(child.iter().map(String::as_str));
+    if line.chars().count() <= NOTIFY_COMMAND_MAX {
+        return format!("{}\n{}", summary, line);
+    }
+    let cut: String = line.chars().take(NOTIFY_COMMAND_MAX - 1).collect();
+    format!("{}\n{}…", summary, cut)
+}
+
+/// "Profile 'x' exited with code N" / "... was terminated by a signal".
+pub fn describe_exit(profile_name: &str, status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("Profile '{}' exited with code {}", profile_name, code),
+        None => format!("Profile '{}' was terminated by a signal", profile_name),
+    }
+}
+
+/// Sends a desktop notification via `notify-send`; failures only warn.
+fn notify(summary: &str, body: &str) {
+    let sent = Command::new("notify-send")
+        .args(["--app-name=wayscope", summary, body])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !sent {
+        crate::output::warn("Failed to send exit notification (is notify-send installed?)");
+    }
+}
+
+/// Maps an exit status to a shell-style exit code (128 + signal when killed).
+pub fn exit_code(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+
+    status
+        .code()
+        .or_else(|| status.signal().map(|sig| 128 + sig))
+        .unwrap_or(1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{BaseEnvGroup, HookFailure};
+    use crate::profile::BinarySource;
     use std::collections::HashMap;
 
     /// Creates a mock profile with common defaults. Use builder methods to customize.
@@ -164,6 +725,9 @@ mod tests {
         use_wsi: bool,
         binary: String,
         unset_vars: Vec<String>,
+        quiet_child: bool,
+        game_env: BTreeMap<String, String>,
+        game_unset: Vec<String>,
     }
 
     impl MockProfile {
@@ -173,6 +737,9 @@ mod tests {
                 use_wsi: false,
                 binary: "gamescope".to_string(),
                 unset_vars: Vec::new(),
+                quiet_child: false,
+                game_env: BTreeMap::new(),
+                game_unset: Vec::new(),
             }
         }
 
@@ -191,11 +758,25 @@ mod tests {
             self
         }
 
+        fn with_quiet_child(mut self, quiet_child: bool) -> Self {
+            self.quiet_child = quiet_child;
+            self
+        }
+
         fn with_unset(mut self, unset_vars: Vec<String>) -> Self {
             self.unset_vars = unset_vars;
             self
         }
 
+        fn with_game_env(mut self, env: &[(&str, &str)], unset: &[&str]) -> Self {
+            self.game_env = env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            self.game_unset = unset.iter().map(|s| s.to_string()).collect();
+            self
+        }
+
         fn build(self) -> ResolvedProfile {
             let mut options = HashMap::new();
             options.insert(
@@ -209,11 +790,43 @@ mod tests {
                 name: "test".to_string(),
                 monitor_name: "main".to_string(),
                 binary: self.binary,
+                binary_source: BinarySource::Profile,
                 use_hdr: self.use_hdr,
                 use_wsi: self.use_wsi,
+                allow_hdr_without_wsi: false,
+                immediate_flips_with_vrr: None,
+                rotation: None,
+                disable_color_management: false,
+                quiet_child: self.quiet_child,
+                notify_on_exit: false,
+                log_sessions: false,
+                skip_gamescope: false,
+                locale: None,
+                timezone: None,
+                gamescope_log: None,
+                pre_hook: None,
+                post_hook: None,
+                pre_timeout: None,
+                post_timeout: None,
+                hook_failure: HookFailure::Abort,
+                supersample: None,
+                proton_version: None,
+                compat_data_path: None,
+                compat_client_install_path: None,
+                icc_profile: None,
+                rlimits: Default::default(),
+                wrapper: Vec::new(),
+                wrapper_target: WrapperTarget::Gamescope,
+                env_priority: Default::default(),
+                base_env: BaseEnvGroup::ALL.into_iter().collect(),
+                systemd_scope: false,
+                scope_properties: Default::default(),
+                config_warnings: Vec::new(),
                 options,
                 user_env: HashMap::new(),
                 unset_vars: self.unset_vars,
+                game_env: self.game_env,
+                game_unset: self.game_unset,
             }
         }
     }
@@ -230,6 +843,56 @@ mod tests {
         assert!(!cmd.needs_workaround);
     }
 
+    #[test]
+    fn test_fullscreen_false_omits_flag() {
+        use crate::config::{Config, MonitorsConfig, ProfilesConfig};
+
+        let monitors: MonitorsConfig = serde_yaml::from_str(
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        )
+        .unwrap();
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  windowed:\n    options:\n      fullscreen: false")
+                .unwrap();
+        let config = Config { monitors, profiles };
+
+        let profile = config.resolve_profile("windowed").unwrap();
+        let cmd = build(&profile, &["steam".to_string()]);
+
+        assert!(!cmd.args.contains(&"--fullscreen".to_string()));
+        assert!(!cmd.args.contains(&"false".to_string()));
+    }
+
+    #[test]
+    fn test_window_mode_flags() {
+        use crate::config::{Config, MonitorsConfig, ProfilesConfig};
+
+        let monitors: MonitorsConfig = serde_yaml::from_str(
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        )
+        .unwrap();
+        for (mode, fullscreen, borderless) in [
+            ("fullscreen", true, false),
+            ("borderless", false, true),
+            ("windowed", false, false),
+        ] {
+            // A contradicting raw option is overridden by the mode
+            let profiles: ProfilesConfig = serde_yaml::from_str(&format!(
+                "profiles:\n  p:\n    windowMode: {}\n    options: {{borderless: true}}",
+                mode
+            ))
+            .unwrap();
+            let config = Config {
+                monitors: monitors.clone(),
+                profiles,
+            };
+            let cmd = build(&config.resolve_profile("p").unwrap(), &[]);
+            let has = |flag: &str| cmd.args.iter().any(|arg| arg == flag);
+            assert_eq!(has("--fullscreen"), fullscreen, "{}", mode);
+            assert_eq!(has("--borderless"), borderless, "{}", mode);
+        }
+    }
+
     #[test]
     fn test_build_with_custom_binary() {
         let profile = MockProfile::new()
@@ -260,6 +923,303 @@ mod tests {
         assert!(display.contains("-- steam -gamepadui"));
     }
 
+    #[test]
+    fn test_display_quotes_shell_string() {
+        let profile = MockProfile::new().build();
+        let child = ["sh", "-c", "steam -gamepadui && echo 'done'"].map(String::from);
+        let cmd = build(&profile, &child);
+
+        assert!(cmd
+            .display()
+            .ends_with(r#"-- sh -c 'steam -gamepadui && echo '\''done'\'''"#));
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("steam"), "steam");
+        assert_eq!(shell_quote("--output-width"), "--output-width");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r#"'it'\''s'"#);
+    }
+
+    #[test]
+    fn test_child_line() {
+        let profile = MockProfile::new().with_hdr(true).with_wsi(true).build();
+        let child = ["steam".to_string(), "-gamepadui".to_string()];
+        let mut cmd = build(&profile, &child);
+        cmd.needs_workaround = true;
+        assert_eq!(cmd.child_line(), "env DISABLE_HDR_WSI=1 steam -gamepadui");
+        assert!(cmd.display().ends_with(&format!("-- {}", cmd.child_line())));
+
+        assert_eq!(build(&MockProfile::new().build(), &[]).child_line(), "");
+    }
+
+    #[test]
+    fn test_game_env_only_reaches_child() {
+        let profile = MockProfile::new()
+            .with_hdr(true)
+            .with_wsi(true)
+            .with_game_env(&[("MANGOHUD", "1"), ("DXVK_ASYNC", "1")], &["LD_PRELOAD"])
+            .build();
+        let child = ["steam".to_string()];
+        let mut cmd = build(&profile, &child);
+        assert_eq!(
+            cmd.child_line(),
+            "env -u LD_PRELOAD DXVK_ASYNC=1 MANGOHUD=1 steam"
+        );
+        // gamescope's own environment is untouched
+        assert!(!cmd.env.iter().any(|(k, _)| k == "MANGOHUD"));
+        assert!(!cmd.unset.contains(&"LD_PRELOAD".to_string()));
+
+        // One `env` with the workaround between the unsets and gameEnv
+        cmd.needs_workaround = true;
+        assert_eq!(
+            cmd.child_line(),
+            "env -u LD_PRELOAD DISABLE_HDR_WSI=1 DXVK_ASYNC=1 MANGOHUD=1 steam"
+        );
+
+        // Without gamescope the prefix goes straight on the child
+        assert_eq!(
+            direct_argv(&profile, &child),
+            vec![
+                "env",
+                "-u",
+                "LD_PRELOAD",
+                "DXVK_ASYNC=1",
+                "MANGOHUD=1",
+                "steam"
+            ]
+        );
+        // Nothing to prefix for a bare compositor
+        assert_eq!(build(&profile, &[]).child_line(), "");
+    }
+
+    #[test]
+    fn test_display_without_child() {
+        let profile = MockProfile::new().build();
+        let cmd = build(&profile, &[]);
+        let display = cmd.display();
+
+        assert!(display.starts_with("gamescope"));
+        assert!(!display.ends_with("--"));
+        assert!(!display.contains(" -- "));
+    }
+
+    #[test]
+    fn test_quiet_child_wraps_child_only() {
+        let profile = MockProfile::new()
+            .with_hdr(true)
+            .with_wsi(true)
+            .with_quiet_child(true)
+            .build();
+        let mut cmd = build(&profile, &["steam".to_string()]);
+        cmd.needs_workaround = true;
+
+        assert_eq!(
+            cmd.segments().child_side(),
+            vec![
+                "sh",
+                "-c",
+                "exec \"$@\" >/dev/null 2>&1",
+                "sh",
+                "env",
+                "DISABLE_HDR_WSI=1",
+                "steam"
+            ]
+        );
+    }
+
+    fn segments<'a>(
+        wrapper: &'a [String],
+        wrapper_target: WrapperTarget,
+        child: &'a [String],
+    ) -> Segments<'a> {
+        Segments {
+            scope: &[],
+            wrapper,
+            wrapper_target,
+            binary: "gamescope",
+            args: &[],
+            quiet_child: false,
+            workaround: true,
+            game_unset: &[],
+            game_env: &[],
+            child,
+        }
+    }
+
+    #[test]
+    fn test_assemble_unwrapped() {
+        let child = ["steam".to_string()];
+        assert_eq!(
+            segments(&[], WrapperTarget::Gamescope, &child).assemble(),
+            vec!["gamescope", "--", "env", "DISABLE_HDR_WSI=1", "steam"]
+        );
+        // Bare compositor: no separator, no workaround
+        assert_eq!(
+            segments(&[], WrapperTarget::Gamescope, &[]).assemble(),
+            vec!["gamescope"]
+        );
+    }
+
+    #[test]
+    fn test_assemble_wrapped() {
+        let wrapper = ["gamemoderun".to_string()];
+        let child = ["steam".to_string()];
+        assert_eq!(
+            segments(&wrapper, WrapperTarget::Gamescope, &child).assemble(),
+            vec![
+                "gamemoderun",
+                "gamescope",
+                "--",
+                "env",
+                "DISABLE_HDR_WSI=1",
+                "steam"
+            ]
+        );
+        assert_eq!(
+            segments(&wrapper, WrapperTarget::Child, &child).assemble(),
+            vec![
+                "gamescope",
+                "--",
+                "env",
+                "DISABLE_HDR_WSI=1",
+                "gamemoderun",
+                "steam"
+            ]
+        );
+        // A child-side wrapper has nothing to wrap without a child
+        assert_eq!(
+            segments(&wrapper, WrapperTarget::Child, &[]).assemble(),
+            vec!["gamescope"]
+        );
+        // A gamescope-side wrapper still wraps a bare compositor
+        assert_eq!(
+            segments(&wrapper, WrapperTarget::Gamescope, &[]).assemble(),
+            vec!["gamemoderun", "gamescope"]
+        );
+    }
+
+    #[test]
+    fn test_systemd_scope_prefix() {
+        let mut profile = MockProfile::new().build();
+        profile.wrapper = vec!["gamemoderun".to_string()];
+        profile.systemd_scope = true;
+        profile
+            .scope_properties
+            .insert("MemoryMax".to_string(), "8G".to_string());
+        let child = ["steam".to_string()];
+
+        let display = build(&profile, &child).display();
+        assert!(display
+            .starts_with("systemd-run --user --scope -p MemoryMax=8G -- gamemoderun gamescope "));
+        assert_eq!(
+            direct_argv(&profile, &child),
+            [
+                "systemd-run",
+                "--user",
+                "--scope",
+                "-p",
+                "MemoryMax=8G",
+                "--",
+                "gamemoderun",
+                "steam"
+            ]
+        );
+
+        profile.systemd_scope = false;
+        assert!(scope_argv(&profile).is_empty());
+        assert!(build(&profile, &child)
+            .display()
+            .starts_with("gamemoderun gamescope"));
+    }
+
+    #[test]
+    fn test_rlimit_names_all_map() {
+        for name in crate::config::RLIMIT_NAMES {
+            assert!(rlimit_resource(name).is_some(), "{}", name);
+        }
+        assert!(rlimit_resource("files").is_none());
+    }
+
+    #[test]
+    fn test_apply_rlimits() {
+        // Lowering the core soft limit is always permitted, and so is
+        // raising it back afterwards: the hard limit is left as it was
+        let before = getrlimit(libc::RLIMIT_CORE).unwrap();
+        let rlimits = BTreeMap::from([("core".to_string(), RlimitValue::Limit(0))]);
+        let warnings = apply_rlimits(&rlimits);
+        let after = getrlimit(libc::RLIMIT_CORE).unwrap();
+        // Restored before asserting, so a failure doesn't leak into other tests
+        setrlimit(libc::RLIMIT_CORE, before.rlim_cur, before.rlim_max).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(after.rlim_cur, 0);
+        assert_eq!(after.rlim_max, before.rlim_max);
+
+        let unknown = BTreeMap::from([("files".to_string(), RlimitValue::Limit(1))]);
+        assert_eq!(apply_rlimits(&unknown).len(), 1);
+    }
+
+    #[test]
+    fn test_detach_writes_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("session.log");
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo out; echo err >&2"]);
+
+        let pid = detach(command, Some(&log)).unwrap();
+        assert!(pid > 0);
+        // Not waited on by design; poll for the output instead
+        for _ in 0..100 {
+            if std::fs::read_to_string(&log).unwrap().lines().count() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "out\nerr\n");
+
+        let err = detach(Command::new("true"), Some(&dir.path().join("no/such.log")));
+        assert!(err.unwrap_err().to_string().contains("Failed to open log"));
+    }
+
+    #[test]
+    fn test_detach_starts_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("session.log");
+        // Field 6 of /proc/<pid>/stat is the session id; stdin must be empty
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "read -r stat < /proc/self/stat; set -- $stat; echo $6; cat; echo done",
+        ]);
+
+        let pid = detach(command, Some(&log)).unwrap();
+        for _ in 0..100 {
+            if std::fs::read_to_string(&log).unwrap().ends_with("done\n") {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            format!("{}\ndone\n", pid)
+        );
+    }
+
+    #[test]
+    fn test_quiet_child_wrapper_propagates_exit_code() {
+        let status = Command::new(QUIET_WRAPPER[0])
+            .args(&QUIET_WRAPPER[1..])
+            .args(["sh", "-c", "echo noisy; exit 3"])
+            .stdout(Stdio::piped())
+            .output()
+            .expect("Failed to run sh");
+
+        assert_eq!(status.status.code(), Some(3));
+        assert!(status.stdout.is_empty());
+    }
+
     #[test]
     fn test_display_no_cloning_overhead() {
         let profile = MockProfile::new().build();
@@ -271,6 +1231,43 @@ mod tests {
         assert_eq!(d1, d2);
     }
 
+    #[test]
+    fn test_build_args_float_options() {
+        let mut profile = MockProfile::new().build();
+        profile
+            .options
+            .insert("scale-factor".to_string(), OptionValue::Float(1.5));
+        profile
+            .options
+            .insert("fsr-sharpness".to_string(), OptionValue::Float(2.0));
+        let args = build_args(&profile);
+
+        let pos = args.iter().position(|a| a == "--scale-factor").unwrap();
+        assert_eq!(args[pos + 1], "1.5");
+        let pos = args.iter().position(|a| a == "--fsr-sharpness").unwrap();
+        assert_eq!(args[pos + 1], "2");
+    }
+
+    #[test]
+    fn test_current_option_name_beats_alias() {
+        use crate::config::{Config, MonitorsConfig, ProfilesConfig};
+
+        let monitors: MonitorsConfig = serde_yaml::from_str(
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        )
+        .unwrap();
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  p:\n    options: {fsr-sharpness: 5, sharpness: 2}")
+                .unwrap();
+        let config = Config { monitors, profiles };
+
+        let args = build_args(&config.resolve_profile("p").unwrap());
+        assert!(!args.contains(&"--fsr-sharpness".to_string()));
+        let pos = args.iter().position(|a| a == "--sharpness").unwrap();
+        assert_eq!(args[pos + 1], "2");
+        assert_eq!(args.iter().filter(|a| *a == "--sharpness").count(), 1);
+    }
+
     // ========================================================================
     // Unset Variables Tests
     // ========================================================================
@@ -305,7 +1302,13 @@ mod tests {
             env: vec![("KEY".to_string(), "VALUE".to_string())],
             unset: vec!["REMOVE_ME".to_string()],
             child: vec!["game".to_string()],
+            game_unset: vec![],
+            game_env: vec![],
             needs_workaround: false,
+            quiet_child: false,
+            wrapper: vec![],
+            wrapper_target: WrapperTarget::Gamescope,
+            scope: vec![],
         };
 
         assert_eq!(cmd.unset.len(), 1);
@@ -414,4 +1417,112 @@ mod tests {
         std::env::remove_var("WAYSCOPE_TEST_KEEP");
         std::env::remove_var("WAYSCOPE_TEST_REMOVE");
     }
+
+    #[test]
+    fn test_exit_code_maps_signals() {
+        use std::os::unix::process::ExitStatusExt;
+
+        assert_eq!(exit_code(std::process::ExitStatus::from_raw(3 << 8)), 3);
+        // Raw status 9 = killed by SIGKILL
+        assert_eq!(exit_code(std::process::ExitStatus::from_raw(9)), 137);
+    }
+
+    #[test]
+    fn test_exit_body_shows_the_child() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let status = std::process::ExitStatus::from_raw(3 << 8);
+        let child = vec![
+            "steam".to_string(),
+            "-applaunch".to_string(),
+            "a b".to_string(),
+        ];
+        assert_eq!(
+            exit_body("gaming", &child, status),
+            "Profile 'gaming' exited with code 3\nsteam -applaunch 'a b'"
+        );
+        assert_eq!(
+            exit_body("gaming", &[], status),
+            "Profile 'gaming' exited with code 3"
+        );
+
+        let long = vec!["x".repeat(100)];
+        let body = exit_body("gaming", &long, status);
+        let line = body.lines().nth(1).unwrap();
+        assert_eq!(line.chars().count(), NOTIFY_COMMAND_MAX);
+        assert!(line.ends_with('…'));
+    }
+
+    #[test]
+    fn test_runnable_line_includes_env_and_unset() {
+        let profile = MockProfile::new()
+            .with_hdr(false)
+            .with_wsi(true)
+            .with_unset(vec!["SDL_VIDEODRIVER".to_string()])
+            .build();
+        let mut cmd = build(&profile, &["my game".to_string()]);
+        cmd.env = vec![
+            ("ENABLE_GAMESCOPE_WSI".to_string(), "1".to_string()),
+            ("SDL_VIDEODRIVER".to_string(), "x11".to_string()),
+            ("PROTON_ARGS".to_string(), "a b".to_string()),
+        ];
+
+        let line = cmd.runnable_line();
+        assert!(
+            line.starts_with("env -u SDL_VIDEODRIVER ENABLE_GAMESCOPE_WSI=1 'PROTON_ARGS=a b' ")
+        );
+        // Unset wins, as in apply_env_to_command
+        assert!(!line.contains("SDL_VIDEODRIVER=x11"));
+        assert!(line.ends_with(&cmd.display()));
+        assert!(line.ends_with(" -- 'my game'"));
+    }
+
+    #[test]
+    fn test_inline_env_quotes_values() {
+        let env = [
+            ("A".to_string(), "1".to_string()),
+            ("B".to_string(), "two words".to_string()),
+            ("C".to_string(), "it's".to_string()),
+        ];
+        assert_eq!(inline_env(&env), r"A=1 'B=two words' 'C=it'\''s'");
+    }
+
+    #[test]
+    fn test_nesting() {
+        assert_eq!(nesting(None, false), Nesting::None);
+        assert_eq!(nesting(None, true), Nesting::Foreign);
+        assert_eq!(nesting(Some("gamescope"), true), Nesting::Wayscope);
+        // Base env sets GAMESCOPE_WAYLAND_DISPLAY even when gamescope is skipped
+        assert_eq!(nesting(Some("direct"), true), Nesting::None);
+        assert_eq!(nesting(Some("bogus"), true), Nesting::Foreign);
+    }
+
+    #[test]
+    fn test_option_group() {
+        assert_eq!(option_group("output-width"), OptionGroup::Resolution);
+        assert_eq!(option_group("filter"), OptionGroup::Resolution);
+        assert_eq!(option_group("framerate-limit"), OptionGroup::Performance);
+        assert_eq!(option_group("hdr-itm-target-nits"), OptionGroup::Hdr);
+        assert_eq!(option_group("disable-color-management"), OptionGroup::Hdr);
+        assert_eq!(option_group("default-touch-mode"), OptionGroup::Input);
+        assert_eq!(option_group("backend"), OptionGroup::Misc);
+        assert_eq!(option_group("some-future-flag"), OptionGroup::Misc);
+    }
+
+    #[test]
+    fn test_option_groups_table_has_no_duplicates() {
+        let mut flags: Vec<_> = OPTION_GROUPS.iter().map(|(flag, _)| *flag).collect();
+        flags.sort();
+        flags.dedup();
+        assert_eq!(flags.len(), OPTION_GROUPS.len());
+    }
+
+    #[test]
+    fn test_process_name() {
+        assert_eq!(process_name("hdr"), "wayscope:hdr");
+        assert_eq!(process_name("living-room-tv"), "wayscope:living");
+        // Cut on a character boundary
+        assert_eq!(process_name("abcdé"), "wayscope:abcdé");
+        assert_eq!(process_name("abcdeé"), "wayscope:abcde");
+    }
 }
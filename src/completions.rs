@@ -0,0 +1,151 @@
+//! Shell completion scripts.
+//!
+//! Static scripts come from clap_complete. For fish and zsh, a snippet is
+//! appended that completes profile and monitor names by calling the hidden
+//! `__complete-profiles`/`__complete-monitors` commands, forwarding any
+//! `--config`/`--monitors` already on the command line.
+
+use std::io::Write;
+
+use anyhow::Result;
+use clap::{Command, CommandFactory};
+use clap_complete::{generate, Shell};
+
+use crate::cli::Cli;
+
+const BIN: &str = "wayscope";
+
+const FISH_DYNAMIC: &str = r#"
+# Live profile/monitor names, read with the same --config/--monitors as the command line
+function __fish_wayscope_names
+    set -l cmd (commandline -opc)
+    set -e cmd[1]
+    argparse -i (__fish_wayscope_global_optspecs) -- $cmd 2>/dev/null
+    set -l args
+    set -q _flag_monitors; and set -a args --monitors $_flag_monitors
+    set -q _flag_config; and set -a args --config $_flag_config
+    set -q _flag_config_relative_to_config_dir; and set -a args --config-relative-to-config-dir
+    wayscope $args __complete-$argv[1] 2>/dev/null
+end
+
+complete -c wayscope -n "__fish_wayscope_using_subcommand run" -s p -l profile -f -r -a "(__fish_wayscope_names profiles)"
+complete -c wayscope -n "__fish_wayscope_using_subcommand show get" -f -a "(__fish_wayscope_names profiles)"
+complete -c wayscope -n "__fish_wayscope_using_subcommand monitors" -l diff -f -r -a "(__fish_wayscope_names monitors)"
+complete -c wayscope -n "__fish_wayscope_using_subcommand monitors" -l dump-defaults -f -r -a "(__fish_wayscope_names monitors)"
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+# Live profile/monitor names, read with the same --config/--monitors as the command line
+(( $+functions[_wayscope_names] )) ||
+_wayscope_names() {
+    local -a line args names
+    line=(${(z)BUFFER})
+    local i
+    for (( i = 2; i <= $#line; i++ )); do
+        case ${line[i]} in
+            -m|--monitors|-c|--config) args+=(${line[i]} ${(Q)line[i+1]}) ;;
+            --monitors=*|--config=*|--config-relative-to-config-dir) args+=(${(Q)line[i]}) ;;
+        esac
+    done
+    names=(${(f)"$(wayscope $args __complete-$1 2>/dev/null)"})
+    _describe -t $1 ${1%s} names
+}
+(( $+functions[_wayscope_profiles] )) ||
+_wayscope_profiles() { _wayscope_names profiles }
+(( $+functions[_wayscope_monitors] )) ||
+_wayscope_monitors() { _wayscope_names monitors }
+
+"#;
+
+/// Marker for the trailing dispatch block in clap's zsh script.
+const ZSH_DISPATCH: &str = "if [ \"$funcstack[1]\" = \"_wayscope\" ]; then";
+
+pub fn print(shell: Shell) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    out.write_all(script(shell).as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+fn script(shell: Shell) -> String {
+    let mut buf = Vec::new();
+    generate(shell, &mut visible_command(), BIN, &mut buf);
+    let script = String::from_utf8_lossy(&buf).into_owned();
+
+    match shell {
+        Shell::Fish => script + FISH_DYNAMIC,
+        Shell::Zsh => wire_zsh(&script),
+        _ => script,
+    }
+}
+
+/// The CLI without hidden subcommands, which clap_complete would otherwise list.
+fn visible_command() -> Command {
+    let full = Cli::command();
+    let mut cmd = Command::new(BIN)
+        .about(full.get_about().cloned().unwrap_or_default())
+        .version(env!("CARGO_PKG_VERSION"))
+        .args(full.get_arguments().cloned())
+        .subcommand_required(full.is_subcommand_required_set());
+    for sub in full.get_subcommands().filter(|s| !s.is_hide_set()) {
+        cmd = cmd.subcommand(sub.clone());
+    }
+    cmd
+}
+
+/// Swaps zsh's `_default` completer for live names on profile and monitor
+/// arguments, and defines the helpers before the script dispatches.
+fn wire_zsh(script: &str) -> String {
+    let lines: Vec<String> = script
+        .lines()
+        .map(|line| {
+            if line.starts_with("'*--diff=") || line.contains(":MONITOR:_default'") {
+                line.replace(":_default", ":_wayscope_monitors")
+            } else if line.contains(":PROFILE:_default'") || line.starts_with("':profile -- ") {
+                line.replace(":_default'", ":_wayscope_profiles'")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    let script = lines.join("\n") + "\n";
+
+    match script.find(ZSH_DISPATCH) {
+        Some(at) => format!("{}{}{}", &script[..at], ZSH_DYNAMIC, &script[at..]),
+        None => script + ZSH_DYNAMIC,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_commands_not_completed() {
+        for shell in [Shell::Bash, Shell::Fish, Shell::Zsh] {
+            let script = script(shell);
+            assert!(!script.contains("__complete-profiles:"), "{}", shell);
+            assert!(!script.contains("-a \"__complete-profiles\""), "{}", shell);
+        }
+    }
+
+    #[test]
+    fn test_fish_wires_dynamic_names() {
+        let script = script(Shell::Fish);
+        assert!(script.contains("-l profile -f -r -a \"(__fish_wayscope_names profiles)\""));
+        assert!(script.contains("-l diff -f -r -a \"(__fish_wayscope_names monitors)\""));
+        assert!(script.contains("-l dump-defaults -f -r -a \"(__fish_wayscope_names monitors)\""));
+    }
+
+    #[test]
+    fn test_zsh_wires_dynamic_names() {
+        let script = script(Shell::Zsh);
+        assert!(script.contains(":PROFILE:_wayscope_profiles'"));
+        assert!(script.contains("':profile -- Profile name to inspect:_wayscope_profiles'"));
+        assert!(script.contains(":_wayscope_monitors"));
+        assert!(script.contains(":MONITOR:_wayscope_monitors'"));
+        // Helpers are defined before the script dispatches
+        let helpers = script.find("_wayscope_names() {").unwrap();
+        assert!(helpers < script.find(ZSH_DISPATCH).unwrap());
+    }
+}
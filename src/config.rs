@@ -3,14 +3,20 @@
 //! Configuration is split across two files:
 //! - `monitors.yaml` - Display definitions with resolution and capabilities
 //! - `config.yaml` - Profile definitions that reference monitors
+//!
+//! Both files may also be shipped system-wide in `/etc/wayscope/`. Layers
+//! apply in order system < user (or the `--monitors`/`--config` path), with
+//! same-named entries in the later layer replacing earlier ones whole.
 
 use anyhow::{bail, Context, Result};
-use serde::de::DeserializeOwned;
-use serde::Deserialize;
-use std::collections::HashMap;
+use clap::ValueEnum;
+use serde::de::{DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::Deserializer;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use crate::profile::ResolvedProfile;
+use crate::profile::{BinarySource, ResolvedProfile};
 
 // ============================================================================
 // Environment Variable Name Validation
@@ -42,7 +48,7 @@ use crate::profile::ResolvedProfile;
 /// assert!(!is_valid_env_var_name("MY=VAR"));  // Contains =
 /// assert!(!is_valid_env_var_name("MY VAR"));  // Contains space
 /// ```
-fn is_valid_env_var_name(name: &str) -> bool {
+pub fn is_valid_env_var_name(name: &str) -> bool {
     if name.is_empty() {
         return false;
     }
@@ -61,13 +67,19 @@ fn is_valid_env_var_name(name: &str) -> bool {
     chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
+/// Validates an `unset` entry: a plain variable name, or a glob where `*`
+/// matches any run of characters (e.g. `SDL_*`).
+fn is_valid_unset_pattern(pattern: &str) -> bool {
+    is_valid_env_var_name(&pattern.replace('*', "_"))
+}
+
 /// Validates all environment variable names in a profile and returns errors for invalid ones.
 ///
 /// # Arguments
 ///
 /// * `profile_name` - Name of the profile (for error messages)
 /// * `env_keys` - Iterator of environment variable names to validate
-/// * `unset_vars` - List of variables to unset
+/// * `unset_vars` - List of variables (or `*` globs) to unset
 ///
 /// # Returns
 ///
@@ -86,7 +98,7 @@ fn validate_env_var_names<'a>(
     }
 
     for name in unset_vars {
-        if !is_valid_env_var_name(name) {
+        if !is_valid_unset_pattern(name) {
             invalid.push(format!("unset entry '{}'", name));
         }
     }
@@ -102,6 +114,242 @@ fn validate_env_var_names<'a>(
     }
 }
 
+/// Expands a leading `~` to the user's home directory.
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Parses a dotenv-style file of `KEY=VALUE` lines.
+///
+/// Blank lines and `#` comments are skipped, an `export ` prefix is allowed,
+/// and values may be wrapped in matching single or double quotes.
+fn load_env_file(path: &str) -> Result<Vec<(String, String)>> {
+    let path = expand_tilde(path);
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read env file: {}", path.display()))?;
+
+    let mut vars = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("{}:{}: expected KEY=VALUE", path.display(), idx + 1);
+        };
+        let key = key.trim();
+        if !is_valid_env_var_name(key) {
+            bail!(
+                "{}:{}: invalid environment variable name '{}'",
+                path.display(),
+                idx + 1,
+                key
+            );
+        }
+        let value = value.trim();
+        let value = [('"', '"'), ('\'', '\'')]
+            .iter()
+            .find_map(|(open, close)| value.strip_prefix(*open)?.strip_suffix(*close))
+            .unwrap_or(value);
+        vars.push((key.to_string(), value.to_string()));
+    }
+
+    Ok(vars)
+}
+
+/// Serializes a map with sorted keys so written configs are stable and diffable.
+fn sorted_map<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Directory for distro-packaged defaults, layered under the user's config.
+pub const SYSTEM_CONFIG_DIR: &str = "/etc/wayscope";
+
+/// Directory, relative to the current one, for per-directory overrides
+/// layered over the user's config with `--local-config`. Opt-in, since a
+/// profile can run commands (`preHook`, `wrapper`, ...) and whoever
+/// controls the directory controls its files.
+pub const LOCAL_CONFIG_DIR: &str = ".wayscope";
+
+/// The layers above the system file, lowest precedence first: the `user`
+/// files, `file_name` in `local` when given and it exists, then `explicit`
+/// (`--monitors`/`--config`).
+pub fn stack_layers(
+    user: Vec<PathBuf>,
+    local: Option<&Path>,
+    file_name: &str,
+    explicit: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut layers = user;
+    let local = local.map(|local| local.join(file_name));
+    if let Some(local) = local.filter(|local| local.is_file()) {
+        layers.push(local);
+    }
+    layers.extend(explicit);
+    layers
+}
+
+/// A config file that can be overlaid on a lower-precedence layer.
+trait Layer: DeserializeOwned {
+    /// Applies `upper` on top of `self`; entries in `upper` win.
+    fn overlay(&mut self, upper: Self);
+
+    /// Parses one file of this layer.
+    fn parse(content: &str, path: &Path) -> Result<Self> {
+        parse_yaml(content, path)
+    }
+}
+
+/// Reads a config file, returning `None` if it doesn't exist.
+fn load_optional<T: Layer>(path: &Path) -> Result<Option<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => T::parse(&content, path).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read: {}", path.display())),
+    }
+}
+
+/// Loads each file in `layers` on top of the ones before it. Missing files
+/// are skipped, but at least one must exist.
+fn load_layers<T: Layer>(layers: &[PathBuf]) -> Result<T> {
+    let mut merged: Option<T> = None;
+    for path in layers {
+        let Some(upper) = load_optional::<T>(path)? else {
+            continue;
+        };
+        match &mut merged {
+            Some(base) => base.overlay(upper),
+            None => merged = Some(upper),
+        }
+    }
+    merged.with_context(|| {
+        let last = layers.last().map(|p| p.display().to_string());
+        format!(
+            "Failed to read: {} (file not found)",
+            last.unwrap_or_default()
+        )
+    })
+}
+
+/// Colon-separated directories searched for `config.yaml`/`monitors.yaml`
+/// before the default config dir.
+pub const CONFIG_PATH_ENV: &str = "WAYSCOPE_CONFIG_PATH";
+
+/// The `file_name` files that exist in the `search` directories, in search
+/// order. Empty entries are skipped, as in `PATH`.
+pub fn search_config_path(search: &str, file_name: &str) -> Vec<PathBuf> {
+    std::env::split_paths(search)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join(file_name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Walks a YAML document recording the path of every mapping key that
+/// appears more than once in the same mapping (serde_yaml keeps the last).
+struct DuplicateKeyScan<'a> {
+    path: Vec<String>,
+    found: &'a mut Vec<Vec<String>>,
+}
+
+impl<'de> DeserializeSeed<'de> for DuplicateKeyScan<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DuplicateKeyScan<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any YAML value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen = HashSet::new();
+        while let Some(key) = map.next_key::<serde_yaml::Value>()? {
+            let key = match key {
+                serde_yaml::Value::String(s) => s,
+                other => serde_yaml::to_string(&other)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            };
+            let mut path = self.path.clone();
+            path.push(key.clone());
+            if !seen.insert(key) {
+                self.found.push(path.clone());
+            }
+            map.next_value_seed(DuplicateKeyScan {
+                path,
+                found: &mut *self.found,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        loop {
+            let mut path = self.path.clone();
+            path.push(index.to_string());
+            let seed = DuplicateKeyScan {
+                path,
+                found: &mut *self.found,
+            };
+            if seq.next_element_seed(seed)?.is_none() {
+                return Ok(());
+            }
+            index += 1;
+        }
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+    fn visit_none<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Paths of duplicated mapping keys in `content`. Best effort: syntax errors
+/// are left for the real parse to report.
+fn duplicate_keys(content: &str) -> Vec<Vec<String>> {
+    let mut found = Vec::new();
+    let scan = DuplicateKeyScan {
+        path: Vec::new(),
+        found: &mut found,
+    };
+    let _ = scan.deserialize(serde_yaml::Deserializer::from_str(content));
+    found
+}
+
 /// Wraps serde_yaml with helpful hints for common YAML syntax errors.
 fn parse_yaml<T: DeserializeOwned>(content: &str, path: &Path) -> Result<T> {
     serde_yaml::from_str(content).map_err(|e| {
@@ -126,30 +374,291 @@ fn parse_yaml<T: DeserializeOwned>(content: &str, path: &Path) -> Result<T> {
     })
 }
 
+/// Resolves a user-supplied config path: absolute paths are used as-is,
+/// relative paths are joined onto `base` (the CWD or the config dir).
+pub fn resolve_config_path(path: &Path, base: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
 // ============================================================================
 // Monitor Configuration
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct MonitorsConfig {
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_map")]
     pub monitors: HashMap<String, MonitorDef>,
 }
 
 /// Field names match mix.nix format (refreshRate, not refresh_rate).
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[allow(non_snake_case)]
 pub struct MonitorDef {
     pub width: u32,
     pub height: u32,
-    #[serde(alias = "refresh")]
-    pub refreshRate: u32,
+    /// Refresh rate in Hz; fractional rates like 59.94 are allowed.
+    #[serde(
+        alias = "refresh",
+        deserialize_with = "deserialize_refresh_rate",
+        serialize_with = "serialize_refresh_rate"
+    )]
+    pub refreshRate: f64,
     #[serde(default)]
     pub vrr: bool,
     #[serde(default)]
     pub hdr: bool,
     #[serde(default, alias = "default")]
     pub primary: bool,
+    /// Connector name as detected or written by the user (e.g. `DP-1`, `HDMI-1`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connector: Option<String>,
+    /// Connectors to try in order instead of a single `connector`, e.g. an
+    /// external display with the laptop panel as fallback. drm backend only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connectors: Option<Vec<String>>,
+    /// Output name passed to gamescope's `--prefer-output`. Derived from
+    /// `connector`/`connectors` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferOutput: Option<String>,
+    /// ICC profile for the display; `~` and `${VAR}` are expanded. Recorded
+    /// and checked, but not applied: gamescope has no option to load one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iccProfile: Option<String>,
+    /// The physical monitor, e.g. `Dell U2720Q`, as read from its EDID by
+    /// `detect --yaml`. Informational only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Refresh rate `monitors --changed` compares against.
+const TYPICAL_REFRESH_RATE: f64 = 60.0;
+
+/// Generous sanity bounds that still catch zeros and extra-digit typos.
+const MAX_DIMENSION: u32 = 16384;
+const MAX_REFRESH_RATE: u32 = 1000;
+
+/// Accepts `refreshRate` as an integer (`60`) or a float (`59.94`).
+fn deserialize_refresh_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    struct RefreshRate;
+
+    impl Visitor<'_> for RefreshRate {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a refresh rate in Hz, e.g. 60 or 59.94")
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<f64, E> {
+            Ok(v)
+        }
+    }
+
+    deserializer.deserialize_any(RefreshRate)
+}
+
+/// Writes whole rates as integers so saved files keep `refreshRate: 60`.
+fn serialize_refresh_rate<S: Serializer>(rate: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if rate.fract() == 0.0 && *rate >= 0.0 && *rate <= u32::MAX as f64 {
+        serializer.serialize_u32(*rate as u32)
+    } else {
+        serializer.serialize_f64(*rate)
+    }
+}
+
+impl MonitorDef {
+    fn validate(&self, name: &str) -> Result<()> {
+        let checks = [
+            ("width", f64::from(self.width), MAX_DIMENSION),
+            ("height", f64::from(self.height), MAX_DIMENSION),
+            ("refreshRate", self.refreshRate, MAX_REFRESH_RATE),
+        ];
+        for (field, value, max) in checks {
+            if !(1.0..=f64::from(max)).contains(&value) {
+                bail!(
+                    "Monitor '{}': {} {} is out of range (expected 1-{})",
+                    name,
+                    field,
+                    value,
+                    max
+                );
+            }
+        }
+        if let Some(connectors) = &self.connectors {
+            if self.connector.is_some() {
+                bail!(
+                    "Monitor '{}': set either connector or connectors, not both",
+                    name
+                );
+            }
+            if connectors.is_empty() {
+                bail!("Monitor '{}': connectors is empty", name);
+            }
+        }
+        // Each entry is one name; the list is joined with commas for gamescope
+        let connectors = self.connectors.iter().flatten();
+        for (field, value) in [
+            ("connector", self.connector.as_ref()),
+            ("preferOutput", self.preferOutput.as_ref()),
+        ]
+        .into_iter()
+        .chain(connectors.map(|c| ("connectors", Some(c))))
+        {
+            if let Some(value) = value {
+                let list = field == "connectors" && value.contains(',');
+                if value.is_empty() || value.contains(char::is_whitespace) || list {
+                    bail!(
+                        "Monitor '{}': {} '{}' is not a connector name (e.g. DP-1)",
+                        name,
+                        field,
+                        value
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The output name gamescope expects: `preferOutput`, else the connector
+    /// (or the comma-separated `connectors`) translated to DRM names.
+    pub fn gamescope_output(&self) -> Option<String> {
+        if let Some(output) = &self.preferOutput {
+            return Some(output.clone());
+        }
+        match &self.connectors {
+            Some(connectors) => {
+                let names: Vec<_> = connectors
+                    .iter()
+                    .map(|c| gamescope_output_name(c))
+                    .collect();
+                Some(names.join(","))
+            }
+            None => self.connector.as_deref().map(gamescope_output_name),
+        }
+    }
+
+    /// Whether gamescope is given more than one output to choose from.
+    pub fn has_fallback_outputs(&self) -> bool {
+        self.preferOutput.is_none() && self.connectors.as_ref().is_some_and(|c| c.len() > 1)
+    }
+
+    /// A monitor of the same size with nothing else set: 60 Hz, no VRR or
+    /// HDR, not primary and no connector.
+    pub fn baseline(&self) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            refreshRate: TYPICAL_REFRESH_RATE,
+            vrr: false,
+            hdr: false,
+            primary: false,
+            connector: None,
+            connectors: None,
+            preferOutput: None,
+            iccProfile: None,
+            model: None,
+        }
+    }
+
+    /// Fields set away from `baseline`, as (field, default, value).
+    /// `preferOutput` only counts when written out, not when derived from
+    /// the connector.
+    pub fn changed_from_defaults(&self) -> Vec<(&'static str, String, String)> {
+        self.baseline()
+            .diff(self)
+            .into_iter()
+            .filter(|(field, _, _)| *field != "preferOutput" || self.preferOutput.is_some())
+            .collect()
+    }
+
+    /// The capabilities (`vrr`, `hdr`) this monitor turns on.
+    pub fn enabled_capabilities(&self) -> Vec<&'static str> {
+        [("vrr", self.vrr), ("hdr", self.hdr)]
+            .into_iter()
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Fields that differ between two monitors, as (field, self, other).
+    pub fn diff(&self, other: &Self) -> Vec<(&'static str, String, String)> {
+        let fields = [
+            ("width", self.width.to_string(), other.width.to_string()),
+            ("height", self.height.to_string(), other.height.to_string()),
+            (
+                "refreshRate",
+                self.refreshRate.to_string(),
+                other.refreshRate.to_string(),
+            ),
+            ("vrr", self.vrr.to_string(), other.vrr.to_string()),
+            ("hdr", self.hdr.to_string(), other.hdr.to_string()),
+            (
+                "primary",
+                self.primary.to_string(),
+                other.primary.to_string(),
+            ),
+            (
+                "connector",
+                self.connector.clone().unwrap_or_default(),
+                other.connector.clone().unwrap_or_default(),
+            ),
+            (
+                "connectors",
+                self.connectors
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(","),
+                other
+                    .connectors
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            (
+                "preferOutput",
+                self.gamescope_output().unwrap_or_default(),
+                other.gamescope_output().unwrap_or_default(),
+            ),
+            (
+                "iccProfile",
+                self.iccProfile.clone().unwrap_or_default(),
+                other.iccProfile.clone().unwrap_or_default(),
+            ),
+            (
+                "model",
+                self.model.clone().unwrap_or_default(),
+                other.model.clone().unwrap_or_default(),
+            ),
+        ];
+        fields.into_iter().filter(|(_, a, b)| a != b).collect()
+    }
+}
+
+impl Layer for MonitorsConfig {
+    fn overlay(&mut self, upper: Self) {
+        // Only one monitor may be primary; the upper layer's choice wins
+        if upper.monitors.values().any(|m| m.primary) {
+            for monitor in self.monitors.values_mut() {
+                monitor.primary = false;
+            }
+        }
+        self.monitors.extend(upper.monitors);
+    }
 }
 
 impl MonitorsConfig {
@@ -163,13 +672,17 @@ impl MonitorsConfig {
         Self::config_dir().join("monitors.yaml")
     }
 
-    pub fn load(path: &Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read: {}", path.display()))?;
-        parse_yaml(&content, path)
+    /// System-wide monitors file, layered under the user's.
+    pub fn system_path() -> PathBuf {
+        Path::new(SYSTEM_CONFIG_DIR).join("monitors.yaml")
+    }
+
+    /// Loads each file on top of the previous ones, lowest precedence first.
+    pub fn load_layers(layers: &[PathBuf]) -> Result<Self> {
+        load_layers(layers)
     }
 
-    fn get(&self, name: &str) -> Result<&MonitorDef> {
+    pub fn get(&self, name: &str) -> Result<&MonitorDef> {
         self.monitors
             .get(name)
             .with_context(|| format!("Unknown monitor '{}'", name))
@@ -179,7 +692,10 @@ impl MonitorsConfig {
         self.monitors
             .iter()
             .find(|(_, m)| m.primary)
-            .with_context(|| "No primary monitor. Set 'primary: true' on one monitor.")
+            .with_context(|| {
+                "No primary monitor. Set 'primary: true' on one monitor \
+                 (or pick one for a run with 'run --pick-monitor')."
+            })
     }
 }
 
@@ -187,352 +703,4349 @@ impl MonitorsConfig {
 // Profile Configuration
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 pub struct ProfilesConfig {
-    #[serde(default)]
+    #[serde(default, serialize_with = "sorted_map")]
     pub profiles: HashMap<String, ProfileDef>,
+    /// Short names mapping to profiles (or other aliases).
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "sorted_map"
+    )]
+    pub aliases: HashMap<String, String>,
+    /// Profile used by `run` when neither `-p` nor `WAYSCOPE_PROFILE` is set.
+    #[serde(rename = "defaultProfile", skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 pub struct ProfileDef {
+    /// Profiles merged underneath this one, left to right (later wins).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compose: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub monitor: Option<String>,
-    #[serde(default = "default_binary")]
-    pub binary: String,
-    #[serde(rename = "useHDR")]
+    /// Explicit gamescope binary; falls back to `WAYSCOPE_GAMESCOPE_BIN`, then `gamescope`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+    #[serde(rename = "useHDR", skip_serializing_if = "Option::is_none")]
     pub use_hdr: Option<bool>,
-    #[serde(rename = "useWSI")]
+    #[serde(rename = "useWSI", skip_serializing_if = "Option::is_none")]
     pub use_wsi: Option<bool>,
-    #[serde(default)]
+    #[serde(rename = "allowHDRWithoutWSI", skip_serializing_if = "Option::is_none")]
+    pub allow_hdr_without_wsi: Option<bool>,
+    /// Turn adaptive sync on or off; defaults to the monitor's `vrr`. Only a
+    /// monitor with `vrr: true` gets it turned on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vrr: Option<bool>,
+    /// Keep (`true`) or drop (`false`) `immediate-flips` when `adaptive-sync`
+    /// is on. Unset keeps it, warning when the profile turns both on itself.
+    #[serde(
+        rename = "immediateFlipsWithVRR",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub immediate_flips_with_vrr: Option<bool>,
+    #[serde(
+        default,
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
     pub options: HashMap<String, OptionValue>,
-    #[serde(default)]
+    /// Options merged over `options` only when the resolved monitor has `hdr`.
+    #[serde(
+        default,
+        rename = "optionsWhenHdr",
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub options_when_hdr: HashMap<String, OptionValue>,
+    /// Options merged over `options` only when the resolved monitor has `vrr`.
+    #[serde(
+        default,
+        rename = "optionsWhenVrr",
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub options_when_vrr: HashMap<String, OptionValue>,
+    #[serde(
+        default,
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
     pub environment: HashMap<String, EnvValue>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub unset: Vec<String>,
+    /// Variables set only for the child (via `env` before it), not gamescope.
+    #[serde(
+        default,
+        rename = "gameEnv",
+        serialize_with = "sorted_map",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub game_env: HashMap<String, EnvValue>,
+    /// Variables removed only from the child's environment (`env -u`).
+    #[serde(default, rename = "gameUnset", skip_serializing_if = "Vec::is_empty")]
+    pub game_unset: Vec<String>,
+    /// gamescope `--default-touch-mode` (0-4).
+    #[serde(rename = "touchMode", skip_serializing_if = "Option::is_none")]
+    pub touch_mode: Option<u8>,
+    /// Shorthand for touch passthrough, as on tablets and handhelds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tablet: Option<bool>,
+    /// Display rotation in degrees clockwise (0, 90, 180, 270).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<u16>,
+    /// Swap monitor-derived output width/height when rotated 90/270.
+    #[serde(
+        rename = "rotationSwapsResolution",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rotation_swaps_resolution: Option<bool>,
+    /// gamescope `--disable-color-management`, for games that misbehave with it.
+    #[serde(
+        rename = "disableColorManagement",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub disable_color_management: Option<bool>,
+    /// dotenv-style file loaded before the inline `environment`.
+    #[serde(rename = "envFile", skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Send the child's stdout/stderr to /dev/null.
+    #[serde(rename = "quietChild", skip_serializing_if = "Option::is_none")]
+    pub quiet_child: Option<bool>,
+    /// gamescope `--xwayland-count` (1-4).
+    #[serde(rename = "xwaylandCount", skip_serializing_if = "Option::is_none")]
+    pub xwayland_count: Option<u8>,
+    /// gamescope `--hide-cursor-delay`: ms of inactivity before the cursor hides.
+    #[serde(rename = "hideCursorDelay", skip_serializing_if = "Option::is_none")]
+    pub hide_cursor_delay: Option<u32>,
+    /// gamescope `--cursor-scale-height`: output height the cursor size is
+    /// scaled against (gamescope has no direct scale factor).
+    #[serde(rename = "cursorScaleHeight", skip_serializing_if = "Option::is_none")]
+    pub cursor_scale_height: Option<u32>,
+    /// gamescope `--hdr-sdr-content-nits`: SDR content brightness in HDR mode.
+    #[serde(rename = "sdrNits", skip_serializing_if = "Option::is_none")]
+    pub sdr_nits: Option<u32>,
+    /// gamescope `--hdr-itm-target-nits`: peak brightness for inverse tone mapping.
+    #[serde(rename = "hdrMaxNits", skip_serializing_if = "Option::is_none")]
+    pub hdr_max_nits: Option<u32>,
+    /// Send a desktop notification when the session exits (spawns instead of exec).
+    #[serde(rename = "notifyOnExit", skip_serializing_if = "Option::is_none")]
+    pub notify_on_exit: Option<bool>,
+    /// Append start/end records to the session log (`wayscope sessions`).
+    #[serde(rename = "logSessions", skip_serializing_if = "Option::is_none")]
+    pub log_sessions: Option<bool>,
+    /// gamescope window title; `${VAR}` expands from the profile environment.
+    #[serde(rename = "windowTitle", skip_serializing_if = "Option::is_none")]
+    pub window_title: Option<String>,
+    /// Locale for the game, exported as `LANG` and `LC_ALL` (e.g. `ja_JP.UTF-8`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Timezone for the game, exported as `TZ` (e.g. `Asia/Tokyo`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Run the command directly with the profile environment, without gamescope.
+    #[serde(rename = "skipGamescope", skip_serializing_if = "Option::is_none")]
+    pub skip_gamescope: Option<bool>,
+    /// Render at this multiple of the output resolution and downscale (1.0-2.0).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supersample: Option<f32>,
+    /// gamescope `--filter`, the upscaling filter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+    /// Fullscreen, borderless or windowed; sets `fullscreen` and
+    /// `borderless` together so they can't contradict each other.
+    #[serde(rename = "windowMode", skip_serializing_if = "Option::is_none")]
+    pub window_mode: Option<WindowMode>,
+    /// gamescope `--generate-drm-mode` (`cvt` or `fixed`); drm backend only.
+    #[serde(rename = "drmMode", skip_serializing_if = "Option::is_none")]
+    pub drm_mode: Option<String>,
+    /// Resource limits applied before launch, e.g. `nofile: 524288`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rlimits: BTreeMap<String, RlimitValue>,
+    /// Command prepended to the launch (e.g. `[gamemoderun]`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrapper: Vec<String>,
+    /// Whether `wrapper` wraps gamescope itself (default) or only the child.
+    #[serde(rename = "wrapperTarget", skip_serializing_if = "Option::is_none")]
+    pub wrapper_target: Option<WrapperTarget>,
+    /// Launch inside a transient `systemd-run --user --scope`.
+    #[serde(rename = "systemdScope", skip_serializing_if = "Option::is_none")]
+    pub systemd_scope: Option<bool>,
+    /// Scope unit properties such as `MemoryMax: 8G`; implies `systemdScope`
+    /// unless it is explicitly `false`.
+    #[serde(
+        default,
+        rename = "scopeProperties",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub scope_properties: BTreeMap<String, String>,
+    /// Whether `environment` (default) or wayscope's base variables win
+    /// when both set the same variable.
+    #[serde(rename = "envPriority", skip_serializing_if = "Option::is_none")]
+    pub env_priority: Option<EnvPriority>,
+    /// Groups of wayscope's base variables to leave out, e.g. `amd` on
+    /// NVIDIA or `proton` for native games.
+    #[serde(
+        default,
+        rename = "disableBaseEnv",
+        skip_serializing_if = "BTreeSet::is_empty"
+    )]
+    pub disable_base_env: BTreeSet<BaseEnvGroup>,
+    /// Proton version for Steam compat tooling, exported as `PROTON_VERSION`.
+    #[serde(rename = "protonVersion", skip_serializing_if = "Option::is_none")]
+    pub proton_version: Option<String>,
+    /// Proton prefix directory, exported as `STEAM_COMPAT_DATA_PATH`.
+    /// `~` and `${VAR}` are expanded.
+    #[serde(rename = "compatDataPath", skip_serializing_if = "Option::is_none")]
+    pub compat_data_path: Option<String>,
+    /// Steam install directory, exported as `STEAM_COMPAT_CLIENT_INSTALL_PATH`.
+    /// `~` and `${VAR}` are expanded.
+    #[serde(
+        rename = "compatClientInstallPath",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub compat_client_install_path: Option<String>,
+    /// gamescope's own log level, exported as `GAMESCOPE_LOG` (e.g. `debug`).
+    #[serde(rename = "gamescopeLog", skip_serializing_if = "Option::is_none")]
+    pub gamescope_log: Option<String>,
+    /// Shell command run (`sh -c`) before the launch.
+    #[serde(rename = "preHook", skip_serializing_if = "Option::is_none")]
+    pub pre_hook: Option<String>,
+    /// Shell command run after the session exits; wayscope stays resident.
+    #[serde(rename = "postHook", skip_serializing_if = "Option::is_none")]
+    pub post_hook: Option<String>,
+    /// Seconds `preHook` may run before it's killed.
+    #[serde(rename = "preTimeout", skip_serializing_if = "Option::is_none")]
+    pub pre_timeout: Option<u64>,
+    /// Seconds `postHook` may run before it's killed.
+    #[serde(rename = "postTimeout", skip_serializing_if = "Option::is_none")]
+    pub post_timeout: Option<u64>,
+    /// What a failed or timed-out hook does (`abort` by default).
+    #[serde(rename = "hookFailure", skip_serializing_if = "Option::is_none")]
+    pub hook_failure: Option<HookFailure>,
+    /// `options`/`environment` keys written more than once, as `block.key`.
+    /// Filled in after parsing; serde_yaml silently keeps the last value.
+    #[serde(skip)]
+    pub duplicate_keys: Vec<String>,
 }
 
-fn default_binary() -> String {
-    "gamescope".to_string()
+/// A resource limit: a number (bytes, count, ... per the limit) or `unlimited`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RlimitValue {
+    Limit(u64),
+    Unlimited,
 }
 
-impl ProfilesConfig {
-    pub fn default_path() -> PathBuf {
-        MonitorsConfig::config_dir().join("config.yaml")
+impl RlimitValue {
+    /// Converts a raw `rlim_t`-style value, where `u64::MAX` means unlimited.
+    pub fn from_raw(value: u64) -> Self {
+        if value == u64::MAX {
+            Self::Unlimited
+        } else {
+            Self::Limit(value)
+        }
     }
+}
 
-    pub fn load(path: &Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read: {}", path.display()))?;
-        parse_yaml(&content, path)
+impl std::fmt::Display for RlimitValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Limit(n) => write!(f, "{}", n),
+            Self::Unlimited => write!(f, "unlimited"),
+        }
     }
+}
 
-    fn get(&self, name: &str) -> Result<&ProfileDef> {
-        self.profiles
-            .get(name)
-            .with_context(|| format!("Unknown profile '{}'", name))
+impl Serialize for RlimitValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Limit(n) => serializer.serialize_u64(*n),
+            Self::Unlimited => serializer.serialize_str("unlimited"),
+        }
     }
+}
 
-    fn names(&self) -> Vec<&String> {
-        let mut names: Vec<_> = self.profiles.keys().collect();
-        names.sort();
-        names
+impl<'de> Deserialize<'de> for RlimitValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Limit;
+
+        impl Visitor<'_> for Limit {
+            type Value = RlimitValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a non-negative limit or \"unlimited\"")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<RlimitValue, E> {
+                Ok(RlimitValue::Limit(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<RlimitValue, E> {
+                u64::try_from(v)
+                    .map(RlimitValue::Limit)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<RlimitValue, E> {
+                match v {
+                    "unlimited" => Ok(RlimitValue::Unlimited),
+                    _ => Err(E::invalid_value(serde::de::Unexpected::Str(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(Limit)
     }
 }
 
-// ============================================================================
-// Value Types
-// ============================================================================
+/// Limit names accepted in `rlimits`, as in `ulimit`/`prlimit` (`RLIMIT_<NAME>`).
+pub const RLIMIT_NAMES: &[&str] = &[
+    "as", "core", "cpu", "data", "fsize", "memlock", "nice", "nofile", "nproc", "rtprio", "stack",
+];
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(untagged)]
-pub enum OptionValue {
-    Bool(bool),
-    Int(i64),
-    String(String),
+/// What a profile's `wrapper` command wraps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapperTarget {
+    /// `wrapper gamescope ... -- child`
+    #[default]
+    Gamescope,
+    /// `gamescope ... -- wrapper child`
+    Child,
 }
 
-impl std::fmt::Display for OptionValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// gamescope upscaling filters (`filter`).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    Linear,
+    Nearest,
+    /// AMD FidelityFX Super Resolution; tuned with `sharpness`.
+    Fsr,
+    /// NVIDIA Image Scaling; tuned with `sharpness` or `nis-sharpness`.
+    Nis,
+    Pixel,
+}
+
+impl Filter {
+    pub fn as_str(self) -> &'static str {
         match self {
-            Self::Bool(b) => write!(f, "{}", b),
-            Self::Int(i) => write!(f, "{}", i),
-            Self::String(s) => write!(f, "{}", s),
+            Self::Linear => "linear",
+            Self::Nearest => "nearest",
+            Self::Fsr => "fsr",
+            Self::Nis => "nis",
+            Self::Pixel => "pixel",
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(untagged)]
-pub enum EnvValue {
-    Int(i64),
-    String(String),
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-impl std::fmt::Display for EnvValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// How gamescope's window is shown (`windowMode`).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowMode {
+    Fullscreen,
+    Borderless,
+    Windowed,
+}
+
+impl WindowMode {
+    pub fn as_str(self) -> &'static str {
         match self {
-            Self::Int(i) => write!(f, "{}", i),
-            Self::String(s) => write!(f, "{}", s),
+            Self::Fullscreen => "fullscreen",
+            Self::Borderless => "borderless",
+            Self::Windowed => "windowed",
         }
     }
-}
 
-// ============================================================================
-// Combined Configuration
-// ============================================================================
+    /// Sets `fullscreen` and `borderless` for this mode. `fullscreen` is
+    /// turned off explicitly since the monitor defaults turn it on.
+    pub fn apply(self, options: &mut HashMap<String, OptionValue>) {
+        options.insert(
+            FULLSCREEN_OPTION.to_string(),
+            OptionValue::Bool(self == Self::Fullscreen),
+        );
+        if self == Self::Borderless {
+            options.insert(BORDERLESS_OPTION.to_string(), OptionValue::Bool(true));
+        } else {
+            options.remove(BORDERLESS_OPTION);
+        }
+    }
 
-#[derive(Debug)]
-pub struct Config {
-    pub monitors: MonitorsConfig,
-    pub profiles: ProfilesConfig,
+    /// The mode `options` amount to, or `None` when `fullscreen` and
+    /// `borderless` are both on.
+    pub fn of(options: &HashMap<String, OptionValue>) -> Option<Self> {
+        let on = |key: &str| options.get(key) == Some(&OptionValue::Bool(true));
+        match (on(FULLSCREEN_OPTION), on(BORDERLESS_OPTION)) {
+            (true, true) => None,
+            (true, false) => Some(Self::Fullscreen),
+            (false, true) => Some(Self::Borderless),
+            (false, false) => Some(Self::Windowed),
+        }
+    }
 }
 
-impl Config {
-    pub fn load(monitors_path: &Path, profiles_path: &Path) -> Result<Self> {
-        let monitors = MonitorsConfig::load(monitors_path)?;
-        let profiles = ProfilesConfig::load(profiles_path)?;
+impl std::fmt::Display for WindowMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
-        // Validate each profile
-        for (name, profile) in &profiles.profiles {
-            // Validate environment variable names (both set and unset)
-            validate_env_var_names(name, profile.environment.keys(), &profile.unset)?;
+/// Which side wins when `environment` sets one of wayscope's base variables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvPriority {
+    /// The profile's value replaces (or with `+`, extends) the base value.
+    #[default]
+    User,
+    /// The base value is reapplied over the profile's.
+    Base,
+}
 
-            // Validate monitor reference exists
-            if let Some(ref mon_name) = profile.monitor {
-                if !monitors.monitors.contains_key(mon_name) {
-                    bail!(
-                        "Profile '{}' references unknown monitor '{}'",
-                        name,
-                        mon_name
-                    );
-                }
-            }
-            // Note: We don't deduplicate unset vars because env_remove() is idempotent.
-            // Duplicate entries in the config are harmless and removing them adds complexity.
-        }
+/// What a hook that fails or runs past its timeout does (`hookFailure`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookFailure {
+    /// A `preHook` stops the launch; a `postHook` makes wayscope exit with
+    /// an error.
+    #[default]
+    Abort,
+    /// Warn and carry on.
+    Warn,
+}
 
-        Ok(Self { monitors, profiles })
+impl std::fmt::Display for HookFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Abort => "abort",
+            Self::Warn => "warn",
+        })
     }
+}
 
-    /// Combines profile settings with monitor config into a ready-to-execute profile.
-    pub fn resolve_profile(&self, name: &str) -> Result<ResolvedProfile> {
-        let profile = self.profiles.get(name)?;
+/// A group of wayscope's base environment variables (`disableBaseEnv`).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BaseEnvGroup {
+    /// RADV as the Vulkan driver, and the switchable-graphics layers off.
+    Amd,
+    /// Wayland for gamescope clients and SDL.
+    Wayland,
+    /// Proton's Wayland driver and config.
+    Proton,
+}
 
-        let (monitor_name, monitor) = match &profile.monitor {
-            Some(n) => (n.clone(), self.monitors.get(n)?),
-            None => {
-                let (n, m) = self.monitors.default_monitor()?;
-                (n.clone(), m)
-            }
-        };
+impl BaseEnvGroup {
+    pub const ALL: [Self; 3] = [Self::Amd, Self::Wayland, Self::Proton];
+}
+
+impl std::fmt::Display for BaseEnvGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Amd => write!(f, "amd"),
+            Self::Wayland => write!(f, "wayland"),
+            Self::Proton => write!(f, "proton"),
+        }
+    }
+}
+
+/// Environment variable that overrides the default gamescope binary.
+pub const GAMESCOPE_BIN_ENV: &str = "WAYSCOPE_GAMESCOPE_BIN";
+
+/// Environment variable that selects the profile when `-p` isn't passed.
+pub const PROFILE_ENV: &str = "WAYSCOPE_PROFILE";
+
+/// Where the selected profile name came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileSource {
+    Flag,
+    Environment,
+    Config,
+    Default,
+}
+
+impl std::fmt::Display for ProfileSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flag => write!(f, "-p flag"),
+            Self::Environment => write!(f, "{}", PROFILE_ENV),
+            Self::Config => write!(f, "config defaultProfile"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Picks the profile name: `-p` flag > `WAYSCOPE_PROFILE` > `defaultProfile` > `default`.
+pub fn select_profile(
+    flag: Option<&str>,
+    env: Option<String>,
+    config_default: Option<&str>,
+) -> (String, ProfileSource) {
+    match (flag, env, config_default) {
+        (Some(name), _, _) => (name.to_string(), ProfileSource::Flag),
+        (None, Some(name), _) if !name.is_empty() => (name, ProfileSource::Environment),
+        (None, _, Some(name)) => (name.to_string(), ProfileSource::Config),
+        (None, _, None) => ("default".to_string(), ProfileSource::Default),
+    }
+}
+
+/// Picks the gamescope binary: explicit profile binary > env var > `gamescope`.
+pub fn resolve_binary(explicit: Option<&str>, env: Option<String>) -> (String, BinarySource) {
+    match (explicit, env) {
+        (Some(binary), _) => (binary.to_string(), BinarySource::Profile),
+        (None, Some(binary)) if !binary.is_empty() => (binary, BinarySource::Environment),
+        (None, _) => ("gamescope".to_string(), BinarySource::Default),
+    }
+}
+
+/// gamescope touch modes: hover, left click, right click, middle click, passthrough.
+const TOUCH_MODE_PASSTHROUGH: u8 = 4;
+
+const MAX_XWAYLAND_COUNT: u8 = 4;
+
+const MAX_NITS: u32 = 10000;
+
+/// One minute; longer delays are almost certainly a seconds/ms mixup.
+const MAX_HIDE_CURSOR_DELAY_MS: u32 = 60_000;
+
+const SUPERSAMPLE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=2.0;
+/// Filter `supersample` selects when no filter is set.
+const SUPERSAMPLE_FILTER: Filter = Filter::Linear;
+
+pub const FILTER_OPTION: &str = "filter";
+pub const FULLSCREEN_OPTION: &str = "fullscreen";
+pub const BORDERLESS_OPTION: &str = "borderless";
+/// Sharpness options, and the filters each one tunes.
+const SHARPNESS_OPTIONS: &[(&str, &[Filter])] = &[
+    ("sharpness", &[Filter::Fsr, Filter::Nis]),
+    ("nis-sharpness", &[Filter::Nis]),
+];
+/// Older gamescope's upscaler switches, and the filter each one turns on.
+const LEGACY_UPSCALING_OPTIONS: &[(&str, Filter)] = &[
+    ("fsr-upscaling", Filter::Fsr),
+    ("nis-upscaling", Filter::Nis),
+];
+
+pub const SDR_NITS_OPTION: &str = "hdr-sdr-content-nits";
+pub const HDR_MAX_NITS_OPTION: &str = "hdr-itm-target-nits";
+/// gamescope flag that `windowTitle` maps to.
+pub const WINDOW_TITLE_OPTION: &str = "title";
+
+/// gamescope backends a profile may select with `options.backend`.
+const BACKENDS: &[&str] = &["sdl", "wayland", "drm", "auto", "headless", "openvr"];
+
+/// Canonical form of a backend value: ` Wayland ` means `wayland`.
+pub fn normalize_backend(value: &str) -> String {
+    value.trim().to_ascii_lowercase()
+}
+
+pub const DRM_MODE_OPTION: &str = "generate-drm-mode";
+/// Modeline generators gamescope accepts for `--generate-drm-mode`.
+const DRM_MODES: &[&str] = &["cvt", "fixed"];
+
+/// Variable gamescope reads its internal log level from (`gamescopeLog`).
+pub const GAMESCOPE_LOG_ENV: &str = "GAMESCOPE_LOG";
+/// Log levels gamescope understands, quietest first.
+const GAMESCOPE_LOG_LEVELS: &[&str] = &["silent", "error", "warning", "info", "debug"];
+
+/// Loose locale check: `C`/`POSIX`, or `xx[_XX][.codeset][@modifier]`.
+fn locale_looks_valid(locale: &str) -> bool {
+    let (locale, modifier) = locale.split_once('@').unwrap_or((locale, ""));
+    let (name, codeset) = locale.split_once('.').unwrap_or((locale, ""));
+    let (lang, territory) = name.split_once('_').unwrap_or((name, ""));
+
+    let word = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    let lang_ok = matches!(lang, "C" | "POSIX")
+        || ((2..=3).contains(&lang.len()) && lang.chars().all(|c| c.is_ascii_lowercase()));
+    let territory_ok = territory.is_empty()
+        || (territory.len() == 2 && territory.chars().all(|c| c.is_ascii_uppercase()));
+
+    lang_ok && territory_ok && word(codeset) && word(modifier)
+}
+
+/// Expands `${NAME}` references in `template` using `lookup`; `$${` is a
+/// literal `${`, for values such as shell snippets.
+///
+/// Returns the expanded string and the names `lookup` couldn't resolve,
+/// which expand to nothing. Unterminated or invalid references are errors.
+fn interpolate(
+    template: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<(String, Vec<String>)> {
+    let mut out = String::with_capacity(template.len());
+    let mut missing = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            out.push_str(before);
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let Some(end) = after.find('}') else {
+            bail!("unterminated '${{' in '{}'", template);
+        };
+        let name = &after[..end];
+        if !is_valid_env_var_name(name) {
+            bail!("invalid variable name '{}' in '{}'", name, template);
+        }
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => missing.push(name.to_string()),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+
+    Ok((out, missing))
+}
+
+/// Expands `${NAME}` references between a profile's environment values.
+///
+/// A reference to another variable in `env` resolves to that variable's
+/// (expanded) value; anything else, including a variable referencing
+/// itself (`PATH: "${PATH}:/opt/bin"`), falls back to `process`. Expands
+/// `env` in place and returns the unresolved `(variable, reference)` pairs.
+/// Reference cycles between variables are errors.
+fn expand_env_refs(
+    env: &mut HashMap<String, String>,
+    process: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<(String, String)>> {
+    struct Expander<'a, F> {
+        env: &'a HashMap<String, String>,
+        process: F,
+        done: HashMap<String, String>,
+        stack: Vec<&'a str>,
+        missing: Vec<(String, String)>,
+    }
+
+    impl<'a, F: Fn(&str) -> Option<String>> Expander<'a, F> {
+        fn expand(&mut self, name: &'a str) -> Result<()> {
+            if self.done.contains_key(name) {
+                return Ok(());
+            }
+            if let Some(at) = self.stack.iter().position(|n| *n == name) {
+                let mut cycle = self.stack[at..].to_vec();
+                cycle.push(name);
+                bail!("environment reference cycle: {}", cycle.join(" -> "));
+            }
+            let template = &self.env[name];
+            // Every reference comes back as "missing" when nothing resolves
+            let (_, refs) = interpolate(template, |_| None)
+                .with_context(|| format!("invalid value for {}", name))?;
+
+            self.stack.push(name);
+            for var in &refs {
+                if let Some((var, _)) = self.env.get_key_value(var.as_str()) {
+                    if var != name {
+                        self.expand(var)?;
+                    }
+                }
+            }
+            self.stack.pop();
+
+            let (value, missing) = interpolate(template, |var| match self.done.get(var) {
+                Some(value) if var != name => Some(value.clone()),
+                _ => (self.process)(var),
+            })?;
+            self.missing
+                .extend(missing.into_iter().map(|var| (name.to_string(), var)));
+            self.done.insert(name.to_string(), value);
+            Ok(())
+        }
+    }
+
+    let source = std::mem::take(env);
+    let mut expander = Expander {
+        env: &source,
+        process,
+        done: HashMap::with_capacity(source.len()),
+        stack: Vec::new(),
+        missing: Vec::new(),
+    };
+    let mut names: Vec<_> = source.keys().collect();
+    names.sort();
+    for name in names {
+        expander.expand(name)?;
+    }
+    *env = expander.done;
+    expander.missing.sort();
+    Ok(expander.missing)
+}
+
+impl ProfileDef {
+    /// Validates typed fields that serde can't constrain on its own.
+    fn validate(&self, name: &str) -> Result<()> {
+        // Passed to `env`, so no `unset`-style globs
+        for (field, var) in self
+            .game_env
+            .keys()
+            .map(|k| ("gameEnv key", k))
+            .chain(self.game_unset.iter().map(|u| ("gameUnset entry", u)))
+        {
+            if !is_valid_env_var_name(var) {
+                bail!(
+                    "Profile '{}': invalid {} '{}' (expected a variable name)",
+                    name,
+                    field,
+                    var
+                );
+            }
+        }
+        if let Some(mode) = self.touch_mode {
+            if mode > TOUCH_MODE_PASSTHROUGH {
+                bail!(
+                    "Profile '{}': invalid touchMode {} (expected 0=hover, 1=left, 2=right, \
+                     3=middle, 4=passthrough)",
+                    name,
+                    mode
+                );
+            }
+        }
+        if let Some(rotation) = self.rotation {
+            if rotation_orientation(rotation).is_none() {
+                bail!(
+                    "Profile '{}': invalid rotation {} (expected 0, 90, 180 or 270)",
+                    name,
+                    rotation
+                );
+            }
+        }
+        if let Some(count) = self.xwayland_count {
+            if !(1..=MAX_XWAYLAND_COUNT).contains(&count) {
+                bail!(
+                    "Profile '{}': invalid xwaylandCount {} (expected 1-{})",
+                    name,
+                    count,
+                    MAX_XWAYLAND_COUNT
+                );
+            }
+        }
+        if let Some(delay) = self.hide_cursor_delay {
+            if delay > MAX_HIDE_CURSOR_DELAY_MS {
+                bail!(
+                    "Profile '{}': invalid hideCursorDelay {} (expected 0-{} ms)",
+                    name,
+                    delay,
+                    MAX_HIDE_CURSOR_DELAY_MS
+                );
+            }
+        }
+        if let Some(height) = self.cursor_scale_height {
+            if !(1..=MAX_DIMENSION).contains(&height) {
+                bail!(
+                    "Profile '{}': invalid cursorScaleHeight {} (expected 1-{})",
+                    name,
+                    height,
+                    MAX_DIMENSION
+                );
+            }
+        }
+        for (field, nits) in [
+            ("sdrNits", self.sdr_nits),
+            ("hdrMaxNits", self.hdr_max_nits),
+        ] {
+            if let Some(nits) = nits {
+                if !(1..=MAX_NITS).contains(&nits) {
+                    bail!(
+                        "Profile '{}': invalid {} {} (expected 1-{})",
+                        name,
+                        field,
+                        nits,
+                        MAX_NITS
+                    );
+                }
+            }
+        }
+        if let Some(factor) = self.supersample {
+            if !SUPERSAMPLE_RANGE.contains(&factor) {
+                bail!(
+                    "Profile '{}': invalid supersample {} (expected {}-{})",
+                    name,
+                    factor,
+                    SUPERSAMPLE_RANGE.start(),
+                    SUPERSAMPLE_RANGE.end()
+                );
+            }
+        }
+        for (field, hook) in [("preHook", &self.pre_hook), ("postHook", &self.post_hook)] {
+            if hook.as_ref().is_some_and(|hook| hook.trim().is_empty()) {
+                bail!("Profile '{}': {} must not be empty", name, field);
+            }
+        }
+        for (field, timeout) in [
+            ("preTimeout", self.pre_timeout),
+            ("postTimeout", self.post_timeout),
+        ] {
+            if timeout == Some(0) {
+                bail!(
+                    "Profile '{}': invalid {} 0 (expected at least 1 second)",
+                    name,
+                    field
+                );
+            }
+        }
+        if let Some(binary) = &self.binary {
+            if binary.trim().is_empty() {
+                bail!("Profile '{}': binary must not be empty", name);
+            }
+            // Paths with spaces are fine if they exist; otherwise it's likely a command line
+            if binary.contains(char::is_whitespace) && !expand_tilde(binary).is_file() {
+                bail!(
+                    "Profile '{}': binary '{}' looks like a command with arguments; \
+                     set only the executable path and pass gamescope flags via 'options'",
+                    name,
+                    binary
+                );
+            }
+        }
+        for (block, options) in [
+            ("options", &self.options),
+            ("optionsWhenHdr", &self.options_when_hdr),
+            ("optionsWhenVrr", &self.options_when_vrr),
+        ] {
+            let Some(backend) = options.get("backend") else {
+                continue;
+            };
+            let known = matches!(backend, OptionValue::String(value)
+                if BACKENDS.contains(&normalize_backend(value).as_str()));
+            if !known {
+                bail!(
+                    "Profile '{}': invalid {}.backend '{}' (expected one of: {})",
+                    name,
+                    block,
+                    backend,
+                    BACKENDS.join(", ")
+                );
+            }
+        }
+        if let Some(mode) = &self.drm_mode {
+            if !DRM_MODES.contains(&mode.as_str()) {
+                bail!(
+                    "Profile '{}': invalid drmMode '{}' (expected one of: {})",
+                    name,
+                    mode,
+                    DRM_MODES.join(", ")
+                );
+            }
+        }
+        for limit in self.rlimits.keys() {
+            if !RLIMIT_NAMES.contains(&limit.as_str()) {
+                bail!(
+                    "Profile '{}': unknown rlimit '{}' (expected one of: {})",
+                    name,
+                    limit,
+                    RLIMIT_NAMES.join(", ")
+                );
+            }
+        }
+        if self.wrapper.iter().any(|arg| arg.trim().is_empty()) {
+            bail!("Profile '{}': wrapper entries must not be empty", name);
+        }
+        for (property, value) in &self.scope_properties {
+            if property.is_empty() || !property.chars().all(|c| c.is_ascii_alphanumeric()) {
+                bail!(
+                    "Profile '{}': invalid scopeProperties name '{}' (expected e.g. MemoryMax)",
+                    name,
+                    property
+                );
+            }
+            if value.contains('\n') {
+                bail!(
+                    "Profile '{}': scopeProperties.{} must be a single line",
+                    name,
+                    property
+                );
+            }
+        }
+        for (field, path) in [
+            ("compatDataPath", &self.compat_data_path),
+            ("compatClientInstallPath", &self.compat_client_install_path),
+        ] {
+            if let Some(path) = path {
+                if path.trim().is_empty() {
+                    bail!("Profile '{}': {} must not be empty", name, field);
+                }
+                interpolate(path, |_| None)
+                    .with_context(|| format!("Profile '{}': invalid {}", name, field))?;
+            }
+        }
+        if let Some(title) = &self.window_title {
+            if title.trim().is_empty() {
+                bail!("Profile '{}': windowTitle must not be empty", name);
+            }
+            interpolate(title, |_| None)
+                .with_context(|| format!("Profile '{}': invalid windowTitle", name))?;
+        }
+        let mut environment: HashMap<_, _> = self
+            .environment
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_string()))
+            .collect();
+        expand_env_refs(&mut environment, |_| None)
+            .with_context(|| format!("Profile '{}': invalid environment", name))?;
+        Ok(())
+    }
+
+    /// Maps typed fields onto gamescope options, taking precedence over `options`.
+    fn apply_typed_options(&self, options: &mut HashMap<String, OptionValue>) {
+        let touch_mode = self.touch_mode.or(self
+            .tablet
+            .unwrap_or(false)
+            .then_some(TOUCH_MODE_PASSTHROUGH));
+        if let Some(mode) = touch_mode {
+            options.insert(
+                "default-touch-mode".to_string(),
+                OptionValue::Int(i64::from(mode)),
+            );
+        }
+        if let Some(orientation) = self.rotation.and_then(rotation_orientation) {
+            options.insert(
+                "force-orientation".to_string(),
+                OptionValue::String(orientation.to_string()),
+            );
+        }
+        if let Some(disable) = self.disable_color_management {
+            options.insert(
+                "disable-color-management".to_string(),
+                OptionValue::Bool(disable),
+            );
+        }
+        if let Some(count) = self.xwayland_count {
+            options.insert(
+                "xwayland-count".to_string(),
+                OptionValue::Int(i64::from(count)),
+            );
+        }
+        for (flag, value) in [
+            (SDR_NITS_OPTION, self.sdr_nits),
+            (HDR_MAX_NITS_OPTION, self.hdr_max_nits),
+            ("hide-cursor-delay", self.hide_cursor_delay),
+            ("cursor-scale-height", self.cursor_scale_height),
+        ] {
+            if let Some(value) = value {
+                options.insert(flag.to_string(), OptionValue::Int(i64::from(value)));
+            }
+        }
+        if let Some(filter) = self.filter {
+            options.insert(
+                FILTER_OPTION.to_string(),
+                OptionValue::String(filter.to_string()),
+            );
+        }
+        if let Some(mode) = self.window_mode {
+            mode.apply(options);
+        }
+        // Scales the (possibly rotation-swapped) output size already in `options`
+        if let Some(factor) = self.supersample {
+            for (nested, output) in [
+                ("nested-width", "output-width"),
+                ("nested-height", "output-height"),
+            ] {
+                if let Some(OptionValue::Int(size)) = options.get(output) {
+                    let scaled = (*size as f32 * factor).round() as i64;
+                    options.insert(nested.to_string(), OptionValue::Int(scaled));
+                }
+            }
+            options
+                .entry(FILTER_OPTION.to_string())
+                .or_insert_with(|| OptionValue::String(SUPERSAMPLE_FILTER.to_string()));
+        }
+    }
+
+    /// Merges `upper` over `self` for `compose`: `upper`'s set fields win,
+    /// maps merge per key, and `unset` and `disableBaseEnv` accumulate.
+    fn overlay(&mut self, upper: Self) {
+        // Destructured so a new field can't be forgotten here
+        let Self {
+            compose: _,
+            monitor,
+            binary,
+            use_hdr,
+            use_wsi,
+            allow_hdr_without_wsi,
+            vrr,
+            immediate_flips_with_vrr,
+            options,
+            options_when_hdr,
+            options_when_vrr,
+            environment,
+            unset,
+            game_env,
+            game_unset,
+            touch_mode,
+            tablet,
+            rotation,
+            rotation_swaps_resolution,
+            disable_color_management,
+            env_file,
+            quiet_child,
+            xwayland_count,
+            hide_cursor_delay,
+            cursor_scale_height,
+            sdr_nits,
+            hdr_max_nits,
+            notify_on_exit,
+            log_sessions,
+            window_title,
+            locale,
+            timezone,
+            skip_gamescope,
+            supersample,
+            filter,
+            window_mode,
+            drm_mode,
+            rlimits,
+            wrapper,
+            wrapper_target,
+            systemd_scope,
+            scope_properties,
+            env_priority,
+            disable_base_env,
+            proton_version,
+            compat_data_path,
+            compat_client_install_path,
+            gamescope_log,
+            pre_hook,
+            post_hook,
+            pre_timeout,
+            post_timeout,
+            hook_failure,
+            duplicate_keys,
+        } = upper;
+
+        fn or<T>(lower: &mut Option<T>, upper: Option<T>) {
+            if upper.is_some() {
+                *lower = upper;
+            }
+        }
+        or(&mut self.monitor, monitor);
+        or(&mut self.binary, binary);
+        or(&mut self.use_hdr, use_hdr);
+        or(&mut self.use_wsi, use_wsi);
+        or(&mut self.allow_hdr_without_wsi, allow_hdr_without_wsi);
+        or(&mut self.vrr, vrr);
+        or(&mut self.immediate_flips_with_vrr, immediate_flips_with_vrr);
+        or(&mut self.touch_mode, touch_mode);
+        or(&mut self.tablet, tablet);
+        or(&mut self.rotation, rotation);
+        or(
+            &mut self.rotation_swaps_resolution,
+            rotation_swaps_resolution,
+        );
+        or(&mut self.disable_color_management, disable_color_management);
+        or(&mut self.env_file, env_file);
+        or(&mut self.quiet_child, quiet_child);
+        or(&mut self.xwayland_count, xwayland_count);
+        or(&mut self.hide_cursor_delay, hide_cursor_delay);
+        or(&mut self.cursor_scale_height, cursor_scale_height);
+        or(&mut self.sdr_nits, sdr_nits);
+        or(&mut self.hdr_max_nits, hdr_max_nits);
+        or(&mut self.notify_on_exit, notify_on_exit);
+        or(&mut self.log_sessions, log_sessions);
+        or(&mut self.window_title, window_title);
+        or(&mut self.locale, locale);
+        or(&mut self.timezone, timezone);
+        or(&mut self.skip_gamescope, skip_gamescope);
+        or(&mut self.supersample, supersample);
+        or(&mut self.filter, filter);
+        or(&mut self.window_mode, window_mode);
+        or(&mut self.drm_mode, drm_mode);
+        or(&mut self.wrapper_target, wrapper_target);
+        or(&mut self.env_priority, env_priority);
+        or(&mut self.systemd_scope, systemd_scope);
+        self.scope_properties.extend(scope_properties);
+        or(&mut self.proton_version, proton_version);
+        or(&mut self.compat_data_path, compat_data_path);
+        or(
+            &mut self.compat_client_install_path,
+            compat_client_install_path,
+        );
+        or(&mut self.gamescope_log, gamescope_log);
+        or(&mut self.pre_hook, pre_hook);
+        or(&mut self.post_hook, post_hook);
+        or(&mut self.pre_timeout, pre_timeout);
+        or(&mut self.post_timeout, post_timeout);
+        or(&mut self.hook_failure, hook_failure);
+
+        self.options.extend(options);
+        self.options_when_hdr.extend(options_when_hdr);
+        self.options_when_vrr.extend(options_when_vrr);
+        self.environment.extend(environment);
+        self.unset.extend(unset);
+        self.game_env.extend(game_env);
+        self.game_unset.extend(game_unset);
+        self.disable_base_env.extend(disable_base_env);
+        self.rlimits.extend(rlimits);
+        if !wrapper.is_empty() {
+            self.wrapper = wrapper;
+        }
+        self.duplicate_keys.extend(duplicate_keys);
+    }
+
+    /// Typed fields that are set, paired with the gamescope option each maps to.
+    fn typed_option_keys(&self) -> Vec<(&'static str, &'static str)> {
+        [
+            (
+                "touchMode",
+                "default-touch-mode",
+                self.touch_mode.is_some() || self.tablet.unwrap_or(false),
+            ),
+            ("rotation", "force-orientation", self.rotation.is_some()),
+            (
+                "disableColorManagement",
+                "disable-color-management",
+                self.disable_color_management.is_some(),
+            ),
+            (
+                "xwaylandCount",
+                "xwayland-count",
+                self.xwayland_count.is_some(),
+            ),
+            ("sdrNits", SDR_NITS_OPTION, self.sdr_nits.is_some()),
+            (
+                "hdrMaxNits",
+                HDR_MAX_NITS_OPTION,
+                self.hdr_max_nits.is_some(),
+            ),
+            (
+                "windowTitle",
+                WINDOW_TITLE_OPTION,
+                self.window_title.is_some(),
+            ),
+            (
+                "hideCursorDelay",
+                "hide-cursor-delay",
+                self.hide_cursor_delay.is_some(),
+            ),
+            (
+                "cursorScaleHeight",
+                "cursor-scale-height",
+                self.cursor_scale_height.is_some(),
+            ),
+            ("drmMode", DRM_MODE_OPTION, self.drm_mode.is_some()),
+            ("filter", FILTER_OPTION, self.filter.is_some()),
+            ("windowMode", FULLSCREEN_OPTION, self.window_mode.is_some()),
+            ("windowMode", BORDERLESS_OPTION, self.window_mode.is_some()),
+            ("vrr", "adaptive-sync", self.vrr.is_some()),
+            ("supersample", "nested-width", self.supersample.is_some()),
+            ("supersample", "nested-height", self.supersample.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, _, set)| *set)
+        .map(|(field, option, _)| (field, option))
+        .collect()
+    }
+
+    /// Whether monitor-derived output dimensions should be swapped for rotation.
+    fn swaps_resolution(&self) -> bool {
+        self.rotation_swaps_resolution.unwrap_or(false) && matches!(self.rotation, Some(90 | 270))
+    }
+}
+
+/// Maps clockwise rotation degrees to gamescope's `--force-orientation` values.
+fn rotation_orientation(degrees: u16) -> Option<&'static str> {
+    match degrees {
+        0 => Some("normal"),
+        90 => Some("right"),
+        180 => Some("upsidedown"),
+        270 => Some("left"),
+        _ => None,
+    }
+}
+
+impl Layer for ProfilesConfig {
+    fn overlay(&mut self, upper: Self) {
+        self.profiles.extend(upper.profiles);
+        self.aliases.extend(upper.aliases);
+        if upper.default_profile.is_some() {
+            self.default_profile = upper.default_profile;
+        }
+    }
+
+    fn parse(content: &str, path: &Path) -> Result<Self> {
+        let mut config: Self = parse_yaml(content, path)?;
+        for dup in duplicate_keys(content) {
+            if let [root, profile, block, key] = dup.as_slice() {
+                if root == "profiles" && (block == "options" || block == "environment") {
+                    if let Some(def) = config.profiles.get_mut(profile) {
+                        def.duplicate_keys.push(format!("{}.{}", block, key));
+                    }
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl ProfilesConfig {
+    pub fn default_path() -> PathBuf {
+        MonitorsConfig::config_dir().join("config.yaml")
+    }
+
+    /// System-wide profiles file, layered under the user's.
+    pub fn system_path() -> PathBuf {
+        Path::new(SYSTEM_CONFIG_DIR).join("config.yaml")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read: {}", path.display()))?;
+        Self::parse(&content, path)
+    }
+
+    /// Loads each file on top of the previous ones, lowest precedence first.
+    pub fn load_layers(layers: &[PathBuf]) -> Result<Self> {
+        load_layers(layers)
+    }
+
+    /// Loads the `layers` below `path`, the file a command edits, so its
+    /// edits can be checked in context. Empty when none of them exists.
+    pub fn load_below(layers: &[PathBuf], path: &Path) -> Result<Self> {
+        let below = match layers.iter().position(|layer| layer == path) {
+            Some(index) => &layers[..index],
+            None => layers,
+        };
+        if below.iter().any(|layer| layer.is_file()) {
+            load_layers(below)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// `upper` overlaid on a copy of `self`, as when loading layers.
+    pub fn overlaid(&self, upper: &Self) -> Self {
+        let mut merged = self.clone();
+        merged.overlay(upper.clone());
+        merged
+    }
+
+    fn get(&self, name: &str) -> Result<&ProfileDef> {
+        let name = self.canonical_name(name)?;
+        self.profiles
+            .get(name)
+            .with_context(|| format!("Unknown profile '{}'", name))
+    }
+
+    /// The profile with its `compose` fragments merged in: fragments left to
+    /// right (each composed itself first), then the profile's own settings.
+    pub fn composed(&self, name: &str) -> Result<ProfileDef> {
+        self.composed_traced(name, &mut Trace::default())
+    }
+
+    /// `composed`, recording each fragment and what it changed in `trace`.
+    pub fn composed_traced(&self, name: &str, trace: &mut Trace) -> Result<ProfileDef> {
+        self.compose_from(name, &mut Vec::new(), trace)
+    }
+
+    fn compose_from(
+        &self,
+        name: &str,
+        chain: &mut Vec<String>,
+        trace: &mut Trace,
+    ) -> Result<ProfileDef> {
+        let name = self.canonical_name(name)?;
+        if chain.iter().any(|seen| seen == name) {
+            chain.push(name.to_string());
+            bail!("Compose cycle detected: {}", chain.join(" -> "));
+        }
+        let profile = self.get(name)?;
+        if profile.compose.is_empty() {
+            return Ok(profile.clone());
+        }
+
+        trace.record("compose", || {
+            format!("'{}' composes {}", name, profile.compose.join(", "))
+        });
+        chain.push(name.to_string());
+        let mut merged = ProfileDef::default();
+        for fragment in &profile.compose {
+            if self.canonical_name(fragment).is_err() {
+                bail!("Profile '{}' composes unknown profile '{}'", name, fragment);
+            }
+            let before = trace.is_on().then(|| merged.clone());
+            merged.overlay(self.compose_from(fragment, chain, trace)?);
+            if let Some(before) = before {
+                let fields = changed_fields(&before, &merged);
+                trace.record("compose", || {
+                    format!(
+                        "'{}': '{}' sets {}",
+                        name,
+                        fragment,
+                        describe_fields(&fields)
+                    )
+                });
+            }
+        }
+        chain.pop();
+
+        let before = trace.is_on().then(|| merged.clone());
+        merged.overlay(profile.clone());
+        merged.compose.clear();
+        if let Some(before) = before {
+            let fields = changed_fields(&before, &merged);
+            trace.record("compose", || {
+                format!(
+                    "'{}': its own settings set {}",
+                    name,
+                    describe_fields(&fields)
+                )
+            });
+        }
+        Ok(merged)
+    }
+
+    /// Follows aliases until a real profile name is reached. Direct profile
+    /// names win over aliases of the same name.
+    pub fn canonical_name<'a>(&'a self, name: &'a str) -> Result<&'a str> {
+        let mut current = name;
+        let mut chain = vec![name];
+
+        while !self.profiles.contains_key(current) {
+            let Some(target) = self.aliases.get(current) else {
+                if current == name {
+                    bail!("Unknown profile '{}'", name);
+                }
+                bail!(
+                    "Alias '{}' points at unknown profile '{}'",
+                    chain[chain.len() - 2],
+                    current
+                );
+            };
+            if chain.contains(&target.as_str()) {
+                chain.push(target);
+                bail!("Alias cycle detected: {}", chain.join(" -> "));
+            }
+            chain.push(target);
+            current = target;
+        }
+
+        Ok(current)
+    }
+
+    /// Profiles whose `monitor` isn't defined, as (profile, monitor) sorted by profile.
+    pub fn missing_monitor_refs<'a>(
+        &'a self,
+        monitors: &MonitorsConfig,
+    ) -> Vec<(&'a str, &'a str)> {
+        let mut missing: Vec<_> = self
+            .profiles
+            .iter()
+            .filter_map(|(name, profile)| {
+                let mon_name = profile.monitor.as_deref()?;
+                (!monitors.monitors.contains_key(mon_name)).then_some((name.as_str(), mon_name))
+            })
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    /// Like `missing_monitor_refs`, but following `compose`: profiles whose
+    /// monitor, set on them or taken from a fragment, isn't defined, as
+    /// (profile, monitor, whether the profile sets it itself) sorted by
+    /// profile. Profiles that fail to compose are left to `validate`.
+    pub fn dead_monitor_refs(&self, monitors: &MonitorsConfig) -> Vec<(String, String, bool)> {
+        let mut dead: Vec<_> = self
+            .profiles
+            .iter()
+            .filter_map(|(name, profile)| {
+                let monitor = self.composed(name).ok()?.monitor?;
+                (!monitors.monitors.contains_key(&monitor)).then(|| {
+                    let own = profile.monitor.as_ref() == Some(&monitor);
+                    (name.clone(), monitor, own)
+                })
+            })
+            .collect();
+        dead.sort();
+        dead
+    }
+
+    /// Checks aliases, `compose` chains and each profile's settings; monitor
+    /// references are checked against the monitors by the caller.
+    pub fn validate(&self) -> Result<()> {
+        for alias in self.aliases.keys() {
+            self.canonical_name(alias)?;
+        }
+
+        for name in self.profiles.keys() {
+            self.composed(name)?;
+        }
+
+        // Validate each profile
+        for (name, profile) in &self.profiles {
+            // Validate environment variable names (both set and unset)
+            validate_env_var_names(name, profile.environment.keys(), &profile.unset)?;
+            profile.validate(name)?;
+
+            // Note: We don't deduplicate unset vars because env_remove() is idempotent.
+            // Duplicate entries in the config are harmless and removing them adds complexity.
+        }
+        Ok(())
+    }
+
+    /// Writes the config back out, replacing the file atomically.
+    ///
+    /// Comments in the original file are not preserved.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize profiles")?;
+        write_atomic(path, &yaml)
+    }
+
+    fn names(&self) -> Vec<&String> {
+        let mut names: Vec<_> = self.profiles.keys().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Replaces `path` with `content` through a temporary file and a rename, so
+/// an interrupted write can't leave a half-written config behind.
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, content).with_context(|| format!("Failed to write: {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace: {}", path.display()))
+}
+
+// ============================================================================
+// Value Types
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Int(i) => write!(f, "{}", i),
+            // f64's Display already omits a trailing `.0` for integral values
+            Self::Float(x) => write!(f, "{}", x),
+            Self::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum EnvValue {
+    Int(i64),
+    String(String),
+}
+
+impl std::fmt::Display for EnvValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{}", i),
+            Self::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+// ============================================================================
+// Resolution Trace
+// ============================================================================
+
+/// Sink for `--trace`: every resolution decision as a stage and a message,
+/// in order. The default trace is off and records nothing; messages are
+/// built lazily, so threading an off trace through resolution costs nothing.
+#[derive(Debug, Default)]
+pub struct Trace {
+    steps: Option<Vec<(&'static str, String)>>,
+}
+
+impl Trace {
+    /// A trace that records.
+    pub fn on() -> Self {
+        Self {
+            steps: Some(Vec::new()),
+        }
+    }
+
+    pub fn record(&mut self, stage: &'static str, message: impl FnOnce() -> String) {
+        if let Some(steps) = &mut self.steps {
+            steps.push((stage, message()));
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.steps.is_some()
+    }
+
+    pub fn steps(&self) -> &[(&'static str, String)] {
+        self.steps.as_deref().unwrap_or_default()
+    }
+
+    /// One numbered `stage: message` line per step.
+    pub fn render(&self) -> String {
+        self.steps()
+            .iter()
+            .enumerate()
+            .map(|(i, (stage, message))| format!("{:03} {}: {}\n", i + 1, stage, message))
+            .collect()
+    }
+}
+
+/// Records each option `after` sets, changes or drops relative to `before`.
+fn trace_option_changes(
+    trace: &mut Trace,
+    stage: &'static str,
+    before: &HashMap<String, OptionValue>,
+    after: &HashMap<String, OptionValue>,
+) {
+    if !trace.is_on() {
+        return;
+    }
+    let mut keys: Vec<_> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        match (before.get(key), after.get(key)) {
+            (None, Some(new)) => trace.record(stage, || format!("{} = {}", key, new)),
+            (Some(old), Some(new)) if old != new => {
+                trace.record(stage, || format!("{} = {} (was {})", key, new, old))
+            }
+            (Some(old), None) => trace.record(stage, || format!("{} removed (was {})", key, old)),
+            _ => {}
+        }
+    }
+}
+
+fn describe_fields(fields: &[String]) -> String {
+    if fields.is_empty() {
+        "nothing new".to_string()
+    } else {
+        fields.join(", ")
+    }
+}
+
+/// The top-level fields (and keys of map fields) that differ between two
+/// profile definitions, for tracing what a `compose` step changed.
+fn changed_fields(before: &ProfileDef, after: &ProfileDef) -> Vec<String> {
+    let as_map = |def: &ProfileDef| {
+        let mut map = match serde_yaml::to_value(def) {
+            Ok(serde_yaml::Value::Mapping(map)) => map,
+            _ => serde_yaml::Mapping::new(),
+        };
+        // Left out when empty, but still diffed key by key
+        for field in ["options", "environment"] {
+            map.entry(field.into())
+                .or_insert_with(|| serde_yaml::Mapping::new().into());
+        }
+        map
+    };
+    let (before, after) = (as_map(before), as_map(after));
+    let key_name = |key: &serde_yaml::Value| key.as_str().unwrap_or("?").to_string();
+
+    let mut changed = Vec::new();
+    for (key, value) in &after {
+        match (before.get(key), value) {
+            (Some(serde_yaml::Value::Mapping(old)), serde_yaml::Value::Mapping(new)) => {
+                for (sub, sub_value) in new {
+                    if old.get(sub) != Some(sub_value) {
+                        changed.push(format!("{}.{}", key_name(key), key_name(sub)));
+                    }
+                }
+            }
+            (Some(old), new) if old == new => {}
+            _ => changed.push(key_name(key)),
+        }
+    }
+    changed.sort();
+    changed
+}
+
+// ============================================================================
+// Combined Configuration
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub monitors: MonitorsConfig,
+    pub profiles: ProfilesConfig,
+}
+
+impl Config {
+    /// Loads the user's files on top of `monitors.yaml`/`config.yaml` in
+    /// `system_dir`, so packaged defaults can be overridden per-user.
+    #[cfg(test)]
+    pub fn load(system_dir: &Path, monitors_path: &Path, profiles_path: &Path) -> Result<Self> {
+        Self::load_layers(
+            &[
+                system_dir.join("monitors.yaml"),
+                monitors_path.to_path_buf(),
+            ],
+            &[system_dir.join("config.yaml"), profiles_path.to_path_buf()],
+        )
+    }
+
+    /// Like `load`, but with any number of layers per file, lowest
+    /// precedence first (see `WAYSCOPE_CONFIG_PATH`).
+    pub fn load_layers(monitors: &[PathBuf], profiles: &[PathBuf]) -> Result<Self> {
+        let monitors: MonitorsConfig = load_layers(monitors)?;
+        let profiles: ProfilesConfig = load_layers(profiles)?;
+
+        for (name, monitor) in &monitors.monitors {
+            monitor.validate(name)?;
+        }
+        profiles.validate()?;
+
+        // Validate monitor references exist
+        if let Some((name, mon_name)) = profiles.missing_monitor_refs(&monitors).first() {
+            bail!(
+                "Profile '{}' references unknown monitor '{}' (run 'wayscope prune' to fix)",
+                name,
+                mon_name
+            );
+        }
+
+        Ok(Self { monitors, profiles })
+    }
+
+    /// Combines profile settings with monitor config into a ready-to-execute profile.
+    pub fn resolve_profile(&self, name: &str) -> Result<ResolvedProfile> {
+        self.resolve_profile_on(name, None)
+    }
+
+    /// Whether `name` can only resolve with a monitor chosen for it: the
+    /// profile names none and no monitor is primary.
+    pub fn lacks_monitor(&self, name: &str) -> bool {
+        self.monitors.default_monitor().is_err()
+            && self
+                .profiles
+                .composed(name)
+                .is_ok_and(|profile| profile.monitor.is_none())
+    }
+
+    /// The `envFile` profile `name` reads, with `~` expanded.
+    pub fn env_file(&self, name: &str) -> Option<PathBuf> {
+        let path = self.profiles.composed(name).ok()?.env_file?;
+        Some(expand_tilde(&path))
+    }
+
+    /// `resolve_profile` on `monitor` (when given) instead of the profile's
+    /// own monitor or the primary.
+    pub fn resolve_profile_on(&self, name: &str, monitor: Option<&str>) -> Result<ResolvedProfile> {
+        self.resolve_profile_traced(name, monitor, &mut Trace::default())
+    }
+
+    /// `resolve_profile_on`, recording each decision in `trace` (`--trace`).
+    pub fn resolve_profile_traced(
+        &self,
+        requested: &str,
+        monitor: Option<&str>,
+        trace: &mut Trace,
+    ) -> Result<ResolvedProfile> {
+        let name = self.profiles.canonical_name(requested)?;
+        if name != requested {
+            trace.record("alias", || {
+                format!("'{}' resolves to '{}'", requested, name)
+            });
+        }
+        let profile = &self.profiles.composed_traced(name, trace)?;
+
+        let chosen = monitor;
+        let (monitor_name, monitor) = match monitor.or(profile.monitor.as_deref()) {
+            Some(n) => (n.to_string(), self.monitors.get(n)?),
+            None => {
+                let (n, m) = self.monitors.default_monitor()?;
+                (n.clone(), m)
+            }
+        };
+        trace.record("monitor", || {
+            let how = match (chosen, &profile.monitor) {
+                (Some(_), _) => "chosen for this launch",
+                (None, Some(_)) => "set by the profile",
+                (None, None) => "the primary monitor",
+            };
+            format!("'{}' ({})", monitor_name, how)
+        });
+
+        let mut options = base_options(monitor);
+        trace_option_changes(trace, "monitor options", &HashMap::new(), &options);
+        if profile.swaps_resolution() {
+            options.insert(
+                "output-width".to_string(),
+                OptionValue::Int(i64::from(monitor.height)),
+            );
+            options.insert(
+                "output-height".to_string(),
+                OptionValue::Int(i64::from(monitor.width)),
+            );
+            trace.record("rotation", || {
+                format!(
+                    "rotationSwapsResolution swaps the output to {}x{}",
+                    monitor.height, monitor.width
+                )
+            });
+        }
+        let mut config_warnings = Vec::new();
+        // Capability-gated options apply after (and over) the unconditional ones
+        let conditional = [
+            (monitor.hdr, &profile.options_when_hdr),
+            (
+                monitor.vrr && profile.vrr != Some(false),
+                &profile.options_when_vrr,
+            ),
+        ];
+        if trace.is_on() {
+            let [(hdr, when_hdr), (vrr, when_vrr)] = &conditional;
+            for (block, active, opts, why) in [
+                ("optionsWhenHdr", *hdr, when_hdr, "monitor hdr"),
+                (
+                    "optionsWhenVrr",
+                    *vrr,
+                    when_vrr,
+                    "monitor vrr and profile vrr not false",
+                ),
+            ] {
+                if !opts.is_empty() {
+                    trace.record("conditional", || {
+                        format!(
+                            "{} is {} ({}: {})",
+                            block,
+                            if active { "active" } else { "inactive" },
+                            why,
+                            active
+                        )
+                    });
+                }
+            }
+        }
+        let before = trace.is_on().then(|| options.clone());
+        let active = conditional
+            .into_iter()
+            .filter(|(supported, _)| *supported)
+            .map(|(_, opts)| opts);
+        // Options the profile sets itself rather than inheriting from the monitor
+        let mut own_options = HashSet::new();
+        for block in std::iter::once(&profile.options).chain(active) {
+            // Deprecated names first, so the current name wins when both are set
+            let mut entries: Vec<_> = block.iter().collect();
+            entries.sort_by_key(|(key, _)| current_option_name(key).is_none());
+            for (key, value) in entries {
+                let key = match current_option_name(key) {
+                    Some(current) if block.contains_key(current) => {
+                        config_warnings.push(format!(
+                            "Profile '{}': both '{}' and its deprecated name '{}' are set, using '{}'",
+                            name, current, key, current
+                        ));
+                        continue;
+                    }
+                    Some(current) => {
+                        config_warnings.push(format!(
+                            "Profile '{}': option '{}' is deprecated, using '{}' instead",
+                            name, key, current
+                        ));
+                        current
+                    }
+                    None => key.as_str(),
+                };
+                own_options.insert(key);
+                options.insert(key.to_string(), value.clone());
+            }
+        }
+        if let Some(before) = before {
+            trace_option_changes(trace, "options", &before, &options);
+        }
+        for key in &profile.duplicate_keys {
+            config_warnings.push(format!(
+                "Profile '{}': {} is set more than once; using the last value",
+                name, key
+            ));
+        }
+        for (field, option) in profile.typed_option_keys() {
+            let raw_set = profile
+                .options
+                .keys()
+                .any(|key| current_option_name(key).unwrap_or(key) == option);
+            if raw_set {
+                config_warnings.push(format!(
+                    "Profile '{}': both '{}' and options.{} are set, using '{}'",
+                    name, field, option, field
+                ));
+            }
+        }
+        config_warnings.sort();
+        let before = trace.is_on().then(|| options.clone());
+        if let Some(OptionValue::String(backend)) = options.get_mut("backend") {
+            *backend = normalize_backend(backend);
+        }
+        profile.apply_typed_options(&mut options);
+        if let Some(before) = before {
+            trace_option_changes(trace, "typed fields", &before, &options);
+        }
+        let before = trace.is_on().then(|| options.clone());
+
+        // Adaptive sync needs a VRR-capable monitor; turning it off always works
+        match profile.vrr {
+            Some(true) if !monitor.vrr => config_warnings.push(format!(
+                "Profile '{}': vrr requested but monitor '{}' doesn't set 'vrr: true'; ignoring it",
+                name, monitor_name
+            )),
+            Some(vrr) => {
+                options.insert("adaptive-sync".to_string(), OptionValue::Bool(vrr));
+                own_options.insert("adaptive-sync");
+            }
+            None => {}
+        }
+
+        // The two flip models can fight under VRR; drop immediate flips if asked to
+        if profile.immediate_flips_with_vrr == Some(false) && vrr_with_immediate_flips(&options) {
+            options.remove("immediate-flips");
+        }
+        // VRR monitors pair them by default, which is no reason to warn; left
+        // unresolved only when the profile turns both on itself
+        let sets_both = ["adaptive-sync", "immediate-flips"]
+            .iter()
+            .all(|flag| own_options.contains(flag));
+        let immediate_flips_with_vrr = profile
+            .immediate_flips_with_vrr
+            .or((!sets_both).then_some(true));
+
+        // gamescope takes a sharpness for any filter but only the upscalers use it
+        let filter = match options.get(FILTER_OPTION) {
+            Some(OptionValue::String(value)) => Some(value.trim().to_ascii_lowercase()),
+            _ => LEGACY_UPSCALING_OPTIONS
+                .iter()
+                .find(|(option, _)| options.get(*option) == Some(&OptionValue::Bool(true)))
+                .map(|(_, filter)| filter.as_str().to_string()),
+        };
+        for (option, filters) in SHARPNESS_OPTIONS {
+            let applies = filters
+                .iter()
+                .any(|f| filter.as_deref() == Some(f.as_str()));
+            if options.contains_key(*option) && !applies {
+                let names: Vec<_> = filters.iter().map(|f| f.as_str()).collect();
+                config_warnings.push(format!(
+                    "Profile '{}': {} only applies to the {} filter{}, but the filter is {}",
+                    name,
+                    option,
+                    names.join(" and "),
+                    if names.len() == 1 { "" } else { "s" },
+                    filter.as_deref().unwrap_or("not set")
+                ));
+            }
+        }
+
+        // A borderless window over the default fullscreen is up to gamescope
+        if WindowMode::of(&options).is_none() {
+            config_warnings.push(format!(
+                "Profile '{}': fullscreen and borderless are both on, and gamescope picks one unpredictably; set windowMode instead",
+                name
+            ));
+        }
+        for (timeout, hook, set) in [
+            (
+                "preTimeout",
+                "preHook",
+                profile.pre_timeout.is_some() && profile.pre_hook.is_none(),
+            ),
+            (
+                "postTimeout",
+                "postHook",
+                profile.post_timeout.is_some() && profile.post_hook.is_none(),
+            ),
+        ] {
+            if set {
+                config_warnings.push(format!(
+                    "Profile '{}': {} is set without a {}, so it does nothing",
+                    name, timeout, hook
+                ));
+            }
+        }
+
+        // Only the drm backend generates modes or picks among outputs
+        let drm_backend = options.get("backend") == Some(&OptionValue::String("drm".to_string()));
+        if monitor.has_fallback_outputs() && !drm_backend {
+            config_warnings.push(format!(
+                "Profile '{}': monitor '{}' lists several connectors, but only the drm backend falls back between them",
+                name, monitor_name
+            ));
+        }
+        if let Some(mode) = &profile.drm_mode {
+            if drm_backend {
+                options.insert(
+                    DRM_MODE_OPTION.to_string(),
+                    OptionValue::String(mode.clone()),
+                );
+            } else {
+                config_warnings.push(format!(
+                    "Profile '{}': drmMode only applies to the drm backend; ignoring it",
+                    name
+                ));
+            }
+        }
+        // vrr, immediateFlipsWithVrr and drmMode
+        if let Some(before) = before {
+            trace_option_changes(trace, "monitor-dependent fields", &before, &options);
+        }
+
+        // Env file entries come first so inline `environment` can override them
+        let mut user_env: HashMap<String, String> = match &profile.env_file {
+            Some(path) => load_env_file(path)
+                .with_context(|| format!("Profile '{}': failed to load envFile", name))?
+                .into_iter()
+                .collect(),
+            None => HashMap::new(),
+        };
+        if let Some(path) = &profile.env_file {
+            trace.record("envFile", || {
+                format!("{} gives {} variable(s)", path, user_env.len())
+            });
+        }
+        user_env.extend(
+            profile
+                .environment
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_string())),
+        );
+        // Profile variables resolve before the process environment
+        let before = trace.is_on().then(|| user_env.clone());
+        let missing = expand_env_refs(&mut user_env, |var| std::env::var(var).ok())
+            .with_context(|| format!("Profile '{}': invalid environment", name))?;
+        if let Some(before) = before {
+            let mut keys: Vec<_> = before.keys().collect();
+            keys.sort();
+            for key in keys {
+                let (template, value) = (&before[key], &user_env[key]);
+                if template != value {
+                    trace.record("interpolate", || {
+                        format!("environment.{}: '{}' -> '{}'", key, template, value)
+                    });
+                }
+            }
+        }
+        for (var, reference) in missing {
+            config_warnings.push(format!(
+                "Profile '{}': environment.{} references unset variable '{}'",
+                name, var, reference
+            ));
+        }
+
+        if let Some(locale) = profile.locale.as_deref() {
+            if !locale_looks_valid(locale) {
+                config_warnings.push(format!(
+                    "Profile '{}': locale '{}' doesn't look like xx_XX or xx_XX.UTF-8",
+                    name, locale
+                ));
+            }
+        }
+        if let Some(level) = profile.gamescope_log.as_deref() {
+            if !GAMESCOPE_LOG_LEVELS.contains(&level) {
+                config_warnings.push(format!(
+                    "Profile '{}': unknown gamescopeLog '{}' (expected one of: {})",
+                    name,
+                    level,
+                    GAMESCOPE_LOG_LEVELS.join(", ")
+                ));
+            }
+        }
+        if let Some(tz) = profile.timezone.as_deref() {
+            if tz.is_empty() || tz.contains(char::is_whitespace) {
+                config_warnings.push(format!(
+                    "Profile '{}': timezone '{}' doesn't look like e.g. Europe/Berlin",
+                    name, tz
+                ));
+            }
+        }
+
+        // Expanded here rather than in apply_typed_options: needs the profile env
+        let mut expand = |field: &str, template: &str| -> Result<String> {
+            let (value, missing) = interpolate(template, |var| {
+                user_env
+                    .get(var)
+                    .cloned()
+                    .or_else(|| std::env::var(var).ok())
+            })?;
+            for var in missing {
+                config_warnings.push(format!(
+                    "Profile '{}': {} references unset variable '{}'",
+                    name, field, var
+                ));
+            }
+            if value != template {
+                trace.record("interpolate", || {
+                    format!("{}: '{}' -> '{}'", field, template, value)
+                });
+            }
+            Ok(value)
+        };
+        if let Some(template) = &profile.window_title {
+            let title = expand("windowTitle", template)?;
+            options.insert(WINDOW_TITLE_OPTION.to_string(), OptionValue::String(title));
+        }
+        let mut game_env = BTreeMap::new();
+        for (key, value) in &profile.game_env {
+            let value = expand(&format!("gameEnv.{}", key), &value.to_string())?;
+            game_env.insert(key.clone(), value);
+        }
+        let mut expand_path = |field: &str, template: &Option<String>| -> Result<Option<String>> {
+            template
+                .as_deref()
+                .map(|t| Ok(expand_tilde(&expand(field, t)?).display().to_string()))
+                .transpose()
+        };
+        let compat_data_path = expand_path("compatDataPath", &profile.compat_data_path)?;
+        let compat_client_install_path = expand_path(
+            "compatClientInstallPath",
+            &profile.compat_client_install_path,
+        )?;
+        let icc_profile = expand_path("iccProfile", &monitor.iccProfile)?;
+
+        let (binary, binary_source) = resolve_binary(
+            profile.binary.as_deref(),
+            std::env::var(GAMESCOPE_BIN_ENV).ok(),
+        );
+        trace.record("binary", || format!("'{}' ({})", binary, binary_source));
+        for warning in &config_warnings {
+            trace.record("warning", || warning.clone());
+        }
+
+        let resolved = ResolvedProfile {
+            name: name.to_string(),
+            monitor_name,
+            binary,
+            binary_source,
+            use_hdr: profile.use_hdr.unwrap_or(monitor.hdr),
+            use_wsi: profile.use_wsi.unwrap_or(true),
+            allow_hdr_without_wsi: profile.allow_hdr_without_wsi.unwrap_or(false),
+            immediate_flips_with_vrr,
+            rotation: profile.rotation,
+            disable_color_management: profile.disable_color_management.unwrap_or(false),
+            quiet_child: profile.quiet_child.unwrap_or(false),
+            notify_on_exit: profile.notify_on_exit.unwrap_or(false),
+            log_sessions: profile.log_sessions.unwrap_or(false),
+            skip_gamescope: profile.skip_gamescope.unwrap_or(false),
+            locale: profile.locale.clone(),
+            timezone: profile.timezone.clone(),
+            gamescope_log: profile.gamescope_log.clone(),
+            pre_hook: profile.pre_hook.clone(),
+            post_hook: profile.post_hook.clone(),
+            pre_timeout: profile.pre_timeout,
+            post_timeout: profile.post_timeout,
+            hook_failure: profile.hook_failure.unwrap_or_default(),
+            supersample: profile.supersample,
+            rlimits: profile.rlimits.clone(),
+            proton_version: profile.proton_version.clone(),
+            compat_data_path,
+            compat_client_install_path,
+            icc_profile,
+            wrapper: profile.wrapper.clone(),
+            wrapper_target: profile.wrapper_target.unwrap_or_default(),
+            env_priority: profile.env_priority.unwrap_or_default(),
+            base_env: BaseEnvGroup::ALL
+                .into_iter()
+                .filter(|group| !profile.disable_base_env.contains(group))
+                .collect(),
+            systemd_scope: profile
+                .systemd_scope
+                .unwrap_or(!profile.scope_properties.is_empty()),
+            scope_properties: profile.scope_properties.clone(),
+            config_warnings,
+            options,
+            user_env,
+            unset_vars: profile.unset.clone(),
+            game_env,
+            game_unset: profile.game_unset.clone(),
+        };
+        resolved.validate_environment()?;
+        Ok(resolved)
+    }
+
+    /// Resolved options that differ from the monitor defaults alone, i.e.
+    /// what the profile actually changes.
+    pub fn overridden_options(
+        &self,
+        profile: &ResolvedProfile,
+    ) -> Result<HashMap<String, OptionValue>> {
+        let defaults = base_options(self.monitors.get(&profile.monitor_name)?);
+        Ok(profile
+            .options
+            .iter()
+            .filter(|(key, value)| defaults.get(*key) != Some(*value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    /// Name and summary for each profile. Profiles that fail to resolve are
+    /// listed with the error, or left out with `skip_broken`.
+    pub fn list_profiles(&self, skip_broken: bool) -> Vec<(String, String)> {
+        self.profiles
+            .names()
+            .into_iter()
+            .filter_map(|name| match self.resolve_profile(name) {
+                Ok(p) => {
+                    let summary = format!(
+                        "monitor={} HDR={} WSI={}",
+                        p.monitor_name, p.use_hdr, p.use_wsi
+                    );
+                    // p.name is already owned; no need to clone `name` again
+                    Some((p.name, summary))
+                }
+                Err(_) if skip_broken => None,
+                Err(e) => Some((name.to_string(), format!("(error: {:#})", e))),
+            })
+            .collect()
+    }
+
+    /// Every profile's flat keys as `profile.key=value` lines. A profile
+    /// that fails to resolve gets a `profile.error=...` line instead, or is
+    /// left out with `skip_broken`, as in `list_profiles`.
+    pub fn flat_profiles(&self, skip_broken: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        for name in self.profiles.names() {
+            match self.resolve_profile(name) {
+                Ok(profile) => lines.extend(
+                    profile
+                        .flatten()
+                        .into_iter()
+                        .map(|(key, value)| format!("{}.{}={}", name, key, value)),
+                ),
+                Err(_) if skip_broken => {}
+                // One line per profile, whatever the error says
+                Err(e) => lines.push(format!(
+                    "{}.error={}",
+                    name,
+                    format!("{:#}", e).replace('\n', " ")
+                )),
+            }
+        }
+        lines
+    }
+
+    /// Monitors that no resolvable profile targets and that aren't the primary, sorted.
+    pub fn unused_monitors(&self) -> Vec<&str> {
+        let used: HashSet<String> = self
+            .profiles
+            .names()
+            .into_iter()
+            .filter_map(|name| self.resolve_profile(name).ok())
+            .map(|p| p.monitor_name)
+            .collect();
+
+        let mut unused: Vec<_> = self
+            .monitors
+            .monitors
+            .iter()
+            .filter(|(name, mon)| !mon.primary && !used.contains(name.as_str()))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        unused.sort();
+        unused
+    }
+
+    /// Alias -> target pairs, sorted by alias.
+    pub fn list_aliases(&self) -> Vec<(&str, &str)> {
+        let mut aliases: Vec<_> = self
+            .profiles
+            .aliases
+            .iter()
+            .map(|(alias, target)| (alias.as_str(), target.as_str()))
+            .collect();
+        aliases.sort();
+        aliases
+    }
+}
+
+/// gamescope option renames as (old, current). Add new renames here.
+const DEPRECATED_OPTIONS: &[(&str, &str)] = &[("fsr-sharpness", "sharpness")];
+
+/// The current name for a deprecated option key, if it was renamed.
+fn current_option_name(key: &str) -> Option<&'static str> {
+    DEPRECATED_OPTIONS
+        .iter()
+        .find_map(|(old, current)| (*old == key).then_some(*current))
+}
+
+/// Maps X11-style connector names to the DRM names gamescope matches
+/// `--prefer-output` against. DRM and wlroots names pass through unchanged.
+///
+/// - `HDMI-1` (modesetting) -> `HDMI-A-1`
+/// - `DisplayPort-0` (xf86-video-amdgpu, zero-based) -> `DP-1`
+/// - `HDMI-A-0` (xf86-video-amdgpu, zero-based) -> `HDMI-A-1`
+pub fn gamescope_output_name(connector: &str) -> String {
+    let numbered = |prefix: &str| {
+        connector
+            .strip_prefix(prefix)
+            .and_then(|n| n.parse::<u32>().ok())
+    };
+    if let Some(n) = numbered("DisplayPort-") {
+        format!("DP-{}", n + 1)
+    } else if let Some(0) = numbered("HDMI-A-") {
+        "HDMI-A-1".to_string()
+    } else if let Some(n) = numbered("HDMI-") {
+        format!("HDMI-A-{}", n)
+    } else {
+        connector.to_string()
+    }
+}
+
+/// Whether both `adaptive-sync` and `immediate-flips` are enabled.
+pub fn vrr_with_immediate_flips(options: &HashMap<String, OptionValue>) -> bool {
+    let enabled = |flag: &str| options.get(flag) == Some(&OptionValue::Bool(true));
+    enabled("adaptive-sync") && enabled("immediate-flips")
+}
+
+/// Sensible gamescope defaults derived from monitor specs; every profile's
+/// options start from these.
+pub fn base_options(monitor: &MonitorDef) -> HashMap<String, OptionValue> {
+    let mut opts = HashMap::with_capacity(10);
+
+    opts.insert(
+        "backend".to_string(),
+        OptionValue::String("sdl".to_string()),
+    );
+    opts.insert("fade-out-duration".to_string(), OptionValue::Int(200));
+    opts.insert(FULLSCREEN_OPTION.to_string(), OptionValue::Bool(true));
+    opts.insert("immediate-flips".to_string(), OptionValue::Bool(true));
+    opts.insert(
+        "nested-refresh".to_string(),
+        // gamescope takes whole Hz; 59.94 becomes 60
+        OptionValue::Int(monitor.refreshRate.round() as i64),
+    );
+    opts.insert(
+        "output-height".to_string(),
+        OptionValue::Int(i64::from(monitor.height)),
+    );
+    opts.insert(
+        "output-width".to_string(),
+        OptionValue::Int(i64::from(monitor.width)),
+    );
+    opts.insert("rt".to_string(), OptionValue::Bool(true));
+
+    if monitor.vrr {
+        opts.insert("adaptive-sync".to_string(), OptionValue::Bool(true));
+    }
+    if let Some(output) = monitor.gamescope_output() {
+        opts.insert("prefer-output".to_string(), OptionValue::String(output));
+    }
+
+    opts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Config {
+        let monitors_yaml = r#"
+monitors:
+  main:
+    width: 2560
+    height: 1440
+    refreshRate: 165
+    vrr: true
+    hdr: true
+    primary: true
+  tv:
+    width: 3840
+    height: 2160
+    refreshRate: 120
+    hdr: true
+"#;
+
+        let profiles_yaml = r#"
+profiles:
+  default:
+    useHDR: true
+    useWSI: true
+    options:
+      backend: sdl
+
+  autohdr:
+    useWSI: false
+    allowHDRWithoutWSI: true
+
+  couch:
+    monitor: tv
+    useHDR: true
+    binary: /custom/gamescope
+
+  performance:
+    useHDR: false
+    options:
+      fsr-upscaling: true
+
+  handheld:
+    tablet: true
+
+  vertical:
+    rotation: 90
+    rotationSwapsResolution: true
+
+aliases:
+  perf: performance
+  p: perf
+"#;
+
+        let monitors: MonitorsConfig = serde_yaml::from_str(monitors_yaml).unwrap();
+        let profiles: ProfilesConfig = serde_yaml::from_str(profiles_yaml).unwrap();
+        Config { monitors, profiles }
+    }
+
+    #[test]
+    fn test_resolve_default_profile() {
+        let config = test_config();
+        let profile = config.resolve_profile("default").unwrap();
+
+        assert_eq!(profile.name, "default");
+        assert_eq!(profile.monitor_name, "main");
+        assert!(profile.use_hdr);
+        assert!(profile.use_wsi);
+        assert_eq!(profile.binary, "gamescope");
+    }
+
+    #[test]
+    fn test_resolve_with_custom_monitor() {
+        let config = test_config();
+        let profile = config.resolve_profile("couch").unwrap();
+
+        assert_eq!(profile.monitor_name, "tv");
+        assert_eq!(profile.binary, "/custom/gamescope");
+        assert!(matches!(
+            profile.options.get("output-width"),
+            Some(OptionValue::Int(3840))
+        ));
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        let env = Some("/opt/gamescope".to_string());
+
+        assert_eq!(
+            resolve_binary(Some("/custom/gamescope"), env.clone()),
+            ("/custom/gamescope".to_string(), BinarySource::Profile)
+        );
+        assert_eq!(
+            resolve_binary(None, env),
+            ("/opt/gamescope".to_string(), BinarySource::Environment)
+        );
+        assert_eq!(
+            resolve_binary(None, None),
+            ("gamescope".to_string(), BinarySource::Default)
+        );
+        assert_eq!(
+            resolve_binary(None, Some(String::new())),
+            ("gamescope".to_string(), BinarySource::Default)
+        );
+    }
+
+    #[test]
+    fn test_profile_selection_precedence() {
+        let env = Some("from-env".to_string());
+
+        assert_eq!(
+            select_profile(Some("flag"), env.clone(), Some("cfg")),
+            ("flag".to_string(), ProfileSource::Flag)
+        );
+        assert_eq!(
+            select_profile(None, env, Some("cfg")),
+            ("from-env".to_string(), ProfileSource::Environment)
+        );
+        assert_eq!(
+            select_profile(None, Some(String::new()), Some("cfg")),
+            ("cfg".to_string(), ProfileSource::Config)
+        );
+        assert_eq!(
+            select_profile(None, None, None),
+            ("default".to_string(), ProfileSource::Default)
+        );
+    }
+
+    #[test]
+    fn test_hdr_defaults_to_monitor() {
+        let config = test_config();
+        let profile = config.resolve_profile("autohdr").unwrap();
+        assert!(profile.use_hdr); // Inherits from monitor.hdr
+    }
+
+    #[test]
+    fn test_allow_hdr_without_wsi_flag() {
+        let config = test_config();
+        let profile = config.resolve_profile("autohdr").unwrap();
+        assert!(profile.allow_hdr_without_wsi);
+        assert!(profile.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_wsi_defaults_to_true() {
+        let config = test_config();
+        let profile = config.resolve_profile("performance").unwrap();
+        assert!(profile.use_wsi);
+    }
+
+    #[test]
+    fn test_tablet_sets_touch_passthrough() {
+        let config = test_config();
+        let profile = config.resolve_profile("handheld").unwrap();
+        assert!(matches!(
+            profile.options.get("default-touch-mode"),
+            Some(OptionValue::Int(4))
+        ));
+    }
+
+    #[test]
+    fn test_touch_mode_overrides_tablet() {
+        let profile: ProfileDef = serde_yaml::from_str("touchMode: 1\ntablet: true").unwrap();
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert!(matches!(
+            options.get("default-touch-mode"),
+            Some(OptionValue::Int(1))
+        ));
+    }
+
+    #[test]
+    fn test_touch_mode_validation() {
+        let valid: ProfileDef = serde_yaml::from_str("touchMode: 4").unwrap();
+        assert!(valid.validate("test").is_ok());
+
+        let invalid: ProfileDef = serde_yaml::from_str("touchMode: 5").unwrap();
+        let err = invalid.validate("test").unwrap_err().to_string();
+        assert!(err.contains("invalid touchMode 5"));
+    }
+
+    #[test]
+    fn test_rotation_swaps_resolution() {
+        let config = test_config();
+        let profile = config.resolve_profile("vertical").unwrap();
+
+        assert_eq!(profile.rotation, Some(90));
+        assert_eq!(
+            profile.options.get("force-orientation"),
+            Some(&OptionValue::String("right".to_string()))
+        );
+        assert_eq!(profile.options["output-width"], OptionValue::Int(1440));
+        assert_eq!(profile.options["output-height"], OptionValue::Int(2560));
+    }
+
+    #[test]
+    fn test_rotation_without_swap_keeps_resolution() {
+        let profile: ProfileDef = serde_yaml::from_str("rotation: 270").unwrap();
+        assert!(!profile.swaps_resolution());
+
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert_eq!(
+            options["force-orientation"],
+            OptionValue::String("left".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rotation_validation() {
+        let valid: ProfileDef = serde_yaml::from_str("rotation: 180").unwrap();
+        assert!(valid.validate("test").is_ok());
+
+        let invalid: ProfileDef = serde_yaml::from_str("rotation: 45").unwrap();
+        let err = invalid.validate("test").unwrap_err().to_string();
+        assert!(err.contains("invalid rotation 45"));
+    }
+
+    #[test]
+    fn test_resolve_alias_chain() {
+        let config = test_config();
+        let profile = config.resolve_profile("p").unwrap();
+        assert_eq!(profile.name, "performance");
+        assert_eq!(
+            config.list_aliases(),
+            vec![("p", "perf"), ("perf", "performance")]
+        );
+    }
+
+    #[test]
+    fn test_alias_cycle_detected() {
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles: {}\naliases:\n  a: b\n  b: a").unwrap();
+        let err = profiles.canonical_name("a").unwrap_err().to_string();
+        assert!(err.contains("cycle"));
+        assert!(err.contains("a -> b -> a"));
+    }
+
+    #[test]
+    fn test_compose_merges_fragments_in_order() {
+        let yaml = r#"
+profiles:
+  base:
+    useWSI: true
+    quietChild: true
+    options: {backend: sdl, fsr-upscaling: true}
+    environment: {A: base, B: base}
+    unset: [X]
+  hdr:
+    useHDR: true
+    options: {backend: wayland}
+    environment: {B: hdr}
+  tv:
+    monitor: tv
+    tablet: true
+    useHDR: false
+    unset: [Y]
+  couch:
+    compose: [base, hdr, tv]
+    environment: {A: couch}
+  nested:
+    compose: [couch]
+    quietChild: false
+"#;
+        let profiles: ProfilesConfig = serde_yaml::from_str(yaml).unwrap();
+        let couch = profiles.composed("couch").unwrap();
+        assert!(couch.compose.is_empty());
+        assert_eq!(couch.monitor.as_deref(), Some("tv"));
+        assert_eq!(couch.use_wsi, Some(true));
+        // Later fragments win over earlier ones
+        assert_eq!(couch.use_hdr, Some(false));
+        assert_eq!(
+            couch.options["backend"],
+            OptionValue::String("wayland".to_string())
+        );
+        assert_eq!(couch.options["fsr-upscaling"], OptionValue::Bool(true));
+        assert_eq!(couch.environment["B"].to_string(), "hdr");
+        // The profile's own settings win over every fragment
+        assert_eq!(couch.environment["A"].to_string(), "couch");
+        assert_eq!(couch.unset, vec!["X", "Y"]);
+        assert_eq!(couch.tablet, Some(true));
+
+        // Fragments compose recursively
+        let nested = profiles.composed("nested").unwrap();
+        assert_eq!(nested.quiet_child, Some(false));
+        assert_eq!(nested.environment["A"].to_string(), "couch");
+
+        // Profiles without compose are unchanged
+        assert_eq!(profiles.composed("hdr").unwrap(), profiles.profiles["hdr"]);
+    }
+
+    #[test]
+    fn test_compose_flags_can_be_turned_off() {
+        let mut config = test_config();
+        config.profiles = serde_yaml::from_str(
+            r#"
+profiles:
+  handheld: {tablet: true, rotation: 90, rotationSwapsResolution: true, allowHDRWithoutWSI: true}
+  desk:
+    compose: [handheld]
+    tablet: false
+    rotationSwapsResolution: false
+    allowHDRWithoutWSI: false
+"#,
+        )
+        .unwrap();
+
+        let handheld = config.resolve_profile("handheld").unwrap();
+        assert_eq!(handheld.options["default-touch-mode"], OptionValue::Int(4));
+        assert_eq!(handheld.options["output-width"], OptionValue::Int(1440));
+
+        // The profile's own false wins over the fragment's true
+        let desk = config.resolve_profile("desk").unwrap();
+        assert!(!desk.options.contains_key("default-touch-mode"));
+        assert_eq!(desk.options["output-width"], OptionValue::Int(2560));
+        assert!(!desk.allow_hdr_without_wsi);
+    }
+
+    #[test]
+    fn test_compose_errors() {
+        let profiles: ProfilesConfig = serde_yaml::from_str(
+            "profiles:\n  a: {compose: [b]}\n  b: {compose: [c]}\n  c: {compose: [a]}\n",
+        )
+        .unwrap();
+        let err = profiles.composed("a").unwrap_err().to_string();
+        assert!(
+            err.contains("Compose cycle detected: a -> b -> c -> a"),
+            "{}",
+            err
+        );
+
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  a: {compose: [missing]}\n").unwrap();
+        let err = profiles.composed("a").unwrap_err().to_string();
+        assert!(err.contains("Profile 'a' composes unknown profile 'missing'"));
+    }
+
+    #[test]
+    fn test_config_load_rejects_compose_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let monitors_path = dir.path().join("monitors.yaml");
+        let profiles_path = dir.path().join("config.yaml");
+        std::fs::write(
+            &monitors_path,
+            "monitors:\n  main: {width: 1920, height: 1080, refreshRate: 60, primary: true}\n",
+        )
+        .unwrap();
+        std::fs::write(&profiles_path, "profiles:\n  loop: {compose: [loop]}\n").unwrap();
+
+        let err = Config::load(&dir.path().join("etc"), &monitors_path, &profiles_path)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("loop -> loop"), "{}", err);
+    }
+
+    #[test]
+    fn test_alias_to_unknown_profile() {
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles: {}\naliases:\n  bp: bigpicture").unwrap();
+        let err = profiles.canonical_name("bp").unwrap_err().to_string();
+        assert!(err.contains("Alias 'bp' points at unknown profile 'bigpicture'"));
+    }
+
+    #[test]
+    fn test_disable_color_management_option() {
+        let profile: ProfileDef = serde_yaml::from_str("disableColorManagement: true").unwrap();
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert_eq!(options["disable-color-management"], OptionValue::Bool(true));
+
+        let profile: ProfileDef = serde_yaml::from_str("disableColorManagement: false").unwrap();
+        let mut options = HashMap::new();
+        options.insert(
+            "disable-color-management".to_string(),
+            OptionValue::Bool(true),
+        );
+        profile.apply_typed_options(&mut options);
+        assert_eq!(
+            options["disable-color-management"],
+            OptionValue::Bool(false)
+        );
+    }
+
+    #[test]
+    fn test_xwayland_count() {
+        let profile: ProfileDef = serde_yaml::from_str("xwaylandCount: 2").unwrap();
+        assert!(profile.validate("test").is_ok());
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert_eq!(options["xwayland-count"], OptionValue::Int(2));
+
+        for invalid in ["xwaylandCount: 0", "xwaylandCount: 5"] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            let err = profile.validate("test").unwrap_err().to_string();
+            assert!(err.contains("invalid xwaylandCount"));
+        }
+    }
+
+    #[test]
+    fn test_cursor_fields() {
+        let profile: ProfileDef =
+            serde_yaml::from_str("hideCursorDelay: 3000\ncursorScaleHeight: 720").unwrap();
+        assert!(profile.validate("test").is_ok());
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert_eq!(options["hide-cursor-delay"], OptionValue::Int(3000));
+        assert_eq!(options["cursor-scale-height"], OptionValue::Int(720));
+
+        for (invalid, field) in [
+            ("hideCursorDelay: 3000000", "hideCursorDelay"),
+            ("cursorScaleHeight: 0", "cursorScaleHeight"),
+            ("cursorScaleHeight: 20000", "cursorScaleHeight"),
+        ] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            let err = profile.validate("test").unwrap_err().to_string();
+            assert!(err.contains(&format!("invalid {}", field)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_deprecated_option_renamed_with_warning() {
+        let mut config = test_config();
+        config
+            .profiles
+            .profiles
+            .get_mut("performance")
+            .unwrap()
+            .options
+            .insert("fsr-sharpness".to_string(), OptionValue::Int(5));
+
+        let profile = config.resolve_profile("performance").unwrap();
+        assert_eq!(profile.options.get("sharpness"), Some(&OptionValue::Int(5)));
+        assert!(!profile.options.contains_key("fsr-sharpness"));
+
+        let warnings = profile.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'fsr-sharpness' is deprecated, using 'sharpness'"));
+    }
+
+    #[test]
+    fn test_deprecated_option_loses_to_current_name() {
+        // Each fresh map has its own hash seed and so its own iteration order
+        for _ in 0..16 {
+            let mut config = test_config();
+            let profile = config.profiles.profiles.get_mut("performance").unwrap();
+            profile.filter = Some(Filter::Fsr);
+            profile.options = HashMap::from([
+                ("fsr-sharpness".to_string(), OptionValue::Int(5)),
+                ("sharpness".to_string(), OptionValue::Int(2)),
+            ]);
+
+            let profile = config.resolve_profile("performance").unwrap();
+            assert_eq!(profile.options.get("sharpness"), Some(&OptionValue::Int(2)));
+            assert!(!profile.options.contains_key("fsr-sharpness"));
+            assert_eq!(
+                profile.warnings(),
+                vec!["Profile 'performance': both 'sharpness' and its deprecated name 'fsr-sharpness' are set, using 'sharpness'"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_typed_field_collides_with_raw_option() {
+        let cases = [
+            ("touchMode: 1", "default-touch-mode", OptionValue::Int(1)),
+            ("tablet: true", "default-touch-mode", OptionValue::Int(4)),
+            (
+                "rotation: 90",
+                "force-orientation",
+                OptionValue::String("right".to_string()),
+            ),
+            (
+                "disableColorManagement: true",
+                "disable-color-management",
+                OptionValue::Bool(true),
+            ),
+            ("xwaylandCount: 2", "xwayland-count", OptionValue::Int(2)),
+            ("sdrNits: 203", SDR_NITS_OPTION, OptionValue::Int(203)),
+            (
+                "hdrMaxNits: 1000",
+                HDR_MAX_NITS_OPTION,
+                OptionValue::Int(1000),
+            ),
+            (
+                "windowTitle: typed",
+                WINDOW_TITLE_OPTION,
+                OptionValue::String("typed".to_string()),
+            ),
+        ];
+
+        for (yaml, option, expected) in cases {
+            let mut config = test_config();
+            let mut profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+            profile
+                .options
+                .insert(option.to_string(), OptionValue::String("raw".to_string()));
+            config.profiles.profiles.insert("dup".to_string(), profile);
+
+            let resolved = config.resolve_profile("dup").unwrap();
+            // Typed field wins
+            assert_eq!(resolved.options[option], expected, "{}", yaml);
+            assert_eq!(resolved.config_warnings.len(), 1, "{}", yaml);
+            assert!(resolved.config_warnings[0].contains(&format!("options.{}", option)));
+        }
+
+        // No warning without a collision
+        let resolved = test_config().resolve_profile("vertical").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+    }
+
+    #[test]
+    fn test_current_option_name() {
+        assert_eq!(current_option_name("fsr-sharpness"), Some("sharpness"));
+        assert_eq!(current_option_name("sharpness"), None);
+    }
+
+    #[test]
+    fn test_nit_fields() {
+        let profile: ProfileDef = serde_yaml::from_str("sdrNits: 203\nhdrMaxNits: 1000").unwrap();
+        assert!(profile.validate("test").is_ok());
+        let mut options = HashMap::new();
+        profile.apply_typed_options(&mut options);
+        assert_eq!(options[SDR_NITS_OPTION], OptionValue::Int(203));
+        assert_eq!(options[HDR_MAX_NITS_OPTION], OptionValue::Int(1000));
+
+        for invalid in ["sdrNits: 0", "hdrMaxNits: 10001"] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            assert!(profile.validate("test").is_err());
+        }
+    }
+
+    #[test]
+    fn test_binary_validation() {
+        for invalid in ["binary: ''", "binary: '   '", "binary: 'gamescope --rt'"] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            assert!(profile.validate("test").is_err(), "{}", invalid);
+        }
+
+        let profile: ProfileDef = serde_yaml::from_str("binary: /usr/bin/gamescope").unwrap();
+        assert!(profile.validate("test").is_ok());
+
+        // An existing path containing spaces is accepted
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("my gamescope");
+        std::fs::write(&path, "").unwrap();
+        let profile = ProfileDef {
+            binary: Some(path.display().to_string()),
+            ..serde_yaml::from_str("{}").unwrap()
+        };
+        assert!(profile.validate("test").is_ok());
+    }
+
+    #[test]
+    fn test_options_when_capability() {
+        let yaml = r#"
+profiles:
+  adaptive:
+    options:
+      hdr-sdr-content-nits: 100
+    optionsWhenHdr:
+      hdr-sdr-content-nits: 203
+      hdr-itm-enabled: true
+    optionsWhenVrr:
+      framerate-limit: 0
+"#;
+        let mut config = test_config();
+        config.profiles = serde_yaml::from_str(yaml).unwrap();
+
+        // main reports both HDR and VRR
+        let profile = config.resolve_profile("adaptive").unwrap();
+        assert_eq!(
+            profile.options.get("hdr-sdr-content-nits"),
+            Some(&OptionValue::Int(203))
+        );
+        assert_eq!(
+            profile.options.get("hdr-itm-enabled"),
+            Some(&OptionValue::Bool(true))
+        );
+        assert_eq!(
+            profile.options.get("framerate-limit"),
+            Some(&OptionValue::Int(0))
+        );
+
+        // Neither capability: only the unconditional options remain
+        for monitor in config.monitors.monitors.values_mut() {
+            monitor.hdr = false;
+            monitor.vrr = false;
+        }
+        let profile = config.resolve_profile("adaptive").unwrap();
+        assert_eq!(
+            profile.options.get("hdr-sdr-content-nits"),
+            Some(&OptionValue::Int(100))
+        );
+        assert!(!profile.options.contains_key("hdr-itm-enabled"));
+        assert!(!profile.options.contains_key("framerate-limit"));
+    }
+
+    #[test]
+    fn test_overridden_options() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile
+            .options
+            .insert("rt".to_string(), OptionValue::Bool(true));
+        profile
+            .options
+            .insert("fullscreen".to_string(), OptionValue::Bool(false));
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        let overrides = config.overridden_options(&resolved).unwrap();
+        // Same value as the monitor default: hidden
+        assert!(!overrides.contains_key("rt"));
+        assert!(!overrides.contains_key("backend"));
+        assert_eq!(overrides.get("fullscreen"), Some(&OptionValue::Bool(false)));
+    }
+
+    #[test]
+    fn test_skip_gamescope_field() {
+        let mut config = test_config();
+        assert!(
+            !config
+                .resolve_profile("performance")
+                .unwrap()
+                .skip_gamescope
+        );
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.skip_gamescope = Some(true);
+        assert!(
+            config
+                .resolve_profile("performance")
+                .unwrap()
+                .skip_gamescope
+        );
+    }
+
+    #[test]
+    fn test_locale_looks_valid() {
+        for valid in [
+            "ja_JP",
+            "ja_JP.UTF-8",
+            "de_DE.utf8@euro",
+            "C",
+            "C.UTF-8",
+            "en",
+            "ast_ES",
+        ] {
+            assert!(locale_looks_valid(valid), "{}", valid);
+        }
+        for invalid in ["", "japanese", "ja-JP", "ja_jp", "ja_JP.UTF 8", "JA_JP"] {
+            assert!(!locale_looks_valid(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_locale_and_timezone() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.locale = Some("ja_JP.UTF-8".to_string());
+        profile.timezone = Some("Asia/Tokyo".to_string());
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+        let env: HashMap<_, _> = resolved.environment().into_iter().collect();
+        assert_eq!(env["LANG"], "ja_JP.UTF-8");
+        assert_eq!(env["LC_ALL"], "ja_JP.UTF-8");
+        assert_eq!(env["TZ"], "Asia/Tokyo");
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.locale = Some("japanese".to_string());
+        profile.timezone = Some("Asia Tokyo".to_string());
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(resolved.config_warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_immediate_flips_with_vrr() {
+        let mut config = test_config();
+        let warns =
+            |p: &ResolvedProfile| p.warnings().iter().any(|w| w.contains("immediate-flips"));
+
+        // main has VRR: the stock pairing of both flags is kept quietly
+        let profile = config.resolve_profile("default").unwrap();
+        assert!(profile.options.contains_key("immediate-flips"));
+        assert!(profile.options.contains_key("adaptive-sync"));
+        assert!(!warns(&profile));
+
+        // Turned on by the profile itself: surfaced (not a config warning)
+        let def = config.profiles.profiles.get_mut("performance").unwrap();
+        def.vrr = Some(true);
+        def.options
+            .insert("immediate-flips".to_string(), OptionValue::Bool(true));
+        let profile = config.resolve_profile("performance").unwrap();
+        assert!(warns(&profile));
+        assert!(profile.config_warnings.is_empty());
+
+        // Explicitly kept: same flags, no warning
+        let def = config.profiles.profiles.get_mut("performance").unwrap();
+        def.immediate_flips_with_vrr = Some(true);
+        let profile = config.resolve_profile("performance").unwrap();
+        assert!(profile.options.contains_key("immediate-flips"));
+        assert!(!warns(&profile));
+
+        // Dropped under VRR
+        let def = config.profiles.profiles.get_mut("default").unwrap();
+        def.immediate_flips_with_vrr = Some(false);
+        let profile = config.resolve_profile("default").unwrap();
+        assert!(!profile.options.contains_key("immediate-flips"));
+        assert!(profile.options.contains_key("adaptive-sync"));
+        assert!(!warns(&profile));
+
+        // tv has no VRR: immediate flips stay, nothing to warn about
+        let profile = config.resolve_profile("couch").unwrap();
+        assert!(profile.options.contains_key("immediate-flips"));
+        assert!(!warns(&profile));
+    }
+
+    #[test]
+    fn test_supersample() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.supersample = Some(1.5);
+
+        // main is 2560x1440
+        let resolved = config.resolve_profile("performance").unwrap();
+        // Nested above output is the point, so no "usually a mistake" warning
+        assert!(resolved.warnings().is_empty());
+        assert_eq!(
+            resolved.options.get("nested-width"),
+            Some(&OptionValue::Int(3840))
+        );
+        assert_eq!(
+            resolved.options.get("nested-height"),
+            Some(&OptionValue::Int(2160))
+        );
+        assert_eq!(
+            resolved.options.get("filter"),
+            Some(&OptionValue::String("linear".to_string()))
+        );
+
+        // Explicit nested size conflicts; an explicit filter is kept
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile
+            .options
+            .insert("nested-width".to_string(), OptionValue::Int(1920));
+        profile.options.insert(
+            "filter".to_string(),
+            OptionValue::String("pixel".to_string()),
+        );
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0].contains("'supersample' and options.nested-width"));
+        assert_eq!(
+            resolved.options.get("nested-width"),
+            Some(&OptionValue::Int(3840))
+        );
+        assert_eq!(
+            resolved.options.get("filter"),
+            Some(&OptionValue::String("pixel".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_window_mode() {
+        let mut config = test_config();
+        let flags = |config: &Config| {
+            let resolved = config.resolve_profile("performance").unwrap();
+            let on = |key| resolved.options.get(key) == Some(&OptionValue::Bool(true));
+            (
+                on(FULLSCREEN_OPTION),
+                on(BORDERLESS_OPTION),
+                resolved.config_warnings,
+            )
+        };
+        let set_mode = |config: &mut Config, mode| {
+            config
+                .profiles
+                .profiles
+                .get_mut("performance")
+                .unwrap()
+                .window_mode = mode;
+        };
+
+        // The monitor defaults are fullscreen
+        assert_eq!(flags(&config), (true, false, vec![]));
+        set_mode(&mut config, Some(WindowMode::Fullscreen));
+        assert_eq!(flags(&config), (true, false, vec![]));
+        set_mode(&mut config, Some(WindowMode::Borderless));
+        assert_eq!(flags(&config), (false, true, vec![]));
+        set_mode(&mut config, Some(WindowMode::Windowed));
+        assert_eq!(flags(&config), (false, false, vec![]));
+
+        // Raw options lose to the typed field, with a warning
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile
+            .options
+            .insert(BORDERLESS_OPTION.to_string(), OptionValue::Bool(true));
+        assert_eq!(
+            flags(&config),
+            (
+                false,
+                false,
+                vec!["Profile 'performance': both 'windowMode' and options.borderless are set, using 'windowMode'".to_string()]
+            )
+        );
+
+        // Without it, borderless over the default fullscreen is a conflict
+        set_mode(&mut config, None);
+        let (fullscreen, borderless, warnings) = flags(&config);
+        assert!(fullscreen && borderless);
+        assert_eq!(
+            warnings,
+            vec!["Profile 'performance': fullscreen and borderless are both on, and gamescope picks one unpredictably; set windowMode instead"]
+        );
+
+        let profile: ProfileDef = serde_yaml::from_str("windowMode: borderless").unwrap();
+        assert_eq!(profile.window_mode, Some(WindowMode::Borderless));
+        assert!(serde_yaml::from_str::<ProfileDef>("windowMode: maximized").is_err());
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.filter = Some(Filter::Nis);
+        profile
+            .options
+            .insert("nis-sharpness".to_string(), OptionValue::Int(10));
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+        assert_eq!(
+            resolved.options.get(FILTER_OPTION),
+            Some(&OptionValue::String("nis".to_string()))
+        );
+
+        // The typed field wins over options.filter; sharpness outside fsr/nis warns
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.filter = Some(Filter::Fsr);
+        profile.options.insert(
+            "filter".to_string(),
+            OptionValue::String("linear".to_string()),
+        );
+        profile
+            .options
+            .insert("sharpness".to_string(), OptionValue::Int(5));
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.options.get(FILTER_OPTION),
+            Some(&OptionValue::String("fsr".to_string()))
+        );
+        assert_eq!(
+            resolved.config_warnings,
+            vec![
+                "Profile 'performance': both 'filter' and options.filter are set, using 'filter'",
+                "Profile 'performance': nis-sharpness only applies to the nis filter, but the filter is fsr",
+            ]
+        );
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.filter = None;
+        profile.options.remove("filter");
+        profile.options.remove("nis-sharpness");
+        profile.options.remove("fsr-upscaling");
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.config_warnings,
+            vec![
+                "Profile 'performance': sharpness only applies to the fsr and nis filters, but the filter is not set"
+            ]
+        );
+
+        let err = serde_yaml::from_str::<ProfileDef>("filter: bicubic").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `bicubic`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_sharpness_follows_the_upscaler() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.options.remove("fsr-upscaling");
+        profile
+            .options
+            .insert("fsr-sharpness".to_string(), OptionValue::Int(5));
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.config_warnings,
+            vec![
+                "Profile 'performance': option 'fsr-sharpness' is deprecated, using 'sharpness' instead",
+                "Profile 'performance': sharpness only applies to the fsr and nis filters, but the filter is not set",
+            ]
+        );
+
+        // The typed filter and the legacy upscaling switches both count
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.filter = Some(Filter::Fsr);
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(resolved.config_warnings.len(), 1);
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.filter = None;
+        profile.options = HashMap::from([
+            ("nis-upscaling".to_string(), OptionValue::Bool(true)),
+            ("nis-sharpness".to_string(), OptionValue::Int(10)),
+        ]);
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+        assert!(!resolved.options.contains_key(FILTER_OPTION));
+    }
+
+    #[test]
+    fn test_drm_mode() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.drm_mode = Some("cvt".to_string());
+
+        // Default sdl backend: not emitted, warned about
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert!(!resolved.options.contains_key(DRM_MODE_OPTION));
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0].contains("drm backend"));
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.options.insert(
+            "backend".to_string(),
+            OptionValue::String("drm".to_string()),
+        );
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.options.get(DRM_MODE_OPTION),
+            Some(&OptionValue::String("cvt".to_string()))
+        );
+        assert!(resolved.config_warnings.is_empty());
+
+        let invalid: ProfileDef = serde_yaml::from_str("drmMode: gtf").unwrap();
+        let err = invalid.validate("test").unwrap_err().to_string();
+        assert!(err.contains("invalid drmMode 'gtf'"));
+    }
+
+    #[test]
+    fn test_vrr_toggle() {
+        let mut config = test_config();
+        for (name, monitor) in [("on-main", "main"), ("on-tv", "tv"), ("off", "main")] {
+            let profile: ProfileDef = serde_yaml::from_str(&format!(
+                "monitor: {}\nvrr: {}\noptionsWhenVrr: {{framerate-limit: 0}}\n",
+                monitor,
+                name != "off"
+            ))
+            .unwrap();
+            config.profiles.profiles.insert(name.to_string(), profile);
+        }
+
+        // VRR monitor: adaptive sync and the VRR-only options
+        let resolved = config.resolve_profile("on-main").unwrap();
+        assert_eq!(
+            resolved.options.get("adaptive-sync"),
+            Some(&OptionValue::Bool(true))
+        );
+        assert!(resolved.options.contains_key("framerate-limit"));
+        assert!(resolved.config_warnings.is_empty());
+
+        // Non-VRR monitor: not turned on, warned about
+        let resolved = config.resolve_profile("on-tv").unwrap();
+        assert!(!resolved.options.contains_key("adaptive-sync"));
+        assert!(!resolved.options.contains_key("framerate-limit"));
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0].contains("monitor 'tv' doesn't set 'vrr: true'"));
+
+        // Off on a VRR monitor: flag off, VRR-only options skipped
+        let resolved = config.resolve_profile("off").unwrap();
+        assert_eq!(
+            resolved.options.get("adaptive-sync"),
+            Some(&OptionValue::Bool(false))
+        );
+        assert!(!resolved.options.contains_key("framerate-limit"));
+    }
+
+    #[test]
+    fn test_systemd_scope() {
+        let mut config = test_config();
+        for (name, yaml) in [
+            ("plain", "{}"),
+            ("scoped", "systemdScope: true"),
+            (
+                "limited",
+                "scopeProperties: {MemoryMax: 8G, CPUWeight: \"50\"}",
+            ),
+            (
+                "off",
+                "systemdScope: false\nscopeProperties: {MemoryMax: 8G}",
+            ),
+        ] {
+            let profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+            profile.validate(name).unwrap();
+            config.profiles.profiles.insert(name.to_string(), profile);
+        }
+        let scoped = |name| config.resolve_profile(name).unwrap().systemd_scope;
+        assert!(!scoped("plain"));
+        assert!(scoped("scoped"));
+        // Properties imply a scope unless it's explicitly off
+        assert!(scoped("limited"));
+        assert!(!scoped("off"));
+
+        for invalid in [
+            "scopeProperties: {\"Memory Max\": 8G}",
+            "scopeProperties: {\"\": 8G}",
+            "scopeProperties: {MemoryMax: \"8G\\nCPUWeight=1\"}",
+        ] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            let err = profile.validate("test").unwrap_err().to_string();
+            assert!(err.contains("scopeProperties"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_disable_base_env() {
+        let yaml = r#"
+profiles:
+  nvidia:
+    disableBaseEnv: [amd]
+  native:
+    compose: [nvidia]
+    disableBaseEnv: [proton]
+"#;
+        let mut config = test_config();
+        config.profiles = serde_yaml::from_str(yaml).unwrap();
+
+        let nvidia = config.resolve_profile("nvidia").unwrap();
+        assert_eq!(
+            nvidia.base_env,
+            BTreeSet::from([BaseEnvGroup::Wayland, BaseEnvGroup::Proton])
+        );
+        let env: HashMap<_, _> = nvidia.environment().into_iter().collect();
+        assert!(!env.contains_key("AMD_VULKAN_ICD"));
+        assert_eq!(env["PROTON_ENABLE_WAYLAND"], "1");
+
+        // Composed profiles accumulate the disabled groups
+        let native = config.resolve_profile("native").unwrap();
+        assert_eq!(native.base_env, BTreeSet::from([BaseEnvGroup::Wayland]));
+
+        assert!(serde_yaml::from_str::<ProfileDef>("disableBaseEnv: [nvidia]").is_err());
+    }
+
+    #[test]
+    fn test_game_env() {
+        let mut config = test_config();
+        let yaml = r#"
+compose: [default]
+environment: {HUD_DIR: /opt/hud}
+gameEnv: {MANGOHUD_CONFIGFILE: "${HUD_DIR}/hud.conf", MANGOHUD: 1}
+gameUnset: [LD_PRELOAD]
+"#;
+        let profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+        config.profiles.profiles.insert("hud".to_string(), profile);
+
+        let resolved = config.resolve_profile("hud").unwrap();
+        assert_eq!(resolved.game_env["MANGOHUD"], "1");
+        assert_eq!(
+            resolved.game_env["MANGOHUD_CONFIGFILE"],
+            "/opt/hud/hud.conf"
+        );
+        assert_eq!(resolved.game_unset, vec!["LD_PRELOAD"]);
+        // Not part of gamescope's environment
+        assert!(!resolved
+            .environment()
+            .iter()
+            .any(|(k, _)| k.starts_with("MANGOHUD")));
+
+        let mut bad = ProfileDef::default();
+        bad.game_unset.push("STEAM_*".to_string());
+        let err = bad.validate("hud").unwrap_err().to_string();
+        assert!(err.contains("invalid gameUnset entry 'STEAM_*'"), "{}", err);
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut config = test_config();
+        config.profiles = serde_yaml::from_str(
+            r#"
+profiles:
+  base: {preHook: "pkill -STOP picom", preTimeout: 10, hookFailure: warn}
+  game: {compose: [base], postHook: "pkill -CONT picom", hookFailure: abort}
+  orphan: {postTimeout: 5}
+"#,
+        )
+        .unwrap();
+        config.profiles.validate().unwrap();
+
+        let game = config.resolve_profile("game").unwrap();
+        assert_eq!(game.pre_hook.as_deref(), Some("pkill -STOP picom"));
+        assert_eq!(game.pre_timeout, Some(10));
+        assert_eq!(game.post_hook.as_deref(), Some("pkill -CONT picom"));
+        assert_eq!(game.post_timeout, None);
+        // The profile's own policy wins over the fragment's
+        assert_eq!(game.hook_failure, HookFailure::Abort);
+
+        let orphan = config.resolve_profile("orphan").unwrap();
+        assert_eq!(
+            orphan.config_warnings,
+            vec!["Profile 'orphan': postTimeout is set without a postHook, so it does nothing"]
+        );
+
+        for (yaml, err) in [
+            (
+                "preTimeout: 0",
+                "invalid preTimeout 0 (expected at least 1 second)",
+            ),
+            ("postHook: \" \"", "postHook must not be empty"),
+        ] {
+            let profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+            let message = profile.validate("hooked").unwrap_err().to_string();
+            assert!(message.contains(err), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_env_priority() {
+        let mut config = test_config();
+        let yaml = "envPriority: base\nenvironment: {RADV_PERFTEST: gpl}\n";
+        let profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(profile.env_priority, Some(EnvPriority::Base));
+        config
+            .profiles
+            .profiles
+            .insert("strict".to_string(), profile);
+
+        let env: HashMap<_, _> = config
+            .resolve_profile("strict")
+            .unwrap()
+            .environment()
+            .into_iter()
+            .collect();
+        assert_eq!(env["RADV_PERFTEST"], "aco");
+
+        assert!(serde_yaml::from_str::<ProfileDef>("envPriority: gamescope").is_err());
+    }
 
-        let mut options = base_options(monitor);
-        for (key, value) in &profile.options {
-            options.insert(key.clone(), value.clone());
+    #[test]
+    fn test_backend_normalized_and_validated() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.options.insert(
+            "backend".to_string(),
+            OptionValue::String(" Wayland ".to_string()),
+        );
+        assert!(profile.validate("performance").is_ok());
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.options.get("backend"),
+            Some(&OptionValue::String("wayland".to_string()))
+        );
+
+        for backend in ["auto", "headless", "openvr"] {
+            let yaml = format!("options: {{backend: {}}}", backend);
+            let profile: ProfileDef = serde_yaml::from_str(&yaml).unwrap();
+            profile.validate("test").unwrap();
+        }
+
+        for yaml in [
+            "options: {backend: vulkan}",
+            "optionsWhenHdr: {backend: \"\"}",
+            "options: {backend: true}",
+        ] {
+            let profile: ProfileDef = serde_yaml::from_str(yaml).unwrap();
+            let err = profile.validate("test").unwrap_err().to_string();
+            assert!(err.contains("backend"), "{}", yaml);
+            assert!(
+                err.contains("expected one of: sdl, wayland, drm"),
+                "{}",
+                yaml
+            );
+        }
+    }
+
+    #[test]
+    fn test_rlimits() {
+        let profile: ProfileDef =
+            serde_yaml::from_str("rlimits: {nofile: 524288, memlock: unlimited}").unwrap();
+        assert!(profile.validate("test").is_ok());
+        assert_eq!(profile.rlimits["nofile"], RlimitValue::Limit(524288));
+        assert_eq!(profile.rlimits["memlock"], RlimitValue::Unlimited);
+        assert_eq!(
+            serde_yaml::to_string(&profile.rlimits).unwrap(),
+            "memlock: unlimited\nnofile: 524288\n"
+        );
+
+        let unknown: ProfileDef = serde_yaml::from_str("rlimits: {files: 1024}").unwrap();
+        let err = unknown.validate("test").unwrap_err().to_string();
+        assert!(err.contains("unknown rlimit 'files'"));
+
+        for invalid in ["rlimits: {nofile: -1}", "rlimits: {nofile: lots}"] {
+            assert!(
+                serde_yaml::from_str::<ProfileDef>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_steam_compat_fields() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.proton_version = Some("GE-Proton9-20".to_string());
+        profile.compat_data_path = Some("~/prefixes/${GAME}".to_string());
+        profile.compat_client_install_path = Some("${WAYSCOPE_TEST_UNSET_STEAM}/steam".to_string());
+        profile.environment.insert(
+            "GAME".to_string(),
+            EnvValue::String("elden-ring".to_string()),
+        );
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        let home = dirs::home_dir().unwrap();
+        let data = home.join("prefixes/elden-ring").display().to_string();
+        assert_eq!(resolved.compat_data_path.as_deref(), Some(data.as_str()));
+        assert_eq!(
+            resolved.compat_client_install_path.as_deref(),
+            Some("/steam")
+        );
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0]
+            .contains("compatClientInstallPath references unset variable"));
+
+        let env: HashMap<_, _> = resolved.environment().into_iter().collect();
+        assert_eq!(env["PROTON_VERSION"], "GE-Proton9-20");
+        assert_eq!(env["STEAM_COMPAT_DATA_PATH"], data);
+        assert_eq!(env["STEAM_COMPAT_CLIENT_INSTALL_PATH"], "/steam");
+
+        // Missing directories only warn at launch
+        assert!(resolved
+            .warnings()
+            .iter()
+            .any(|w| w.contains("compatDataPath") && w.contains("not an existing directory")));
+
+        let invalid: ProfileDef =
+            serde_yaml::from_str("compatDataPath: \"${unterminated\"").unwrap();
+        let err = invalid.validate("test").unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid compatDataPath"));
+    }
+
+    #[test]
+    fn test_icc_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let icc = dir.path().join("main.icc");
+        std::fs::write(&icc, "").unwrap();
+
+        let mut config = test_config();
+        config.monitors.monitors.get_mut("main").unwrap().iccProfile =
+            Some("${ICC_DIR}/main.icc".to_string());
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.environment.insert(
+            "ICC_DIR".to_string(),
+            EnvValue::String(dir.path().display().to_string()),
+        );
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        let path = icc.display().to_string();
+        assert_eq!(resolved.icc_profile.as_deref(), Some(path.as_str()));
+        // Never passed on, since gamescope can't load it
+        assert!(resolved
+            .options
+            .values()
+            .all(|value| !value.to_string().contains("main.icc")));
+        // Nor warned about at launch; doctor checks the path
+        std::fs::remove_file(&icc).unwrap();
+        assert!(resolved
+            .warnings()
+            .iter()
+            .all(|w| !w.contains("iccProfile")));
+
+        let monitors = &config.monitors.monitors;
+        assert_eq!(
+            monitors["main"].diff(&monitors["tv"]).last().unwrap().0,
+            "iccProfile"
+        );
+    }
+
+    #[test]
+    fn test_supersample_validation() {
+        for factor in ["1.0", "1.5", "2.0"] {
+            let valid: ProfileDef =
+                serde_yaml::from_str(&format!("supersample: {}", factor)).unwrap();
+            assert!(valid.validate("test").is_ok(), "{}", factor);
         }
 
-        let user_env = profile
+        let invalid: ProfileDef = serde_yaml::from_str("supersample: 2.5").unwrap();
+        let err = invalid.validate("test").unwrap_err().to_string();
+        assert!(err.contains("invalid supersample 2.5 (expected 1-2)"));
+        let invalid: ProfileDef = serde_yaml::from_str("supersample: 0.5").unwrap();
+        assert!(invalid.validate("test").is_err());
+    }
+
+    #[test]
+    fn test_gamescope_log() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.gamescope_log = Some("debug".to_string());
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+        let env: HashMap<_, _> = resolved.environment().into_iter().collect();
+        assert_eq!(env[GAMESCOPE_LOG_ENV], "debug");
+
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.gamescope_log = Some("verbose".to_string());
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0].contains("unknown gamescopeLog 'verbose'"));
+        // Still passed through; gamescope may know levels this list doesn't
+        let env: HashMap<_, _> = resolved.environment().into_iter().collect();
+        assert_eq!(env[GAMESCOPE_LOG_ENV], "verbose");
+    }
+
+    #[test]
+    fn test_duplicate_keys_warn() {
+        let yaml = r#"
+profiles:
+  dup:
+    options:
+      output-width: 1920
+      filter: fsr
+      output-width: 2560
+    environment:
+      MANGOHUD: 1
+      MANGOHUD: 0
+  clean:
+    options:
+      output-width: 1920
+"#;
+        let profiles = ProfilesConfig::parse(yaml, Path::new("config.yaml")).unwrap();
+        // Last value still wins, as before
+        assert_eq!(
+            profiles.profiles["dup"].options["output-width"],
+            OptionValue::Int(2560)
+        );
+        assert_eq!(
+            profiles.profiles["dup"].duplicate_keys,
+            ["options.output-width", "environment.MANGOHUD"]
+        );
+        assert!(profiles.profiles["clean"].duplicate_keys.is_empty());
+
+        let mut config = test_config();
+        config
+            .profiles
+            .profiles
+            .insert("dup".to_string(), profiles.profiles["dup"].clone());
+        let resolved = config.resolve_profile("dup").unwrap();
+        assert!(resolved
+            .config_warnings
+            .iter()
+            .any(|w| w.contains("options.output-width is set more than once")));
+        assert!(resolved
+            .config_warnings
+            .iter()
+            .any(|w| w.contains("environment.MANGOHUD is set more than once")));
+    }
+
+    #[test]
+    fn test_duplicate_keys_scan() {
+        let dups = duplicate_keys("a: 1\nb:\n  - x: 1\n    x: 2\na: 3\n");
+        assert_eq!(dups, [vec!["b", "0", "x"], vec!["a"]]);
+        // Invalid YAML is left for the real parser
+        assert!(duplicate_keys("a: [").is_empty());
+    }
+
+    #[test]
+    fn test_expand_env_refs() {
+        let mut env: HashMap<_, _> = [
+            ("ROOT", "/games"),
+            ("PREFIX", "${ROOT}/prefix"),
+            ("DERIVED", "${PREFIX}/sub:${HOME}"),
+            ("PATH", "${PATH}:/opt/bin"),
+            ("LOST", "${NOPE}x"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let process = |var: &str| match var {
+            "HOME" => Some("/home/me".to_string()),
+            "PATH" => Some("/usr/bin".to_string()),
+            "ROOT" => Some("/ignored".to_string()),
+            _ => None,
+        };
+
+        let missing = expand_env_refs(&mut env, process).unwrap();
+        let expanded = env;
+        // Profile variables win over the process environment
+        assert_eq!(expanded["PREFIX"], "/games/prefix");
+        assert_eq!(expanded["DERIVED"], "/games/prefix/sub:/home/me");
+        // A self-reference reads the process value
+        assert_eq!(expanded["PATH"], "/usr/bin:/opt/bin");
+        assert_eq!(expanded["LOST"], "x");
+        assert_eq!(missing, [("LOST".to_string(), "NOPE".to_string())]);
+    }
+
+    #[test]
+    fn test_expand_env_refs_cycle() {
+        let mut env: HashMap<_, _> = [("A", "${B}"), ("B", "x${C}"), ("C", "${A}"), ("D", "ok")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let err = expand_env_refs(&mut env, |_| None).unwrap_err().to_string();
+        assert_eq!(err, "environment reference cycle: A -> B -> C -> A");
+
+        let profile: ProfileDef =
+            serde_yaml::from_str("environment: {X: \"${Y}\", Y: \"${X}\"}").unwrap();
+        let err = format!("{:#}", profile.validate("loop").unwrap_err());
+        assert!(
+            err.contains("Profile 'loop': invalid environment"),
+            "{}",
+            err
+        );
+        assert!(err.contains("X -> Y -> X"), "{}", err);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let lookup = |var: &str| (var == "GAME").then(|| "Hades".to_string());
+        let (out, missing) = interpolate("${GAME} via wayscope", lookup).unwrap();
+        assert_eq!(out, "Hades via wayscope");
+        assert!(missing.is_empty());
+
+        let (out, missing) = interpolate("[${NOPE}] $HOME", lookup).unwrap();
+        assert_eq!(out, "[] $HOME");
+        assert_eq!(missing, ["NOPE"]);
+
+        assert!(interpolate("${GAME", lookup).is_err());
+        assert!(interpolate("${1BAD}", lookup).is_err());
+
+        // `$${` escapes a literal `${`, which isn't parsed further
+        let (out, missing) = interpolate("$${BAR:-x} ${GAME} $${", lookup).unwrap();
+        assert_eq!(out, "${BAR:-x} Hades ${");
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_environment_literal_interpolation() {
+        let profile: ProfileDef = serde_yaml::from_str(
+            r#"environment: {FOO: "$${BAR:-x}", SNIPPET: "echo $${HOME}/$${FOO}", REF: "${FOO}!"}"#,
+        )
+        .unwrap();
+        profile.validate("literal").unwrap();
+
+        let mut env: HashMap<_, _> = profile
             .environment
             .iter()
             .map(|(k, v)| (k.clone(), v.to_string()))
             .collect();
+        let missing = expand_env_refs(&mut env, |_| None).unwrap();
+        assert!(missing.is_empty());
+        assert_eq!(env["FOO"], "${BAR:-x}");
+        assert_eq!(env["SNIPPET"], "echo ${HOME}/${FOO}");
+        // The escaped value is substituted as is, not expanded again
+        assert_eq!(env["REF"], "${BAR:-x}!");
+    }
 
-        Ok(ResolvedProfile {
-            name: name.to_string(),
-            monitor_name,
-            binary: profile.binary.clone(),
-            use_hdr: profile.use_hdr.unwrap_or(monitor.hdr),
-            use_wsi: profile.use_wsi.unwrap_or(true),
-            options,
-            user_env,
-            unset_vars: profile.unset.clone(),
-        })
+    #[test]
+    fn test_window_title() {
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("performance").unwrap();
+        profile.window_title = Some("${WS_TITLE_GAME} (${WS_TITLE_UNSET})".to_string());
+        profile.environment.insert(
+            "WS_TITLE_GAME".to_string(),
+            EnvValue::String("Hades".to_string()),
+        );
+        profile.options.insert(
+            WINDOW_TITLE_OPTION.to_string(),
+            OptionValue::String("raw".to_string()),
+        );
+
+        let resolved = config.resolve_profile("performance").unwrap();
+        assert_eq!(
+            resolved.options[WINDOW_TITLE_OPTION],
+            OptionValue::String("Hades ()".to_string())
+        );
+        assert!(resolved
+            .warnings()
+            .iter()
+            .any(|w| w.contains("unset variable 'WS_TITLE_UNSET'")));
+
+        for invalid in ["windowTitle: '  '", "windowTitle: '${GAME'"] {
+            let profile: ProfileDef = serde_yaml::from_str(invalid).unwrap();
+            assert!(profile.validate("test").is_err());
+        }
     }
 
-    pub fn list_profiles(&self) -> Vec<(String, String)> {
-        self.profiles
-            .names()
-            .into_iter()
-            .filter_map(|name| {
-                self.resolve_profile(name).ok().map(|p| {
-                    let summary = format!(
-                        "monitor={} HDR={} WSI={}",
-                        p.monitor_name, p.use_hdr, p.use_wsi
-                    );
-                    // p.name is already owned; no need to clone `name` again
-                    (p.name, summary)
-                })
-            })
-            .collect()
+    #[test]
+    fn test_resolve_profile_on_monitor() {
+        let mut config = test_config();
+        assert!(!config.lacks_monitor("default"));
+        let resolved = config.resolve_profile_on("default", Some("tv")).unwrap();
+        assert_eq!(resolved.monitor_name, "tv");
+        // The chosen monitor wins over the profile's own
+        let resolved = config.resolve_profile_on("couch", Some("main")).unwrap();
+        assert_eq!(resolved.monitor_name, "main");
+        assert!(config.resolve_profile_on("default", Some("nope")).is_err());
+
+        config.monitors.monitors.get_mut("main").unwrap().primary = false;
+        assert!(config.lacks_monitor("default"));
+        assert!(!config.lacks_monitor("couch"));
+        let err = config.resolve_profile("default").unwrap_err().to_string();
+        assert!(err.contains("--pick-monitor"), "{}", err);
+        assert_eq!(
+            config
+                .resolve_profile_on("default", Some("tv"))
+                .unwrap()
+                .monitor_name,
+            "tv"
+        );
     }
-}
 
-/// Sensible gamescope defaults derived from monitor specs.
-fn base_options(monitor: &MonitorDef) -> HashMap<String, OptionValue> {
-    let mut opts = HashMap::with_capacity(10);
+    #[test]
+    fn test_resolve_profile_traced() {
+        let mut config = test_config();
+        config.profiles = serde_yaml::from_str(
+            r#"
+profiles:
+  base: {options: {rt: false}, environment: {GAME: mygame}}
+  vrr:
+    compose: [base]
+    optionsWhenVrr: {fps-limit: 120}
+    optionsWhenHdr: {hdr-itm-enable: true}
+    vrr: false
+    windowTitle: "${GAME} (vrr)"
+aliases:
+  v: vrr
+"#,
+        )
+        .unwrap();
 
-    opts.insert(
-        "backend".to_string(),
-        OptionValue::String("sdl".to_string()),
-    );
-    opts.insert("fade-out-duration".to_string(), OptionValue::Int(200));
-    opts.insert("fullscreen".to_string(), OptionValue::Bool(true));
-    opts.insert("immediate-flips".to_string(), OptionValue::Bool(true));
-    opts.insert(
-        "nested-refresh".to_string(),
-        OptionValue::Int(i64::from(monitor.refreshRate)),
-    );
-    opts.insert(
-        "output-height".to_string(),
-        OptionValue::Int(i64::from(monitor.height)),
-    );
-    opts.insert(
-        "output-width".to_string(),
-        OptionValue::Int(i64::from(monitor.width)),
-    );
-    opts.insert("rt".to_string(), OptionValue::Bool(true));
+        let mut trace = Trace::on();
+        let resolved = config
+            .resolve_profile_traced("v", Some("tv"), &mut trace)
+            .unwrap();
+        assert_eq!(
+            resolved.options,
+            config.resolve_profile_on("v", Some("tv")).unwrap().options
+        );
 
-    if monitor.vrr {
-        opts.insert("adaptive-sync".to_string(), OptionValue::Bool(true));
+        let steps: Vec<String> = trace
+            .steps()
+            .iter()
+            .map(|(stage, message)| format!("{}: {}", stage, message))
+            .collect();
+        for expected in [
+            "alias: 'v' resolves to 'vrr'",
+            "compose: 'vrr': 'base' sets environment.GAME, options.rt",
+            "monitor: 'tv' (chosen for this launch)",
+            "monitor options: output-width = 3840",
+            "conditional: optionsWhenHdr is active (monitor hdr: true)",
+            "conditional: optionsWhenVrr is inactive (monitor vrr and profile vrr not false: false)",
+            "options: hdr-itm-enable = true",
+            "options: rt = false (was true)",
+            "monitor-dependent fields: adaptive-sync = false",
+            "interpolate: windowTitle: '${GAME} (vrr)' -> 'mygame (vrr)'",
+        ] {
+            assert!(steps.iter().any(|s| s == expected), "missing {:?} in {:#?}", expected, steps);
+        }
+        assert!(!steps.iter().any(|s| s.contains("fps-limit")));
+        assert!(trace
+            .render()
+            .starts_with("001 alias: 'v' resolves to 'vrr'\n"));
+
+        // The default trace is off and records nothing
+        let mut off = Trace::default();
+        config.resolve_profile_traced("v", None, &mut off).unwrap();
+        assert!(!off.is_on());
+        assert!(off.steps().is_empty());
+        assert_eq!(off.render(), "");
     }
 
-    opts
-}
+    #[test]
+    fn test_unknown_profile_error() {
+        let config = test_config();
+        assert!(config.resolve_profile("nonexistent").is_err());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_list_profiles() {
+        let config = test_config();
+        let profiles = config.list_profiles(false);
+        assert_eq!(profiles.len(), 6);
+    }
 
-    fn test_config() -> Config {
+    #[test]
+    fn test_list_profiles_broken() {
+        let mut config = test_config();
+        config.profiles =
+            serde_yaml::from_str("profiles:\n  good: {}\n  broken:\n    monitor: missing\n")
+                .unwrap();
+
+        let profiles = config.list_profiles(false);
+        assert_eq!(profiles.len(), 2);
+        let (_, good) = profiles.iter().find(|(n, _)| n == "good").unwrap();
+        assert!(good.starts_with("monitor=main"));
+        let (_, broken) = profiles.iter().find(|(n, _)| n == "broken").unwrap();
+        assert!(broken.starts_with("(error: "), "{}", broken);
+        assert!(broken.contains("missing"), "{}", broken);
+
+        let profiles = config.list_profiles(true);
+        assert_eq!(profiles, vec![("good".to_string(), good.clone())]);
+    }
+
+    #[test]
+    fn test_flat_profiles_broken() {
+        let mut config = test_config();
+        config.profiles =
+            serde_yaml::from_str("profiles:\n  broken:\n    monitor: missing\n  good: {}\n")
+                .unwrap();
+
+        let lines = config.flat_profiles(false);
+        // The broken profile is reported in place and the listing goes on
+        assert!(lines[0].starts_with("broken.error="), "{}", lines[0]);
+        assert!(lines[0].contains("missing"), "{}", lines[0]);
+        assert!(lines.contains(&"good.setting.monitor=main".to_string()));
+        assert!(lines[1..].iter().all(|line| line.starts_with("good.")));
+
+        let skipped = config.flat_profiles(true);
+        assert_eq!(skipped, lines[1..]);
+    }
+
+    #[test]
+    fn test_option_value_float() {
+        let options: HashMap<String, OptionValue> =
+            serde_yaml::from_str("scale: 1.5\nsharpness: 2.0\nwidth: 1920").unwrap();
+
+        assert!(matches!(options.get("scale"), Some(OptionValue::Float(_))));
+        assert!(matches!(
+            options.get("sharpness"),
+            Some(OptionValue::Float(_))
+        ));
+        assert!(matches!(options.get("width"), Some(OptionValue::Int(1920))));
+        assert_eq!(options["scale"].to_string(), "1.5");
+        assert_eq!(options["sharpness"].to_string(), "2");
+    }
+
+    #[test]
+    fn test_unset_in_resolved_profile() {
+        let profiles_yaml = r#"
+profiles:
+  with-unset:
+    useWSI: true
+    environment:
+      CUSTOM: "value"
+    unset:
+      - SDL_VIDEODRIVER
+      - CUSTOM
+"#;
+
+        let profiles: ProfilesConfig = serde_yaml::from_str(profiles_yaml).unwrap();
         let monitors_yaml = r#"
 monitors:
   main:
     width: 2560
     height: 1440
     refreshRate: 165
-    vrr: true
-    hdr: true
     primary: true
+"#;
+        let monitors: MonitorsConfig = serde_yaml::from_str(monitors_yaml).unwrap();
+        let config = Config { monitors, profiles };
+
+        let profile = config.resolve_profile("with-unset").unwrap();
+        assert_eq!(profile.unset_vars.len(), 2);
+        assert!(profile.unset_vars.contains(&"SDL_VIDEODRIVER".to_string()));
+        assert!(profile.unset_vars.contains(&"CUSTOM".to_string()));
+
+        // Verify unset works in environment
+        let env = profile.environment();
+        let env_map: HashMap<_, _> = env.into_iter().collect();
+        assert!(!env_map.contains_key("SDL_VIDEODRIVER"));
+        assert!(!env_map.contains_key("CUSTOM"));
+    }
+
+    #[test]
+    fn test_resolve_config_path_absolute() {
+        let path = Path::new("/etc/wayscope/config.yaml");
+        assert_eq!(resolve_config_path(path, Path::new("/home/user")), path);
+    }
+
+    #[test]
+    fn test_resolve_config_path_relative() {
+        use tempfile::TempDir;
+
+        let cwd = TempDir::new().unwrap();
+        let config_dir = TempDir::new().unwrap();
+        std::fs::write(cwd.path().join("config.yaml"), "profiles: {}").unwrap();
+        std::fs::write(
+            config_dir.path().join("config.yaml"),
+            "profiles:\n  couch: {}",
+        )
+        .unwrap();
+
+        let rel = Path::new("config.yaml");
+        let from_cwd = ProfilesConfig::load(&resolve_config_path(rel, cwd.path())).unwrap();
+        let from_config_dir =
+            ProfilesConfig::load(&resolve_config_path(rel, config_dir.path())).unwrap();
+
+        assert!(from_cwd.profiles.is_empty());
+        assert!(from_config_dir.profiles.contains_key("couch"));
+    }
+
+    #[test]
+    fn test_gamescope_output_name() {
+        assert_eq!(gamescope_output_name("DP-1"), "DP-1");
+        assert_eq!(gamescope_output_name("HDMI-A-1"), "HDMI-A-1");
+        assert_eq!(gamescope_output_name("eDP-1"), "eDP-1");
+        assert_eq!(gamescope_output_name("HDMI-2"), "HDMI-A-2");
+        assert_eq!(gamescope_output_name("HDMI-A-0"), "HDMI-A-1");
+        assert_eq!(gamescope_output_name("DisplayPort-0"), "DP-1");
+    }
+
+    #[test]
+    fn test_monitor_connector_prefer_output() {
+        let yaml = r#"
+monitors:
+  main:
+    width: 2560
+    height: 1440
+    refreshRate: 165
+    primary: true
+    connector: DisplayPort-1
   tv:
     width: 3840
     height: 2160
     refreshRate: 120
-    hdr: true
+    connector: HDMI-1
+    preferOutput: HDMI-A-2
+  plain:
+    width: 1920
+    height: 1080
+    refreshRate: 60
+"#;
+        let monitors: MonitorsConfig = serde_yaml::from_str(yaml).unwrap();
+        let output = |name: &str| {
+            base_options(monitors.get(name).unwrap())
+                .get("prefer-output")
+                .map(|v| v.to_string())
+        };
+        assert_eq!(output("main").as_deref(), Some("DP-2"));
+        // Explicit gamescope name wins over the translation
+        assert_eq!(output("tv").as_deref(), Some("HDMI-A-2"));
+        assert_eq!(output("plain"), None);
+
+        let mut bad = monitors.get("main").unwrap().clone();
+        bad.connector = Some("DP 1".to_string());
+        assert!(bad.validate("main").is_err());
+    }
+
+    #[test]
+    fn test_monitor_connectors_fallback() {
+        let yaml = r#"
+monitors:
+  dock:
+    width: 2560
+    height: 1440
+    refreshRate: 144
+    connectors: [DisplayPort-0, eDP-1]
+  single:
+    width: 1920
+    height: 1080
+    refreshRate: 60
+    connectors: [HDMI-1]
 "#;
+        let monitors: MonitorsConfig = serde_yaml::from_str(yaml).unwrap();
+        let dock = monitors.get("dock").unwrap();
+        dock.validate("dock").unwrap();
+        assert_eq!(dock.gamescope_output().as_deref(), Some("DP-1,eDP-1"));
+        assert!(dock.has_fallback_outputs());
+        let single = monitors.get("single").unwrap();
+        assert_eq!(single.gamescope_output().as_deref(), Some("HDMI-A-1"));
+        assert!(!single.has_fallback_outputs());
+
+        let check = |edit: fn(&mut MonitorDef)| {
+            let mut monitor = dock.clone();
+            edit(&mut monitor);
+            monitor.validate("dock").unwrap_err().to_string()
+        };
+        assert!(check(|m| m.connectors = Some(Vec::new())).contains("connectors is empty"));
+        assert!(check(|m| m.connector = Some("DP-1".to_string())).contains("not both"));
+        assert!(
+            check(|m| m.connectors = Some(vec!["DP-1,DP-2".to_string()]))
+                .contains("connectors 'DP-1,DP-2'")
+        );
+    }
+
+    #[test]
+    fn test_connectors_fallback_backend() {
+        let mut config = test_config();
+        config.monitors.monitors.get_mut("main").unwrap().connectors =
+            Some(vec!["DP-1".to_string(), "eDP-1".to_string()]);
+
+        // The test profiles use sdl: emitted, but warned about
+        let resolved = config.resolve_profile("default").unwrap();
+        assert_eq!(
+            resolved.options.get("prefer-output"),
+            Some(&OptionValue::String("DP-1,eDP-1".to_string()))
+        );
+        assert_eq!(resolved.config_warnings.len(), 1);
+        assert!(resolved.config_warnings[0].contains("only the drm backend falls back"));
+
+        let profile = config.profiles.profiles.get_mut("default").unwrap();
+        profile.options.insert(
+            "backend".to_string(),
+            OptionValue::String("drm".to_string()),
+        );
+        let resolved = config.resolve_profile("default").unwrap();
+        assert!(resolved.config_warnings.is_empty());
+    }
+
+    #[test]
+    fn test_monitor_changed_from_defaults() {
+        let monitors: MonitorsConfig = serde_yaml::from_str(
+            r#"
+monitors:
+  plain: {width: 1920, height: 1080, refreshRate: 60}
+  gaming: {width: 2560, height: 1440, refreshRate: 165, vrr: true, hdr: true, primary: true, connector: DP-1}
+  pinned: {width: 1920, height: 1080, refreshRate: 60, connector: HDMI-1, preferOutput: HDMI-A-2}
+"#,
+        )
+        .unwrap();
+
+        let plain = monitors.get("plain").unwrap();
+        assert!(plain.changed_from_defaults().is_empty());
+        assert!(plain.enabled_capabilities().is_empty());
+
+        let gaming = monitors.get("gaming").unwrap();
+        let fields: Vec<_> = gaming
+            .changed_from_defaults()
+            .into_iter()
+            .map(|(field, default, value)| format!("{}: {} -> {}", field, default, value))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "refreshRate: 60 -> 165",
+                "vrr: false -> true",
+                "hdr: false -> true",
+                "primary: false -> true",
+                "connector:  -> DP-1",
+            ]
+        );
+        assert_eq!(gaming.enabled_capabilities(), vec!["vrr", "hdr"]);
+
+        // A written-out preferOutput counts; one derived from the connector doesn't
+        let pinned = monitors.get("pinned").unwrap();
+        let fields: Vec<_> = pinned
+            .changed_from_defaults()
+            .into_iter()
+            .map(|(field, _, _)| field)
+            .collect();
+        assert_eq!(fields, vec!["connector", "preferOutput"]);
+    }
 
-        let profiles_yaml = r#"
-profiles:
-  default:
-    useHDR: true
-    useWSI: true
-    options:
-      backend: sdl
+    #[test]
+    fn test_monitor_dimension_bounds() {
+        let monitor = |width, height, refresh| MonitorDef {
+            width,
+            height,
+            refreshRate: refresh,
+            vrr: false,
+            hdr: false,
+            primary: false,
+            connector: None,
+            connectors: None,
+            preferOutput: None,
+            iccProfile: None,
+            model: None,
+        };
 
-  autohdr:
-    useWSI: false
+        assert!(monitor(7680, 4320, 480.0).validate("main").is_ok());
+        assert!(monitor(1, 1, 1.0).validate("main").is_ok());
 
-  couch:
-    monitor: tv
-    useHDR: true
-    binary: /custom/gamescope
+        let err = monitor(25600, 1440, 165.0).validate("main").unwrap_err();
+        assert!(err.to_string().contains("Monitor 'main': width 25600"));
+        let err = monitor(2560, 0, 165.0).validate("main").unwrap_err();
+        assert!(err.to_string().contains("height 0"));
+        let err = monitor(2560, 1440, 0.0).validate("main").unwrap_err();
+        assert!(err.to_string().contains("refreshRate 0"));
+        let err = monitor(2560, 1440, 0.5).validate("main").unwrap_err();
+        assert!(err.to_string().contains("refreshRate 0.5"));
+    }
 
-  performance:
-    useHDR: false
-    options:
-      fsr-upscaling: true
-"#;
+    #[test]
+    fn test_fractional_refresh_rate() {
+        let parse = |yaml: &str| serde_yaml::from_str::<MonitorDef>(yaml).unwrap();
 
-        let monitors: MonitorsConfig = serde_yaml::from_str(monitors_yaml).unwrap();
-        let profiles: ProfilesConfig = serde_yaml::from_str(profiles_yaml).unwrap();
-        Config { monitors, profiles }
+        let int = parse("{width: 1920, height: 1080, refreshRate: 60}");
+        assert_eq!(int.refreshRate, 60.0);
+        let float = parse("{width: 1920, height: 1080, refreshRate: 59.94}");
+        assert_eq!(float.refreshRate, 59.94);
+        let alias = parse("{width: 1920, height: 1080, refresh: 143.856}");
+        assert_eq!(alias.refreshRate, 143.856);
+        assert!(serde_yaml::from_str::<MonitorDef>(
+            "{width: 1920, height: 1080, refreshRate: fast}"
+        )
+        .is_err());
+
+        // gamescope gets whole Hz
+        assert_eq!(
+            base_options(&float).get("nested-refresh"),
+            Some(&OptionValue::Int(60))
+        );
+        assert_eq!(
+            base_options(&alias).get("nested-refresh"),
+            Some(&OptionValue::Int(144))
+        );
+
+        // Whole rates stay integers on save; fractional ones round-trip exactly
+        assert!(serde_yaml::to_string(&int)
+            .unwrap()
+            .contains("refreshRate: 60\n"));
+        let saved = serde_yaml::to_string(&float).unwrap();
+        assert!(saved.contains("refreshRate: 59.94\n"));
+        assert_eq!(serde_yaml::from_str::<MonitorDef>(&saved).unwrap(), float);
     }
 
     #[test]
-    fn test_resolve_default_profile() {
-        let config = test_config();
-        let profile = config.resolve_profile("default").unwrap();
+    fn test_unused_monitors() {
+        let mut config = test_config();
+        assert!(config.unused_monitors().is_empty());
 
-        assert_eq!(profile.name, "default");
-        assert_eq!(profile.monitor_name, "main");
-        assert!(profile.use_hdr);
-        assert!(profile.use_wsi);
-        assert_eq!(profile.binary, "gamescope");
+        config.profiles.profiles.remove("couch");
+        assert_eq!(config.unused_monitors(), vec!["tv"]);
+
+        // The primary monitor never counts as unused
+        config.profiles.profiles.clear();
+        assert_eq!(config.unused_monitors(), vec!["tv"]);
     }
 
     #[test]
-    fn test_resolve_with_custom_monitor() {
+    fn test_monitor_diff() {
         let config = test_config();
-        let profile = config.resolve_profile("couch").unwrap();
+        let main = config.monitors.get("main").unwrap();
+        let tv = config.monitors.get("tv").unwrap();
 
-        assert_eq!(profile.monitor_name, "tv");
-        assert_eq!(profile.binary, "/custom/gamescope");
-        assert!(matches!(
-            profile.options.get("output-width"),
-            Some(OptionValue::Int(3840))
-        ));
+        let diff = main.diff(tv);
+        let fields: Vec<_> = diff.iter().map(|(f, _, _)| *f).collect();
+        assert_eq!(
+            fields,
+            vec!["width", "height", "refreshRate", "vrr", "primary"]
+        );
+        assert_eq!(diff[0], ("width", "2560".to_string(), "3840".to_string()));
+        assert!(main.diff(main).is_empty());
     }
 
+    // ========================================================================
+    // Env File Tests
+    // ========================================================================
+
     #[test]
-    fn test_hdr_defaults_to_monitor() {
-        let config = test_config();
-        let profile = config.resolve_profile("autohdr").unwrap();
-        assert!(profile.use_hdr); // Inherits from monitor.hdr
+    fn test_load_env_file() {
+        use tempfile::TempDir;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("game.env");
+        std::fs::write(
+            &path,
+            "# comment\n\nMANGOHUD=1\nexport DXVK_ASYNC=1\nTITLE=\"My Game\"\nEMPTY=\n",
+        )
+        .unwrap();
+
+        let vars = load_env_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("MANGOHUD".to_string(), "1".to_string()),
+                ("DXVK_ASYNC".to_string(), "1".to_string()),
+                ("TITLE".to_string(), "My Game".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
     }
 
     #[test]
-    fn test_wsi_defaults_to_true() {
-        let config = test_config();
-        let profile = config.resolve_profile("performance").unwrap();
-        assert!(profile.use_wsi);
+    fn test_load_env_file_errors() {
+        use tempfile::TempDir;
+
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.env");
+        let err = load_env_file(missing.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Failed to read env file"));
+
+        let invalid = dir.path().join("invalid.env");
+        std::fs::write(&invalid, "OK=1\nBAD-KEY=2\n").unwrap();
+        let err = load_env_file(invalid.to_str().unwrap()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(":2: invalid environment variable name"));
     }
 
     #[test]
-    fn test_unknown_profile_error() {
+    fn test_env_file_inline_environment_wins() {
+        use tempfile::TempDir;
+
+        let dir = TempDir::new().unwrap();
+        let env_path = dir.path().join("game.env");
+        std::fs::write(&env_path, "FROM_FILE=1\nSHARED=file\n").unwrap();
+
+        let mut config = test_config();
+        let profile = config.profiles.profiles.get_mut("default").unwrap();
+        profile.env_file = Some(env_path.to_string_lossy().into_owned());
+        profile
+            .environment
+            .insert("SHARED".to_string(), EnvValue::String("inline".to_string()));
+
+        let resolved = config.resolve_profile("default").unwrap();
+        assert_eq!(resolved.user_env["FROM_FILE"], "1");
+        assert_eq!(resolved.user_env["SHARED"], "inline");
+    }
+
+    #[test]
+    fn test_env_file_path() {
+        let mut config = test_config();
+        config
+            .profiles
+            .profiles
+            .get_mut("default")
+            .unwrap()
+            .env_file = Some("~/game.env".to_string());
+        assert_eq!(
+            config.env_file("default"),
+            Some(dirs::home_dir().unwrap().join("game.env"))
+        );
+        assert_eq!(config.env_file("performance"), None);
+        assert_eq!(config.env_file("nonexistent"), None);
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~/game.env"), home.join("game.env"));
+        assert_eq!(
+            expand_tilde("/etc/game.env"),
+            PathBuf::from("/etc/game.env")
+        );
+        assert_eq!(expand_tilde("~other/x"), PathBuf::from("~other/x"));
+    }
+
+    // ========================================================================
+    // Serialization Round-Trip Tests
+    // ========================================================================
+
+    /// Serializes a value to YAML and parses it back.
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let yaml = serde_yaml::to_string(value).unwrap();
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn test_config_round_trip() {
         let config = test_config();
-        assert!(config.resolve_profile("nonexistent").is_err());
+        assert_eq!(round_trip(&config.monitors), config.monitors);
+        assert_eq!(round_trip(&config.profiles), config.profiles);
     }
 
     #[test]
-    fn test_list_profiles() {
+    fn test_round_trip_preserves_field_names() {
         let config = test_config();
-        let profiles = config.list_profiles();
-        assert_eq!(profiles.len(), 4);
+        let monitors = serde_yaml::to_string(&config.monitors).unwrap();
+        let profiles = serde_yaml::to_string(&config.profiles).unwrap();
+
+        assert!(monitors.contains("refreshRate:"));
+        assert!(profiles.contains("useHDR:"));
+        assert!(profiles.contains("useWSI:"));
+        assert!(!profiles.contains("use_hdr"));
+
+        // Keys are written sorted for stable output
+        let autohdr = profiles.find("autohdr:").unwrap();
+        let vertical = profiles.find("vertical:").unwrap();
+        assert!(autohdr < vertical);
     }
 
     #[test]
-    fn test_unset_in_resolved_profile() {
+    fn test_value_types_round_trip() {
         let profiles_yaml = r#"
 profiles:
-  with-unset:
-    useWSI: true
+  values:
+    touchMode: 2
+    options:
+      flag: true
+      count: 3
+      scale: 1.5
+      integral-float: 2.0
+      filter: fsr
     environment:
-      CUSTOM: "value"
+      NUMBER: 1
+      TEXT: "hello"
+      NUMERIC_TEXT: "42"
     unset:
-      - SDL_VIDEODRIVER
-      - CUSTOM
+      - DISPLAY
 "#;
-
         let profiles: ProfilesConfig = serde_yaml::from_str(profiles_yaml).unwrap();
-        let monitors_yaml = r#"
-monitors:
-  main:
-    width: 2560
-    height: 1440
-    refreshRate: 165
-    primary: true
-"#;
-        let monitors: MonitorsConfig = serde_yaml::from_str(monitors_yaml).unwrap();
-        let config = Config { monitors, profiles };
-
-        let profile = config.resolve_profile("with-unset").unwrap();
-        assert_eq!(profile.unset_vars.len(), 2);
-        assert!(profile.unset_vars.contains(&"SDL_VIDEODRIVER".to_string()));
-        assert!(profile.unset_vars.contains(&"CUSTOM".to_string()));
+        let parsed = round_trip(&profiles);
+        assert_eq!(parsed, profiles);
 
-        // Verify unset works in environment
-        let env = profile.environment();
-        let env_map: HashMap<_, _> = env.into_iter().collect();
-        assert!(!env_map.contains_key("SDL_VIDEODRIVER"));
-        assert!(!env_map.contains_key("CUSTOM"));
+        let options = &parsed.profiles["values"].options;
+        assert_eq!(options["integral-float"], OptionValue::Float(2.0));
+        let env = &parsed.profiles["values"].environment;
+        assert_eq!(env["NUMERIC_TEXT"], EnvValue::String("42".to_string()));
     }
 
     // ========================================================================
@@ -573,7 +5086,7 @@ monitors:
 
     #[test]
     fn test_validate_env_var_names_success() {
-        let env_keys = vec![
+        let env_keys = [
             "VALID_VAR".to_string(),
             "_ANOTHER".to_string(),
             "third123".to_string(),
@@ -586,7 +5099,7 @@ monitors:
 
     #[test]
     fn test_validate_env_var_names_invalid_env_key() {
-        let env_keys = vec!["VALID".to_string(), "INVALID=KEY".to_string()];
+        let env_keys = ["VALID".to_string(), "INVALID=KEY".to_string()];
         let unset = vec![];
 
         let result = validate_env_var_names("test-profile", env_keys.iter(), &unset);
@@ -607,6 +5120,22 @@ monitors:
         assert!(err.contains("unset entry"));
     }
 
+    #[test]
+    fn test_validate_env_var_names_unset_globs() {
+        let env_keys: Vec<String> = vec![];
+        let ok = ["SDL_*", "*_HDR", "DXVK_*_LOG", "*"].map(String::from);
+        assert!(validate_env_var_names("test-profile", env_keys.iter(), &ok).is_ok());
+
+        for bad in ["SDL-*", "1*", "SDL_?"] {
+            let unset = [bad.to_string()];
+            assert!(
+                validate_env_var_names("test-profile", env_keys.iter(), &unset).is_err(),
+                "{} should be rejected",
+                bad
+            );
+        }
+    }
+
     // ========================================================================
     // Integration Tests for Validation During Config Load
     // ========================================================================
@@ -650,7 +5179,7 @@ profiles:
         .unwrap();
 
         // Config should load successfully even with duplicates
-        let config = Config::load(&monitors_path, &profiles_path).unwrap();
+        let config = Config::load(&dir.path().join("etc"), &monitors_path, &profiles_path).unwrap();
         let profile = config.resolve_profile("test").unwrap();
 
         // Duplicates are preserved (env_remove is idempotent, so this is harmless)
@@ -659,6 +5188,253 @@ profiles:
         assert!(profile.unset_vars.contains(&"DXVK_HDR".to_string()));
     }
 
+    #[test]
+    fn test_config_load_layered_user_overrides_system() {
+        use tempfile::TempDir;
+
+        let system = TempDir::new().unwrap();
+        let user = TempDir::new().unwrap();
+        std::fs::write(
+            system.path().join("monitors.yaml"),
+            r#"
+monitors:
+  main:
+    width: 1920
+    height: 1080
+    refreshRate: 60
+    primary: true
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            system.path().join("config.yaml"),
+            r#"
+defaultProfile: packaged
+aliases:
+  pk: packaged
+profiles:
+  packaged:
+    useHDR: false
+  shared:
+    options:
+      fsr-upscale-sharpness: 5
+"#,
+        )
+        .unwrap();
+
+        let monitors_path = user.path().join("monitors.yaml");
+        let profiles_path = user.path().join("config.yaml");
+        std::fs::write(
+            &monitors_path,
+            r#"
+monitors:
+  desk:
+    width: 2560
+    height: 1440
+    refreshRate: 144
+    primary: true
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &profiles_path,
+            r#"
+defaultProfile: mine
+profiles:
+  mine: {}
+  shared:
+    useHDR: true
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(system.path(), &monitors_path, &profiles_path).unwrap();
+
+        // Both layers' entries are present; the user's default wins
+        assert_eq!(config.list_profiles(false).len(), 3);
+        assert_eq!(config.profiles.default_profile.as_deref(), Some("mine"));
+        assert_eq!(config.resolve_profile("pk").unwrap().name, "packaged");
+
+        // Same-named profiles are replaced whole, not field-merged
+        let shared = &config.profiles.profiles["shared"];
+        assert_eq!(shared.use_hdr, Some(true));
+        assert!(shared.options.is_empty());
+
+        // The user's primary monitor replaces the system one
+        assert_eq!(config.resolve_profile("mine").unwrap().monitor_name, "desk");
+        assert!(!config.monitors.get("main").unwrap().primary);
+    }
+
+    #[test]
+    fn test_stack_layers_precedence() {
+        use tempfile::TempDir;
+
+        let dirs: Vec<TempDir> = (0..4).map(|_| TempDir::new().unwrap()).collect();
+        let [system, user, local, cli] = [0, 1, 2, 3].map(|i| dirs[i].path());
+        // Each layer defines its own profile and overrides `shared`
+        for (dir, name) in [
+            (system, "system"),
+            (user, "user"),
+            (local, "local"),
+            (cli, "cli"),
+        ] {
+            std::fs::write(
+                dir.join("config.yaml"),
+                format!(
+                    "profiles:\n  {}: {{}}\n  shared: {{windowTitle: {}}}\n",
+                    name, name
+                ),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            user.join("monitors.yaml"),
+            "monitors:\n  main: {width: 1920, height: 1080, refreshRate: 60, primary: true}\n",
+        )
+        .unwrap();
+
+        let stack = |explicit: Option<PathBuf>| {
+            let mut layers = vec![system.join("config.yaml")];
+            layers.extend(stack_layers(
+                vec![user.join("config.yaml")],
+                Some(local),
+                "config.yaml",
+                explicit,
+            ));
+            Config::load_layers(&[user.join("monitors.yaml")], &layers).unwrap()
+        };
+        let title = |config: &Config| config.profiles.profiles["shared"].window_title.clone();
+
+        // system < user < local < CLI path, and every layer's profiles stay
+        let config = stack(Some(cli.join("config.yaml")));
+        assert_eq!(config.list_profiles(false).len(), 5);
+        assert_eq!(title(&config).as_deref(), Some("cli"));
+        assert_eq!(title(&stack(None)).as_deref(), Some("local"));
+
+        // A missing local file is left out rather than reported
+        let layers = stack_layers(
+            vec![user.join("config.yaml")],
+            Some(local),
+            "monitors.yaml",
+            None,
+        );
+        assert_eq!(layers, vec![user.join("config.yaml")]);
+    }
+
+    #[test]
+    fn test_stack_layers_local_is_opt_in() {
+        let local = tempfile::tempdir().unwrap();
+        std::fs::write(local.path().join("config.yaml"), "profiles: {}\n").unwrap();
+        let user = vec![PathBuf::from("user/config.yaml")];
+
+        assert_eq!(stack_layers(user.clone(), None, "config.yaml", None), user);
+        assert_eq!(
+            stack_layers(user.clone(), Some(local.path()), "config.yaml", None),
+            [user, vec![local.path().join("config.yaml")]].concat()
+        );
+    }
+
+    #[test]
+    fn test_search_config_path() {
+        use tempfile::TempDir;
+
+        let work = TempDir::new().unwrap();
+        let personal = TempDir::new().unwrap();
+        let empty = TempDir::new().unwrap();
+        std::fs::write(work.path().join("config.yaml"), "profiles: {}\n").unwrap();
+        std::fs::write(personal.path().join("config.yaml"), "profiles: {}\n").unwrap();
+        std::fs::write(personal.path().join("monitors.yaml"), "monitors: {}\n").unwrap();
+
+        let search = std::env::join_paths([empty.path(), work.path(), personal.path()])
+            .unwrap()
+            .into_string()
+            .unwrap();
+        assert_eq!(
+            search_config_path(&search, "config.yaml"),
+            vec![
+                work.path().join("config.yaml"),
+                personal.path().join("config.yaml")
+            ]
+        );
+        assert_eq!(
+            search_config_path(&format!(":{}:", search), "monitors.yaml"),
+            vec![personal.path().join("monitors.yaml")]
+        );
+        assert!(search_config_path("", "config.yaml").is_empty());
+    }
+
+    #[test]
+    fn test_config_load_layers() {
+        use tempfile::TempDir;
+
+        let low = TempDir::new().unwrap();
+        let high = TempDir::new().unwrap();
+        std::fs::write(
+            low.path().join("monitors.yaml"),
+            "monitors:\n  main: {width: 1920, height: 1080, refreshRate: 60, primary: true}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            low.path().join("config.yaml"),
+            "defaultProfile: a\nprofiles:\n  a: {}\n  b: {useHDR: false}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            high.path().join("config.yaml"),
+            "profiles:\n  b: {useHDR: true}\n",
+        )
+        .unwrap();
+
+        let missing = low.path().join("missing.yaml");
+        let config = Config::load_layers(
+            &[missing.clone(), low.path().join("monitors.yaml")],
+            &[
+                low.path().join("config.yaml"),
+                high.path().join("config.yaml"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.list_profiles(false).len(), 2);
+        assert_eq!(config.profiles.default_profile.as_deref(), Some("a"));
+        assert_eq!(config.profiles.profiles["b"].use_hdr, Some(true));
+
+        let err = MonitorsConfig::load_layers(std::slice::from_ref(&missing)).unwrap_err();
+        assert!(err.to_string().contains("missing.yaml (file not found)"));
+    }
+
+    #[test]
+    fn test_config_load_layered_missing_files() {
+        use tempfile::TempDir;
+
+        let system = TempDir::new().unwrap();
+        let user = TempDir::new().unwrap();
+        let monitors_path = user.path().join("monitors.yaml");
+        let profiles_path = user.path().join("config.yaml");
+
+        // Neither layer present: the user file is reported
+        let err = Config::load(system.path(), &monitors_path, &profiles_path)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&monitors_path.display().to_string()));
+
+        // System layer alone is enough
+        std::fs::write(
+            system.path().join("monitors.yaml"),
+            "monitors:\n  main:\n    width: 1920\n    height: 1080\n    refreshRate: 60\n    primary: true\n",
+        )
+        .unwrap();
+        std::fs::write(
+            system.path().join("config.yaml"),
+            "profiles:\n  default: {}\n",
+        )
+        .unwrap();
+        let config = Config::load(system.path(), &monitors_path, &profiles_path).unwrap();
+        assert_eq!(
+            config.resolve_profile("default").unwrap().monitor_name,
+            "main"
+        );
+    }
+
     #[test]
     fn test_config_load_rejects_invalid_env_name() {
         use tempfile::TempDir;
@@ -692,12 +5468,74 @@ profiles:
         )
         .unwrap();
 
-        let result = Config::load(&monitors_path, &profiles_path);
+        let result = Config::load(&dir.path().join("etc"), &monitors_path, &profiles_path);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("invalid environment variable"));
     }
 
+    #[test]
+    fn test_missing_monitor_refs() {
+        let config = test_config();
+        let profiles: ProfilesConfig = serde_yaml::from_str(
+            "profiles:\n  a: { monitor: gone }\n  b: { monitor: main }\n  c: {}",
+        )
+        .unwrap();
+        assert_eq!(
+            profiles.missing_monitor_refs(&config.monitors),
+            vec![("a", "gone")]
+        );
+    }
+
+    #[test]
+    fn test_dead_monitor_refs_follow_compose() {
+        let config = test_config();
+        let profiles: ProfilesConfig = serde_yaml::from_str(
+            r#"
+profiles:
+  tvbase: {monitor: gone}
+  couch: {compose: [tvbase]}
+  desk: {compose: [tvbase], monitor: main}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            profiles.dead_monitor_refs(&config.monitors),
+            vec![
+                ("couch".to_string(), "gone".to_string(), false),
+                ("tvbase".to_string(), "gone".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profiles_save_round_trips() {
+        use tempfile::TempDir;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        let config = test_config();
+
+        config.profiles.save(&path).unwrap();
+        assert_eq!(ProfilesConfig::load(&path).unwrap(), config.profiles);
+        assert!(!path.with_extension("yaml.tmp").exists());
+    }
+
+    #[test]
+    fn test_profiles_save_skips_empty_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  default: {}\n  hdr: {useHDR: true}\n").unwrap();
+
+        profiles.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("default: {}"), "{}", saved);
+        for empty in ["options", "environment", "unset"] {
+            assert!(!saved.contains(empty), "{}", saved);
+        }
+    }
+
     #[test]
     fn test_config_load_rejects_invalid_unset_name() {
         use tempfile::TempDir;
@@ -730,7 +5568,7 @@ profiles:
         )
         .unwrap();
 
-        let result = Config::load(&monitors_path, &profiles_path);
+        let result = Config::load(&dir.path().join("etc"), &monitors_path, &profiles_path);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("invalid environment variable"));
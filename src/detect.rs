@@ -0,0 +1,577 @@
+//! Monitor detection backends.
+//!
+//! Detection is pluggable via the `MonitorBackend` trait so the same
+//! features work across compositors:
+//! - `WlrRandr` - Queries `wlr-randr --json` (wlroots compositors)
+//! - `DrmSysfs` - Reads connectors and EDID from `/sys/class/drm`
+//!
+//! `backend()` picks the best available implementation at runtime.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::config::{MonitorDef, MonitorsConfig};
+
+/// Capabilities reported by a backend. `None` means the backend can't tell.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Capabilities {
+    pub vrr: Option<bool>,
+    pub hdr: Option<bool>,
+}
+
+/// A physical output as seen by a detection backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedOutput {
+    /// Connector name (e.g. `DP-1`, `HDMI-A-1`).
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+    /// Whether the output is currently driven by the compositor.
+    pub enabled: bool,
+    pub capabilities: Capabilities,
+    /// Manufacturer and model (e.g. `Dell U2720Q`), when the backend knows.
+    pub model: Option<String>,
+}
+
+pub trait MonitorBackend {
+    /// Short identifier shown in output (e.g. `wlr-randr`).
+    fn name(&self) -> &'static str;
+
+    /// Lists all connected outputs.
+    fn outputs(&self) -> Result<Vec<DetectedOutput>>;
+
+    /// The output games will most likely land on. Backends without a notion
+    /// of focus fall back to the first enabled output.
+    fn active_output(&self) -> Result<Option<DetectedOutput>> {
+        Ok(self.outputs()?.into_iter().find(|o| o.enabled))
+    }
+
+    /// Reads capabilities for a single output by connector name.
+    fn capabilities(&self, output: &str) -> Result<Capabilities> {
+        self.outputs()?
+            .into_iter()
+            .find(|o| o.name == output)
+            .map(|o| o.capabilities)
+            .with_context(|| format!("Output '{}' not found", output))
+    }
+}
+
+/// Selects a backend at runtime: `wlr-randr` when running under Wayland and
+/// the tool is installed, DRM sysfs otherwise.
+pub fn backend() -> Box<dyn MonitorBackend> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && WlrRandr::available() {
+        Box::new(WlrRandr)
+    } else {
+        Box::new(DrmSysfs::default())
+    }
+}
+
+/// A `monitors.yaml` entry for each output, named after its connector in
+/// lower case. `active` becomes the primary. An unknown refresh rate is
+/// written as 60.
+pub fn monitor_defs(outputs: &[DetectedOutput], active: Option<&str>) -> MonitorsConfig {
+    let monitors: HashMap<String, MonitorDef> = outputs
+        .iter()
+        .map(|out| {
+            let monitor = MonitorDef {
+                width: out.width,
+                height: out.height,
+                refreshRate: match out.refresh_rate {
+                    0 => 60.0,
+                    rate => f64::from(rate),
+                },
+                vrr: out.capabilities.vrr.unwrap_or(false),
+                hdr: out.capabilities.hdr.unwrap_or(false),
+                primary: active == Some(out.name.as_str()),
+                connector: Some(out.name.clone()),
+                connectors: None,
+                preferOutput: None,
+                iccProfile: None,
+                model: out.model.clone(),
+            };
+            (out.name.to_ascii_lowercase(), monitor)
+        })
+        .collect();
+    MonitorsConfig { monitors }
+}
+
+/// Joins a manufacturer and model name, without repeating the manufacturer
+/// when the model already starts with it (`DELL U2720Q`).
+fn model_name(make: Option<&str>, model: Option<&str>) -> Option<String> {
+    fn clean(s: Option<&str>) -> Option<&str> {
+        s.map(str::trim).filter(|s| !s.is_empty())
+    }
+    match (clean(make), clean(model)) {
+        (Some(make), Some(model))
+            if model
+                .to_ascii_lowercase()
+                .starts_with(&make.to_ascii_lowercase()) =>
+        {
+            Some(model.to_string())
+        }
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model).map(String::from),
+    }
+}
+
+// ============================================================================
+// wlr-randr Backend
+// ============================================================================
+
+pub struct WlrRandr;
+
+#[derive(Deserialize)]
+struct WlrOutput {
+    name: String,
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+    #[serde(default)]
+    adaptive_sync: Option<bool>,
+    #[serde(default)]
+    make: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    refresh: f64,
+    #[serde(default)]
+    preferred: bool,
+    #[serde(default)]
+    current: bool,
+}
+
+impl WlrRandr {
+    fn available() -> bool {
+        Command::new("wlr-randr")
+            .arg("--help")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    fn parse(json: &str) -> Result<Vec<DetectedOutput>> {
+        let outputs: Vec<WlrOutput> =
+            serde_json::from_str(json).context("Failed to parse wlr-randr output")?;
+
+        Ok(outputs
+            .into_iter()
+            .filter_map(|o| {
+                // Prefer the active mode, then the preferred one
+                let mode = o
+                    .modes
+                    .iter()
+                    .find(|m| m.current)
+                    .or_else(|| o.modes.iter().find(|m| m.preferred))?;
+                Some(DetectedOutput {
+                    width: mode.width,
+                    height: mode.height,
+                    refresh_rate: mode.refresh.round() as u32,
+                    enabled: o.enabled,
+                    capabilities: Capabilities {
+                        vrr: o.adaptive_sync,
+                        hdr: None,
+                    },
+                    model: model_name(o.make.as_deref(), o.model.as_deref()),
+                    name: o.name,
+                })
+            })
+            .collect())
+    }
+}
+
+impl MonitorBackend for WlrRandr {
+    fn name(&self) -> &'static str {
+        "wlr-randr"
+    }
+
+    fn outputs(&self) -> Result<Vec<DetectedOutput>> {
+        let output = Command::new("wlr-randr")
+            .arg("--json")
+            .output()
+            .context("Failed to run wlr-randr")?;
+        if !output.status.success() {
+            bail!(
+                "wlr-randr failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+// ============================================================================
+// DRM sysfs Backend
+// ============================================================================
+
+pub struct DrmSysfs {
+    root: PathBuf,
+}
+
+impl Default for DrmSysfs {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/sys/class/drm"),
+        }
+    }
+}
+
+impl MonitorBackend for DrmSysfs {
+    fn name(&self) -> &'static str {
+        "drm"
+    }
+
+    fn outputs(&self) -> Result<Vec<DetectedOutput>> {
+        let entries = fs::read_dir(&self.root)
+            .with_context(|| format!("Failed to read: {}", self.root.display()))?;
+
+        let mut outputs = Vec::new();
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let file_name = entry.file_name();
+            // Connectors are named `card<N>-<connector>`, e.g. `card0-DP-1`
+            let Some((_, connector)) = file_name.to_str().and_then(|n| n.split_once('-')) else {
+                continue;
+            };
+
+            let read = |file: &str| fs::read_to_string(dir.join(file)).unwrap_or_default();
+            if read("status").trim() != "connected" {
+                continue;
+            }
+
+            // The first listed mode is the preferred one
+            let Some((width, height)) = read("modes")
+                .lines()
+                .next()
+                .and_then(|m| m.split_once('x'))
+                .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+            else {
+                continue;
+            };
+
+            let edid = fs::read(dir.join("edid")).unwrap_or_default();
+            outputs.push(DetectedOutput {
+                name: connector.to_string(),
+                width,
+                height,
+                refresh_rate: edid_refresh_rate(&edid).unwrap_or(0),
+                enabled: read("enabled").trim() == "enabled",
+                capabilities: Capabilities {
+                    // VRR support isn't exposed through sysfs
+                    vrr: None,
+                    hdr: (!edid.is_empty()).then(|| edid_supports_hdr(&edid)),
+                },
+                model: edid_model(&edid),
+            });
+        }
+
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(outputs)
+    }
+}
+
+/// Refresh rate of the preferred timing (first detailed timing descriptor).
+fn edid_refresh_rate(edid: &[u8]) -> Option<u32> {
+    let dtd = edid.get(54..72)?;
+    let pixel_clock = u64::from(u16::from_le_bytes([dtd[0], dtd[1]])) * 10_000;
+    let h_total = u64::from(dtd[2]) | (u64::from(dtd[4] >> 4) << 8);
+    let h_total = h_total + (u64::from(dtd[3]) | (u64::from(dtd[4] & 0x0f) << 8));
+    let v_total = u64::from(dtd[5]) | (u64::from(dtd[7] >> 4) << 8);
+    let v_total = v_total + (u64::from(dtd[6]) | (u64::from(dtd[7] & 0x0f) << 8));
+    let pixels = h_total * v_total;
+    (pixel_clock > 0 && pixels > 0).then(|| ((pixel_clock + pixels / 2) / pixels) as u32)
+}
+
+/// The fixed header every EDID base block starts with.
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// PNP manufacturer IDs of common display makers, and their usual names.
+const MANUFACTURERS: &[(&str, &str)] = &[
+    ("ACR", "Acer"),
+    ("AOC", "AOC"),
+    ("APP", "Apple"),
+    ("AUO", "AU Optronics"),
+    ("AUS", "ASUS"),
+    ("BNQ", "BenQ"),
+    ("BOE", "BOE"),
+    ("CMN", "Innolux"),
+    ("DEL", "Dell"),
+    ("GBT", "Gigabyte"),
+    ("GSM", "LG"),
+    ("HPN", "HP"),
+    ("HWP", "HP"),
+    ("IVM", "Iiyama"),
+    ("LEN", "Lenovo"),
+    ("MSI", "MSI"),
+    ("NEC", "NEC"),
+    ("PHL", "Philips"),
+    ("SAM", "Samsung"),
+    ("SDC", "Samsung"),
+    ("SHP", "Sharp"),
+    ("SNY", "Sony"),
+    ("VSC", "ViewSonic"),
+    ("XMI", "Xiaomi"),
+];
+
+/// Manufacturer and product name from an EDID base block, e.g. `Dell
+/// U2720Q`. Unknown manufacturer IDs are shown as-is (`ABC`). `None` for a
+/// missing or malformed EDID.
+fn edid_model(edid: &[u8]) -> Option<String> {
+    // A base block is 128 bytes
+    if edid.len() < 128 || edid[..8] != EDID_HEADER {
+        return None;
+    }
+
+    // Three 5-bit letters, 1 = 'A', big-endian in bytes 8-9
+    let id = u16::from_be_bytes([edid[8], edid[9]]);
+    let letters: Option<String> = [10, 5, 0]
+        .into_iter()
+        .map(|shift| match (id >> shift) & 0x1f {
+            n @ 1..=26 => Some(char::from(b'A' + n as u8 - 1)),
+            _ => None,
+        })
+        .collect();
+    let make = letters.map(|id| {
+        MANUFACTURERS
+            .iter()
+            .find(|(code, _)| *code == id)
+            .map_or(id, |(_, name)| name.to_string())
+    });
+
+    // The product name is a display descriptor (tag 0xfc) among the four 18-byte ones
+    let name = edid[54..126].chunks_exact(18).find_map(|d| {
+        if d[..3] != [0, 0, 0] || d[3] != 0xfc {
+            return None;
+        }
+        let text = &d[5..];
+        let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+        let text = &text[..end];
+        text.iter()
+            .all(|b| b.is_ascii_graphic() || *b == b' ')
+            .then(|| String::from_utf8_lossy(text).trim().to_string())
+    });
+
+    model_name(make.as_deref(), name.as_deref())
+}
+
+/// Looks for an HDR static metadata block in the CTA-861 extension blocks.
+fn edid_supports_hdr(edid: &[u8]) -> bool {
+    edid.chunks_exact(128).skip(1).any(|block| {
+        // CTA extension tag is 0x02; data blocks live between byte 4 and the DTD offset
+        if block[0] != 0x02 {
+            return false;
+        }
+        let end = usize::from(block[2]).min(127);
+        let mut i = 4;
+        while i < end {
+            let tag = block[i] >> 5;
+            let len = usize::from(block[i] & 0x1f);
+            // Extended tag (7) with extended tag code 6 = HDR static metadata
+            if tag == 7 && len >= 1 && block.get(i + 1) == Some(&0x06) {
+                return true;
+            }
+            i += len + 1;
+        }
+        false
+    })
+}
+
+/// Static backend for tests.
+#[cfg(test)]
+pub struct MockBackend {
+    pub outputs: Vec<DetectedOutput>,
+}
+
+#[cfg(test)]
+impl MonitorBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn outputs(&self) -> Result<Vec<DetectedOutput>> {
+        Ok(self.outputs.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn output(name: &str, enabled: bool) -> DetectedOutput {
+        DetectedOutput {
+            name: name.to_string(),
+            width: 2560,
+            height: 1440,
+            refresh_rate: 165,
+            enabled,
+            capabilities: Capabilities {
+                vrr: Some(true),
+                hdr: Some(false),
+            },
+            model: None,
+        }
+    }
+
+    /// Builds a 256-byte EDID with a 2560x1440@~144Hz DTD and optional HDR block.
+    fn mock_edid(hdr: bool) -> Vec<u8> {
+        let mut edid = vec![0u8; 256];
+        // 2560x1440, hblank 160, vblank 41, pixel clock 580.52 MHz
+        edid[54..62].copy_from_slice(&[0xC4, 0xE2, 0x00, 0xA0, 0xA0, 0xA0, 0x29, 0x50]);
+        edid[128] = 0x02;
+        edid[130] = 8;
+        if hdr {
+            edid[132..135].copy_from_slice(&[0xE2, 0x06, 0x05]);
+        }
+        edid
+    }
+
+    /// `mock_edid` with a valid header, manufacturer `id` and product name
+    /// descriptor `name` (in the second descriptor slot).
+    fn mock_edid_named(id: &str, name: &[u8]) -> Vec<u8> {
+        let mut edid = mock_edid(false);
+        edid[..8].copy_from_slice(&EDID_HEADER);
+        let id = id
+            .bytes()
+            .fold(0u16, |acc, c| (acc << 5) | u16::from(c - b'A' + 1));
+        edid[8..10].copy_from_slice(&id.to_be_bytes());
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+        edid[77..90].fill(b' ');
+        edid[77..77 + name.len()].copy_from_slice(name);
+        edid
+    }
+
+    #[test]
+    fn test_mock_active_output_skips_disabled() {
+        let backend = MockBackend {
+            outputs: vec![output("DP-1", false), output("DP-2", true)],
+        };
+        let active = backend.active_output().unwrap().unwrap();
+        assert_eq!(active.name, "DP-2");
+    }
+
+    #[test]
+    fn test_mock_capabilities_lookup() {
+        let backend = MockBackend {
+            outputs: vec![output("DP-1", true)],
+        };
+        assert_eq!(backend.capabilities("DP-1").unwrap().vrr, Some(true));
+        assert!(backend.capabilities("HDMI-A-1").is_err());
+    }
+
+    #[test]
+    fn test_wlr_randr_parse() {
+        let json = r#"[
+            {"name": "DP-1", "enabled": true, "adaptive_sync": true, "modes": [
+                {"width": 1920, "height": 1080, "refresh": 60.0, "preferred": true, "current": false},
+                {"width": 2560, "height": 1440, "refresh": 164.956, "preferred": false, "current": true}
+            ]},
+            {"name": "HDMI-A-1", "enabled": false, "modes": [
+                {"width": 3840, "height": 2160, "refresh": 120.0, "preferred": true, "current": false}
+            ]}
+        ]"#;
+        let outputs = WlrRandr::parse(json).unwrap();
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].width, 2560);
+        assert_eq!(outputs[0].refresh_rate, 165);
+        assert_eq!(outputs[0].capabilities.vrr, Some(true));
+        assert_eq!(outputs[1].width, 3840);
+        assert!(!outputs[1].enabled);
+        assert_eq!(outputs[1].capabilities.vrr, None);
+    }
+
+    #[test]
+    fn test_edid_parsing() {
+        assert_eq!(edid_refresh_rate(&mock_edid(false)), Some(144));
+        assert!(edid_supports_hdr(&mock_edid(true)));
+        assert!(!edid_supports_hdr(&mock_edid(false)));
+        assert_eq!(edid_refresh_rate(&[]), None);
+    }
+
+    #[test]
+    fn test_edid_model() {
+        assert_eq!(
+            edid_model(&mock_edid_named("DEL", b"DELL U2720Q\n")).as_deref(),
+            Some("DELL U2720Q")
+        );
+        assert_eq!(
+            edid_model(&mock_edid_named("GSM", b"27GN950\n")).as_deref(),
+            Some("LG 27GN950")
+        );
+        // Unknown manufacturers keep their ID; no name descriptor is fine
+        assert_eq!(
+            edid_model(&mock_edid_named("XYZ", b"")).as_deref(),
+            Some("XYZ")
+        );
+        let mut no_name = mock_edid_named("SAM", b"");
+        no_name[75] = 0xff;
+        assert_eq!(edid_model(&no_name).as_deref(), Some("Samsung"));
+
+        // Garbage: no header, truncated, or a name with control bytes
+        assert_eq!(edid_model(&mock_edid(false)), None);
+        assert_eq!(edid_model(&[]), None);
+        assert_eq!(edid_model(&EDID_HEADER), None);
+        assert_eq!(
+            edid_model(&mock_edid_named("DEL", b"U27\x01")).as_deref(),
+            Some("Dell")
+        );
+    }
+
+    #[test]
+    fn test_monitor_defs() {
+        let mut tv = output("HDMI-A-1", true);
+        tv.refresh_rate = 0;
+        tv.model = Some("LG OLED65C1".to_string());
+        let config = monitor_defs(&[output("DP-1", true), tv], Some("DP-1"));
+
+        let dp = &config.monitors["dp-1"];
+        assert!(dp.primary && dp.vrr && !dp.hdr);
+        assert_eq!(dp.connector.as_deref(), Some("DP-1"));
+        let tv = &config.monitors["hdmi-a-1"];
+        assert!(!tv.primary);
+        assert_eq!(tv.refreshRate, 60.0);
+        assert_eq!(tv.model.as_deref(), Some("LG OLED65C1"));
+
+        // What detect --yaml prints loads back
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        let loaded: MonitorsConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded, config);
+    }
+
+    #[test]
+    fn test_drm_sysfs_outputs() {
+        let dir = TempDir::new().unwrap();
+        let connected = dir.path().join("card0-DP-1");
+        let disconnected = dir.path().join("card0-HDMI-A-1");
+        fs::create_dir(&connected).unwrap();
+        fs::create_dir(&disconnected).unwrap();
+        fs::write(connected.join("status"), "connected\n").unwrap();
+        fs::write(connected.join("enabled"), "enabled\n").unwrap();
+        fs::write(connected.join("modes"), "2560x1440\n1920x1080\n").unwrap();
+        fs::write(connected.join("edid"), mock_edid(true)).unwrap();
+        fs::write(disconnected.join("status"), "disconnected\n").unwrap();
+
+        let backend = DrmSysfs {
+            root: dir.path().to_path_buf(),
+        };
+        let outputs = backend.outputs().unwrap();
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].name, "DP-1");
+        assert_eq!((outputs[0].width, outputs[0].height), (2560, 1440));
+        assert_eq!(outputs[0].refresh_rate, 144);
+        assert!(outputs[0].enabled);
+        assert_eq!(outputs[0].capabilities.hdr, Some(true));
+        assert_eq!(outputs[0].model, None);
+    }
+}
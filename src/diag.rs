@@ -0,0 +1,204 @@
+//! Diagnostic bundles for failed launches (`run --diag-bundle <dir>`).
+//!
+//! Everything a bug report needs is captured before launch, then written to
+//! a fresh directory only if the launch fails:
+//! - `failure.txt` - what went wrong (exit code, signal, or spawn error)
+//! - `command.txt` - the launch as one runnable shell line
+//! - `resolved.json` - the resolved profile (`show --dump-resolved` format)
+//! - `environment.txt` - the child's full environment after env/unset, with
+//!   the values of likely secrets (`*TOKEN*`, `*KEY*`, ...) redacted
+//! - `version.txt` - wayscope/gamescope versions and session details
+//! - `config/` - copies of the config files that were read
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::profile::ResolvedProfile;
+use crate::util;
+use crate::version;
+
+pub struct Bundle {
+    dir: PathBuf,
+    profile_name: String,
+    command_line: String,
+    resolved_json: String,
+    environment: Vec<(String, String)>,
+    config_files: Vec<PathBuf>,
+}
+
+impl Bundle {
+    /// Captures the launch state; nothing is written until `write`.
+    pub fn new(
+        dir: &Path,
+        profile: &ResolvedProfile,
+        command_line: String,
+        config_files: Vec<PathBuf>,
+    ) -> Result<Self> {
+        let inherited: Vec<_> = std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+            .collect();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            profile_name: profile.name.clone(),
+            command_line,
+            resolved_json: profile.to_json()?,
+            environment: child_environment(
+                inherited,
+                &profile.environment(),
+                &profile.unset_names(),
+            ),
+            config_files,
+        })
+    }
+
+    /// Writes the bundle to a new `wayscope-diag-<profile>-<time>` directory
+    /// and returns its path.
+    pub fn write(&self, failure: &str) -> Result<PathBuf> {
+        let secs = util::now();
+        let out = self.dir.join(format!(
+            "wayscope-diag-{}-{}",
+            util::file_name_part(&self.profile_name),
+            secs
+        ));
+        let config_dir = out.join("config");
+        fs::create_dir_all(&config_dir)
+            .with_context(|| format!("Failed to create: {}", config_dir.display()))?;
+
+        let environment: String = self
+            .environment
+            .iter()
+            .map(|(k, v)| {
+                let v = if is_secret(k) { REDACTED } else { v };
+                format!("{}={}\n", k, v)
+            })
+            .collect();
+        let versions: String = version::report()
+            .into_iter()
+            .map(|(label, value)| format!("{}: {}\n", label, value))
+            .collect();
+
+        let files = [
+            ("failure.txt", format!("{}\n", failure)),
+            ("command.txt", format!("{}\n", self.command_line)),
+            ("resolved.json", format!("{}\n", self.resolved_json)),
+            ("environment.txt", environment),
+            ("version.txt", versions),
+        ];
+        for (name, content) in files {
+            let path = out.join(name);
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write: {}", path.display()))?;
+        }
+
+        for (i, src) in self.config_files.iter().enumerate() {
+            if !src.is_file() {
+                continue;
+            }
+            // Prefixed so the system and user `config.yaml` don't collide
+            let name = src.file_name().unwrap_or_default().to_string_lossy();
+            let dest = config_dir.join(format!("{}-{}", i, name));
+            fs::copy(src, &dest).with_context(|| format!("Failed to copy: {}", src.display()))?;
+        }
+        Ok(out)
+    }
+}
+
+/// Stands in for the value of a variable that looks like a secret.
+const REDACTED: &str = "<redacted>";
+
+/// Name fragments of variables whose values are left out of the bundle:
+/// the inherited environment often carries credentials unrelated to games.
+const SECRET_PARTS: &[&str] = &["TOKEN", "KEY", "SECRET", "PASSWORD"];
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// The child's environment: `inherited` with profile variables applied and
+/// `unset` names removed, sorted.
+fn child_environment(
+    inherited: Vec<(String, String)>,
+    env: &[(String, String)],
+    unset: &[String],
+) -> Vec<(String, String)> {
+    let mut merged: std::collections::BTreeMap<_, _> = inherited.into_iter().collect();
+    merged.extend(env.iter().cloned());
+    for name in unset {
+        merged.remove(name);
+    }
+    merged.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_environment() {
+        let inherited = vec![
+            ("HOME".to_string(), "/home/u".to_string()),
+            ("DISPLAY".to_string(), ":0".to_string()),
+            ("LANG".to_string(), "C".to_string()),
+        ];
+        let env = vec![("LANG".to_string(), "ja_JP.UTF-8".to_string())];
+        let merged = child_environment(inherited, &env, &["DISPLAY".to_string()]);
+        assert_eq!(
+            merged,
+            vec![
+                ("HOME".to_string(), "/home/u".to_string()),
+                ("LANG".to_string(), "ja_JP.UTF-8".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_secret() {
+        for name in [
+            "GITHUB_TOKEN",
+            "aws_secret_access_key",
+            "API_KEY",
+            "DB_PASSWORD",
+        ] {
+            assert!(is_secret(name), "{}", name);
+        }
+        for name in ["HOME", "LANG", "DXVK_HUD"] {
+            assert!(!is_secret(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_write_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.yaml");
+        fs::write(&config, "profiles: {}\n").unwrap();
+
+        let bundle = Bundle {
+            dir: dir.path().join("out"),
+            profile_name: "hdr".to_string(),
+            command_line: "gamescope -- steam".to_string(),
+            resolved_json: "{}".to_string(),
+            environment: vec![
+                ("A".to_string(), "1".to_string()),
+                ("STEAM_API_KEY".to_string(), "hunter2".to_string()),
+            ],
+            config_files: vec![config, dir.path().join("missing.yaml")],
+        };
+        let out = bundle.write("exited with code 1").unwrap();
+
+        assert!(out
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("wayscope-diag-hdr-"));
+        let read = |name: &str| fs::read_to_string(out.join(name)).unwrap();
+        assert_eq!(read("failure.txt"), "exited with code 1\n");
+        assert_eq!(read("command.txt"), "gamescope -- steam\n");
+        assert_eq!(read("environment.txt"), "A=1\nSTEAM_API_KEY=<redacted>\n");
+        assert!(read("version.txt").starts_with("wayscope: "));
+        assert_eq!(read("config/0-config.yaml"), "profiles: {}\n");
+        assert_eq!(fs::read_dir(out.join("config")).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+//! `wayscope doctor`: checks the system for known causes of failed launches.
+//!
+//! Each check is a pure function over what it inspects, so the verdicts can
+//! be tested without depending on the machine running the tests.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::command;
+use crate::config::{Config, RlimitValue};
+use crate::output;
+
+/// File descriptors Wine's esync wants; Proton's documented minimum.
+pub const ESYNC_NOFILE: u64 = 524_288;
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Ok(String),
+    /// A problem, with suggested fixes.
+    Warn(String, Vec<String>),
+}
+
+/// Runs every check; `config` is the loaded config, whose failure to load
+/// only skips the checks that need it.
+pub fn run(config: Result<Config>) {
+    output::header("wayscope doctor");
+    let mut checks = vec![check_nofile(
+        command::current_rlimit("nofile"),
+        std::env::var_os("PROTON_NO_ESYNC").is_some(),
+    )];
+    match config {
+        Ok(config) => {
+            checks.extend(icc_profiles(&config).into_iter().map(|(monitor, path)| {
+                check_icc_profile(&monitor, &path, Path::new(&path).is_file())
+            }))
+        }
+        Err(e) => checks.push(Verdict::Warn(
+            format!("Skipped the config checks: {:#}", e),
+            Vec::new(),
+        )),
+    }
+
+    let mut problems = 0;
+    for verdict in checks {
+        match verdict {
+            Verdict::Ok(message) => output::success(&message),
+            Verdict::Warn(message, advice) => {
+                problems += 1;
+                output::warn(&message);
+                for line in advice {
+                    output::info(&format!("  {}", line));
+                }
+            }
+        }
+    }
+    if problems == 0 {
+        output::success("No problems found");
+    }
+}
+
+/// esync opens an eventfd per synchronization object, so Proton games can
+/// exhaust a low `RLIMIT_NOFILE` and crash or hang in ways that don't point
+/// at the limit. Wine raises its soft limit to the hard limit itself, so the
+/// hard limit is what matters.
+fn check_nofile(limits: Option<(RlimitValue, RlimitValue)>, esync_disabled: bool) -> Verdict {
+    let Some((soft, hard)) = limits else {
+        return Verdict::Warn(
+            "Could not read the file descriptor limit (RLIMIT_NOFILE)".to_string(),
+            Vec::new(),
+        );
+    };
+    if esync_disabled {
+        return Verdict::Ok("esync is disabled (PROTON_NO_ESYNC), fd limit not needed".to_string());
+    }
+    match hard {
+        RlimitValue::Limit(n) if n < ESYNC_NOFILE => Verdict::Warn(
+            format!(
+                "File descriptor limit is low for Proton's esync (soft {}, hard {}, want {})",
+                soft, hard, ESYNC_NOFILE
+            ),
+            vec![
+                format!(
+                    "Raise it for wayscope launches in the profile: rlimits: {{nofile: {}}}",
+                    ESYNC_NOFILE
+                ),
+                "(raising the hard limit needs root; otherwise set it in limits.conf \
+                 or systemd's DefaultLimitNOFILE)"
+                    .to_string(),
+                "Or disable esync for the game: environment: {PROTON_NO_ESYNC: 1}".to_string(),
+            ],
+        ),
+        _ => Verdict::Ok(format!(
+            "File descriptor limit is enough for esync (soft {}, hard {})",
+            soft, hard
+        )),
+    }
+}
+
+/// Each monitor's `iccProfile` as launches expand it, from every profile
+/// that resolves.
+fn icc_profiles(config: &Config) -> BTreeSet<(String, String)> {
+    config
+        .profiles
+        .profiles
+        .keys()
+        .filter_map(|name| config.resolve_profile(name).ok())
+        .filter_map(|profile| Some((profile.monitor_name, profile.icc_profile?)))
+        .collect()
+}
+
+/// gamescope has no option to load an ICC profile, so `iccProfile` is only
+/// shown; a path that doesn't exist is still worth fixing.
+fn check_icc_profile(monitor: &str, path: &str, exists: bool) -> Verdict {
+    if exists {
+        Verdict::Ok(format!(
+            "Monitor '{}': iccProfile {} exists (shown only; gamescope can't load it)",
+            monitor, path
+        ))
+    } else {
+        Verdict::Warn(
+            format!(
+                "Monitor '{}' sets iccProfile '{}', which doesn't exist",
+                monitor, path
+            ),
+            vec!["Fix the path in monitors.yaml, or remove iccProfile".to_string()],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_nofile() {
+        let limits = |soft, hard| Some((RlimitValue::Limit(soft), RlimitValue::Limit(hard)));
+
+        match check_nofile(limits(1024, 4096), false) {
+            Verdict::Warn(message, advice) => {
+                assert!(message.contains("soft 1024, hard 4096"));
+                assert!(advice[0].contains("rlimits: {nofile: 524288}"));
+                assert!(advice.iter().any(|a| a.contains("PROTON_NO_ESYNC")));
+            }
+            other => panic!("expected a warning, got {:?}", other),
+        }
+        // A low soft limit alone is fine: Wine raises it to the hard limit
+        assert!(matches!(
+            check_nofile(limits(1024, ESYNC_NOFILE), false),
+            Verdict::Ok(_)
+        ));
+        assert!(matches!(
+            check_nofile(
+                Some((RlimitValue::Limit(1024), RlimitValue::Unlimited)),
+                false
+            ),
+            Verdict::Ok(_)
+        ));
+        assert!(matches!(
+            check_nofile(limits(1024, 4096), true),
+            Verdict::Ok(_)
+        ));
+        assert!(matches!(check_nofile(None, false), Verdict::Warn(..)));
+    }
+
+    #[test]
+    fn test_check_icc_profile() {
+        assert!(matches!(
+            check_icc_profile("main", "/icc/main.icc", true),
+            Verdict::Ok(_)
+        ));
+        match check_icc_profile("main", "/icc/gone.icc", false) {
+            Verdict::Warn(message, _) => {
+                assert_eq!(
+                    message,
+                    "Monitor 'main' sets iccProfile '/icc/gone.icc', which doesn't exist"
+                );
+            }
+            other => panic!("expected a warning, got {:?}", other),
+        }
+    }
+}
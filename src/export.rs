@@ -0,0 +1,225 @@
+//! `wayscope export-script`: a launch as a standalone shell script.
+//!
+//! The script reproduces what `run` does before exec'ing gamescope: it
+//! applies the profile's `rlimits` with `ulimit`, removes the `unset`
+//! variables, exports the environment and execs the resolved command line.
+//! It needs bash (for `ulimit` and `compgen`) but not wayscope, so it can be
+//! shared or kept as a pinned launch. wayscope-side behaviour (exit
+//! notifications, session logging, `--last`, hooks) is not part of it.
+
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::command::{self, shell_quote};
+use crate::config::RlimitValue;
+use crate::profile::ResolvedProfile;
+
+/// The `ulimit` flag for an `rlimits` name, and whether bash counts the
+/// limit in KiB rather than in the name's own unit (bytes).
+fn ulimit_flag(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "as" => ("-v", true),
+        "core" => ("-c", true),
+        "cpu" => ("-t", false),
+        "data" => ("-d", true),
+        "fsize" => ("-f", true),
+        "memlock" => ("-l", true),
+        "nice" => ("-e", false),
+        "nofile" => ("-n", false),
+        "nproc" => ("-u", false),
+        "rtprio" => ("-r", false),
+        "stack" => ("-s", true),
+        _ => return None,
+    })
+}
+
+/// Sets a soft limit, raising the hard one when needed and capping at it
+/// when that's not permitted, like `command::apply_rlimits`.
+const LIMIT_FUNCTION: &str = r#"limit() {
+    ulimit -S "$1" "$2" 2>/dev/null && return
+    ulimit -H "$1" "$2" 2>/dev/null && ulimit -S "$1" "$2" && return
+    echo "Not permitted to raise the $3 hard limit to $2; using $(ulimit -H "$1")" >&2
+    ulimit -S "$1" "$(ulimit -H "$1")"
+}
+"#;
+
+/// Writes the script to `path` with mode 0755, refusing to replace an
+/// existing file unless `force`.
+pub fn write(path: &Path, script: &str, force: bool) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).mode(0o755);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(path).with_context(|| {
+        if path.exists() && !force {
+            format!(
+                "{} already exists (use --force to overwrite)",
+                path.display()
+            )
+        } else {
+            format!("Failed to create {}", path.display())
+        }
+    })?;
+    file.write_all(script.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    // The mode above only applies to new files (and is subject to the umask)
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to make {} executable", path.display()))
+}
+
+/// The script launching `profile` with `child` (a bare compositor when empty).
+pub fn script(profile: &ResolvedProfile, child: &[String]) -> String {
+    let mut out = String::from("#!/usr/bin/env bash\n");
+    let _ = writeln!(
+        out,
+        "# wayscope profile '{}' on monitor '{}', exported with 'wayscope export-script'.",
+        profile.name, profile.monitor_name
+    );
+    out.push_str("# Runs without wayscope; export it again after changing the profile.\n");
+
+    if !profile.rlimits.is_empty() {
+        out.push('\n');
+        out.push_str(LIMIT_FUNCTION);
+        for (name, value) in &profile.rlimits {
+            let Some((flag, kib)) = ulimit_flag(name) else {
+                continue;
+            };
+            let value = match value {
+                RlimitValue::Limit(n) if kib => n.div_ceil(1024).to_string(),
+                RlimitValue::Limit(n) => n.to_string(),
+                RlimitValue::Unlimited => "unlimited".to_string(),
+            };
+            let _ = writeln!(out, "limit {} {} {}", flag, value, name);
+        }
+    }
+
+    // Globs are matched when the script runs, against that environment
+    let (globs, plain): (Vec<&String>, Vec<&String>) = profile
+        .unset_vars
+        .iter()
+        .partition(|pattern| pattern.contains('*'));
+    if !plain.is_empty() || !globs.is_empty() {
+        out.push('\n');
+    }
+    if !plain.is_empty() {
+        let names: Vec<&str> = plain.iter().map(|name| name.as_str()).collect();
+        let _ = writeln!(out, "unset {}", names.join(" "));
+    }
+    if !globs.is_empty() {
+        let patterns: Vec<&str> = globs.iter().map(|pattern| pattern.as_str()).collect();
+        out.push_str("for name in $(compgen -e); do\n");
+        let _ = writeln!(
+            out,
+            "    case \"$name\" in {}) unset \"$name\" ;; esac",
+            patterns.join(" | ")
+        );
+        out.push_str("done\n");
+    }
+
+    let env = profile.environment();
+    if !env.is_empty() {
+        out.push('\n');
+    }
+    for (key, value) in &env {
+        let _ = writeln!(out, "export {}={}", key, shell_quote(value));
+    }
+
+    out.push('\n');
+    if profile.skip_gamescope {
+        let argv = command::direct_argv(profile, child);
+        let quiet = if profile.quiet_child {
+            " >/dev/null 2>&1"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "exec {}{}",
+            command::quote_join(argv.iter().map(String::as_str)),
+            quiet
+        );
+    } else {
+        let _ = writeln!(out, "exec {}", command::build(profile, child).display());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn resolve(profile: &str) -> ResolvedProfile {
+        let config = Config {
+            monitors: serde_yaml::from_str(
+                r#"
+monitors:
+  main: {width: 1920, height: 1080, refreshRate: 60, primary: true}
+"#,
+            )
+            .unwrap(),
+            profiles: serde_yaml::from_str(&format!("profiles:\n  game: {}\n", profile)).unwrap(),
+        };
+        config.resolve_profile("game").unwrap()
+    }
+
+    fn child() -> Vec<String> {
+        vec!["steam".to_string(), "-bigpicture".to_string()]
+    }
+
+    #[test]
+    fn test_script() {
+        let profile = resolve(
+            r#"{environment: {MSG: "it's here"}, unset: [DISPLAY, "SDL_*", "XDG_*"], rlimits: {nofile: 524288, memlock: 8388608, core: unlimited}}"#,
+        );
+        let script = script(&profile, &child());
+        let lines: Vec<&str> = script.lines().collect();
+
+        assert_eq!(lines[0], "#!/usr/bin/env bash");
+        assert!(lines.contains(&"limit -c unlimited core"));
+        assert!(lines.contains(&"limit -l 8192 memlock"));
+        assert!(lines.contains(&"limit -n 524288 nofile"));
+        assert!(lines.contains(&"unset DISPLAY"));
+        assert!(lines.contains(&"    case \"$name\" in SDL_* | XDG_*) unset \"$name\" ;; esac"));
+        assert!(lines.contains(&"export MSG='it'\\''s here'"));
+        assert_eq!(
+            *lines.last().unwrap(),
+            format!("exec {}", command::build(&profile, &child()).display())
+        );
+        assert!(lines.last().unwrap().ends_with("-- steam -bigpicture"));
+
+        // Exported before the exec, after the unsets
+        let position = |prefix: &str| lines.iter().position(|l| l.starts_with(prefix));
+        assert!(position("unset").unwrap() < position("export").unwrap());
+    }
+
+    #[test]
+    fn test_script_skip_gamescope() {
+        let profile = resolve("{skipGamescope: true, quietChild: true, wrapper: [gamemoderun]}");
+        let script = script(&profile, &child());
+        assert!(!script.contains("limit()"));
+        assert!(script.ends_with("\nexec gamemoderun steam -bigpicture >/dev/null 2>&1\n"));
+    }
+
+    #[test]
+    fn test_write_is_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.sh");
+        write(&path, "#!/usr/bin/env bash\n", false).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let err = write(&path, "echo replaced\n", false).unwrap_err();
+        assert!(err.to_string().contains("already exists (use --force"));
+        write(&path, "echo replaced\n", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "echo replaced\n");
+    }
+}
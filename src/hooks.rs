@@ -0,0 +1,166 @@
+//! `preHook`/`postHook`: shell commands run around a launch.
+//!
+//! A hook runs with `sh -c` in wayscope's own environment, in a process
+//! group of its own so a timeout kills everything it started. A hook that
+//! fails or outlives `preTimeout`/`postTimeout` is handled per
+//! `hookFailure`: `abort` stops the launch (pre) or fails wayscope's exit
+//! (post), `warn` reports it and carries on.
+
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::HookFailure;
+use crate::output;
+use crate::profile::ResolvedProfile;
+
+/// How often a running hook is checked against its timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Pre,
+    Post,
+}
+
+impl Stage {
+    fn field(self) -> &'static str {
+        match self {
+            Self::Pre => "preHook",
+            Self::Post => "postHook",
+        }
+    }
+}
+
+/// How a hook ended.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Succeeded,
+    /// How it failed, e.g. "exited with code 1".
+    Failed(String),
+    /// Killed after running this long.
+    TimedOut(Duration),
+}
+
+/// Runs the profile's hook for `stage`, if it has one.
+pub fn run(profile: &ResolvedProfile, stage: Stage) -> Result<()> {
+    let (hook, timeout) = match stage {
+        Stage::Pre => (&profile.pre_hook, profile.pre_timeout),
+        Stage::Post => (&profile.post_hook, profile.post_timeout),
+    };
+    let Some(hook) = hook else {
+        return Ok(());
+    };
+    output::info(&format!("Running {}: {}", stage.field(), hook));
+    let outcome = execute(hook, timeout.map(Duration::from_secs))?;
+    judge(stage, outcome, profile.hook_failure)
+}
+
+/// Runs `hook` to completion, or kills it once `timeout` has passed.
+fn execute(hook: &str, timeout: Option<Duration>) -> Result<Outcome> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .process_group(0)
+        .spawn()
+        .context("Failed to run hook")?;
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().context("Failed to check hook")? {
+            return Ok(match status.code() {
+                Some(0) => Outcome::Succeeded,
+                Some(code) => Outcome::Failed(format!("exited with code {}", code)),
+                None => Outcome::Failed("was terminated by a signal".to_string()),
+            });
+        }
+        if let Some(timeout) = timeout.filter(|timeout| started.elapsed() >= *timeout) {
+            kill_group(&mut child)?;
+            return Ok(Outcome::TimedOut(timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Kills the hook and everything in its process group, then reaps it.
+fn kill_group(child: &mut Child) -> Result<()> {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill has no memory-safety preconditions
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    child.wait().context("Failed to stop hook")?;
+    Ok(())
+}
+
+/// Applies `policy` to a hook's outcome: an error under `abort`, a warning
+/// under `warn`.
+fn judge(stage: Stage, outcome: Outcome, policy: HookFailure) -> Result<()> {
+    let problem = match outcome {
+        Outcome::Succeeded => return Ok(()),
+        Outcome::Failed(how) => format!("{} {}", stage.field(), how),
+        Outcome::TimedOut(timeout) => format!(
+            "{} was killed after running for {}s",
+            stage.field(),
+            timeout.as_secs_f64()
+        ),
+    };
+    match (policy, stage) {
+        (HookFailure::Abort, Stage::Pre) => bail!("{}; not launching", problem),
+        (HookFailure::Abort, Stage::Post) => bail!("{}", problem),
+        (HookFailure::Warn, _) => {
+            output::warn(&problem);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_outcomes() {
+        assert_eq!(execute("true", None).unwrap(), Outcome::Succeeded);
+        assert_eq!(
+            execute("exit 3", Some(Duration::from_secs(5))).unwrap(),
+            Outcome::Failed("exited with code 3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_execute_timeout_kills_the_hook() {
+        // The trailing command keeps sh from exec'ing sleep, so the kill
+        // has to reach a grandchild through the process group
+        let timeout = Duration::from_millis(200);
+        let started = Instant::now();
+        let outcome = execute("sleep 30; true", Some(timeout)).unwrap();
+        assert_eq!(outcome, Outcome::TimedOut(timeout));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_judge_policy() {
+        let timed_out = || Outcome::TimedOut(Duration::from_secs(2));
+
+        let err = judge(Stage::Pre, timed_out(), HookFailure::Abort).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "preHook was killed after running for 2s; not launching"
+        );
+        let err = judge(
+            Stage::Post,
+            Outcome::Failed("exited with code 1".to_string()),
+            HookFailure::Abort,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "postHook exited with code 1");
+
+        assert!(judge(Stage::Pre, timed_out(), HookFailure::Warn).is_ok());
+        assert!(judge(Stage::Post, Outcome::Succeeded, HookFailure::Abort).is_ok());
+    }
+}
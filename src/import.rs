@@ -0,0 +1,346 @@
+//! Copies profiles from another config file into the user's `config.yaml`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::{MonitorsConfig, ProfilesConfig};
+use crate::output;
+
+/// What to do with an imported profile whose name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conflict {
+    /// Stop without writing anything.
+    Refuse,
+    /// Replace the existing profile (`--force`).
+    Overwrite,
+    /// Import under the first free `name-2`, `name-3`, ... (`--rename`).
+    Rename,
+}
+
+pub fn run(
+    source_path: &Path,
+    monitor_layers: &[PathBuf],
+    profile_layers: &[PathBuf],
+    profiles_path: &Path,
+    names: &[String],
+    conflict: Conflict,
+) -> Result<()> {
+    let source = ProfilesConfig::load(source_path)?;
+    // Only the user's profiles file is rewritten, as with prune, but it's
+    // checked together with the layers below it
+    let monitors = MonitorsConfig::load_layers(monitor_layers)?;
+    let lower = ProfilesConfig::load_below(profile_layers, profiles_path)?;
+    let mut profiles = ProfilesConfig::load(profiles_path)?;
+
+    let changed = import(&mut profiles, &source, names, conflict)?;
+
+    // The same checks a launch makes, so an import can't break the config
+    let merged = lower.overlaid(&profiles);
+    merged
+        .validate()
+        .context("Imported profiles don't validate; nothing was written")?;
+    let dead = merged.dead_monitor_refs(&monitors);
+    let dead = dead
+        .iter()
+        .find(|(name, _, _)| profiles.profiles.contains_key(name));
+    if let Some((name, monitor, _)) = dead {
+        bail!(
+            "Imported profile '{}' uses monitor '{}', which isn't defined in any monitors file; \
+             add it first (nothing was written)",
+            name,
+            monitor
+        );
+    }
+
+    profiles.save(profiles_path)?;
+    for line in changed {
+        output::success(&line);
+    }
+    Ok(())
+}
+
+/// Copies `names` (all of `source`'s profiles when empty) into `target`
+/// and returns a description of each import.
+///
+/// With `Conflict::Rename`, `compose` entries of the imported profiles
+/// follow their renamed fragments.
+fn import(
+    target: &mut ProfilesConfig,
+    source: &ProfilesConfig,
+    names: &[String],
+    conflict: Conflict,
+) -> Result<Vec<String>> {
+    let mut selected: Vec<&str> = if names.is_empty() {
+        source.profiles.keys().map(String::as_str).collect()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                source
+                    .canonical_name(name)
+                    .with_context(|| format!("Can't import '{}'", name))
+            })
+            .collect::<Result<_>>()?
+    };
+    selected.sort();
+    selected.dedup();
+    if selected.is_empty() {
+        bail!("The imported file has no profiles");
+    }
+
+    // A fragment left behind would silently resolve to whatever profile of
+    // that name the user has instead
+    for &name in &selected {
+        for fragment in &source.profiles[name].compose {
+            let Ok(fragment) = source.canonical_name(fragment) else {
+                continue;
+            };
+            if !selected.contains(&fragment) {
+                bail!(
+                    "Profile '{}' composes '{}' from the imported file; import '{}' too",
+                    name,
+                    fragment,
+                    fragment
+                );
+            }
+        }
+    }
+
+    if conflict == Conflict::Refuse {
+        let taken: Vec<_> = selected
+            .iter()
+            .copied()
+            .filter(|name| target.canonical_name(name).is_ok())
+            .collect();
+        if !taken.is_empty() {
+            bail!(
+                "Already defined: {} (use --force to overwrite or --rename to keep both)",
+                taken.join(", ")
+            );
+        }
+    }
+
+    let mut renamed = HashMap::new();
+    if conflict == Conflict::Rename {
+        for &name in &selected {
+            if target.canonical_name(name).is_err() {
+                continue;
+            }
+            let mut n = 2;
+            let free = loop {
+                let candidate = format!("{}-{}", name, n);
+                if target.canonical_name(&candidate).is_err()
+                    && !source.profiles.contains_key(&candidate)
+                    && !renamed.values().any(|taken| *taken == candidate)
+                {
+                    break candidate;
+                }
+                n += 1;
+            };
+            renamed.insert(name, free);
+        }
+    }
+
+    let mut changed = Vec::new();
+    for name in selected {
+        let mut profile = source.profiles[name].clone();
+        for fragment in &mut profile.compose {
+            if let Some(new) = renamed.get(fragment.as_str()) {
+                *fragment = new.clone();
+            }
+        }
+        let line = match renamed.get(name) {
+            Some(new) => format!("Imported profile '{}' as '{}'", name, new),
+            None if target.profiles.contains_key(name) => {
+                format!("Replaced profile '{}'", name)
+            }
+            // A profile wins over an alias of the same name, so drop the alias
+            None if target.aliases.remove(name).is_some() => {
+                format!("Imported profile '{}', replacing the alias", name)
+            }
+            None => format!("Imported profile '{}'", name),
+        };
+        let new_name = renamed.get(name).map_or(name, String::as_str);
+        target.profiles.insert(new_name.to_string(), profile);
+        changed.push(line);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (ProfilesConfig, ProfilesConfig) {
+        let target = serde_yaml::from_str(
+            r#"
+profiles:
+  default: {}
+  hdr: { useHDR: false }
+aliases:
+  couch: default
+"#,
+        )
+        .unwrap();
+        let source = serde_yaml::from_str(
+            r#"
+profiles:
+  hdr: { useHDR: true }
+  couch: { compose: [hdr], useWSI: false }
+  extra: {}
+aliases:
+  h: hdr
+"#,
+        )
+        .unwrap();
+        (target, source)
+    }
+
+    #[test]
+    fn test_import_selected() {
+        let (mut target, source) = fixtures();
+        let changed = import(
+            &mut target,
+            &source,
+            &["extra".to_string()],
+            Conflict::Refuse,
+        )
+        .unwrap();
+        assert_eq!(changed, vec!["Imported profile 'extra'"]);
+        assert!(target.profiles.contains_key("extra"));
+        assert!(!target.profiles.contains_key("couch"));
+
+        // Aliases in the imported file select their profile
+        let changed = import(
+            &mut target,
+            &source,
+            &["h".to_string()],
+            Conflict::Overwrite,
+        )
+        .unwrap();
+        assert_eq!(changed, vec!["Replaced profile 'hdr'"]);
+        assert_eq!(target.profiles["hdr"].use_hdr, Some(true));
+
+        let err = import(
+            &mut target,
+            &source,
+            &["nope".to_string()],
+            Conflict::Refuse,
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Can't import 'nope': Unknown profile 'nope'"
+        );
+    }
+
+    #[test]
+    fn test_import_requires_fragments() {
+        let (mut target, source) = fixtures();
+        let err = import(
+            &mut target,
+            &source,
+            &["couch".to_string()],
+            Conflict::Overwrite,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Profile 'couch' composes 'hdr' from the imported file; import 'hdr' too"
+        );
+        assert_eq!(target, fixtures().0);
+    }
+
+    #[test]
+    fn test_run_checks_lower_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let base_monitors = write(
+            "base-monitors.yaml",
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        );
+        let monitors = write(
+            "monitors.yaml",
+            "monitors:\n  tv: { width: 3840, height: 2160, refreshRate: 60 }",
+        );
+        let base = write("base.yaml", "profiles:\n  tvbase: { monitor: tv }\n");
+        let profiles = write("config.yaml", "profiles:\n  default: {}\n");
+        let source = write(
+            "shared.yaml",
+            "profiles:\n  couch: { compose: [tvbase], useHDR: true }\n  desk: { monitor: gone }\n",
+        );
+        let layers = [base, profiles.clone()];
+
+        // Fragments and monitors from other layers are fine
+        run(
+            &source,
+            &[base_monitors.clone(), monitors.clone()],
+            &layers,
+            &profiles,
+            &["couch".to_string()],
+            Conflict::Refuse,
+        )
+        .unwrap();
+        let imported = ProfilesConfig::load(&profiles).unwrap();
+        assert_eq!(imported.profiles["couch"].compose, vec!["tvbase"]);
+
+        let err = run(
+            &source,
+            &[base_monitors, monitors],
+            &layers,
+            &profiles,
+            &["desk".to_string()],
+            Conflict::Refuse,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Imported profile 'desk' uses monitor 'gone'"));
+        assert!(!ProfilesConfig::load(&profiles)
+            .unwrap()
+            .profiles
+            .contains_key("desk"));
+    }
+
+    #[test]
+    fn test_import_conflicts() {
+        let (mut target, source) = fixtures();
+        let err = import(&mut target, &source, &[], Conflict::Refuse).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Already defined: couch, hdr (use --force to overwrite or --rename to keep both)"
+        );
+        assert_eq!(target, fixtures().0);
+
+        let changed = import(&mut target, &source, &[], Conflict::Rename).unwrap();
+        assert_eq!(
+            changed,
+            vec![
+                "Imported profile 'couch' as 'couch-2'",
+                "Imported profile 'extra'",
+                "Imported profile 'hdr' as 'hdr-2'",
+            ]
+        );
+        // The user's own profiles are untouched, and compose follows the rename
+        assert_eq!(target.profiles["hdr"].use_hdr, Some(false));
+        assert_eq!(target.profiles["couch-2"].compose, vec!["hdr-2"]);
+        assert_eq!(target.aliases["couch"], "default");
+        target.validate().unwrap();
+    }
+
+    #[test]
+    fn test_import_overwrite_replaces_alias() {
+        let (mut target, source) = fixtures();
+        let changed = import(&mut target, &source, &[], Conflict::Overwrite).unwrap();
+        assert_eq!(changed[0], "Imported profile 'couch', replacing the alias");
+        assert!(!target.aliases.contains_key("couch"));
+        assert_eq!(target.profiles["couch"].compose, vec!["hdr"]);
+        target.validate().unwrap();
+    }
+}
@@ -1,12 +1,13 @@
 //! Creates starter config files with all options documented.
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
-use crate::config::MonitorsConfig;
+use crate::config::{self, MonitorsConfig};
 use crate::output;
+use crate::util;
 
 const DEFAULT_MONITORS: &str = r#"# Wayscope Monitor Configuration
 #
@@ -23,10 +24,15 @@ monitors:
   main:
     width: 1920           # Native horizontal resolution
     height: 1080          # Native vertical resolution
-    refreshRate: 60       # Refresh rate in Hz
+    refreshRate: 60       # Refresh rate in Hz (fractional like 59.94 is fine)
     vrr: false            # Hardware supports VRR (FreeSync/G-Sync)?
     hdr: false            # Hardware supports HDR?
     primary: true         # Use this monitor when profile doesn't specify one
+    # connector: DP-1      # Output name from 'wayscope detect'; passed as --prefer-output
+    # connectors: [DP-1, eDP-1]  # Or try several in order (drm backend), e.g. dock then laptop panel
+    # preferOutput: DP-1   # Override the gamescope-facing name (default: derived from connector)
+    # iccProfile: ~/.local/share/icc/main.icc  # Shown by show/monitors; gamescope can't load it yet
+    # model: Dell U2720Q   # Which physical monitor this is (from 'wayscope detect'); informational
 
   # Example: Secondary monitor (TV for couch gaming)
   # tv:
@@ -60,20 +66,65 @@ const DEFAULT_CONFIG: &str = r#"# Wayscope Profile Configuration
 profiles:
   # Default profile - used when no profile is specified
   default:
+    # compose: [base, hdr] # Merge these profiles first, left to right (own settings win)
     # monitor: main        # Which monitor to use (omit to use default monitor)
-    # binary: gamescope    # Path to gamescope binary (default: gamescope)
+    # binary: gamescope    # Path to gamescope binary (default: $WAYSCOPE_GAMESCOPE_BIN, then gamescope)
 
     # HDR/WSI settings
     # If omitted, useHDR defaults to monitor's hdr capability
     # useHDR: true         # Enable HDR output (overrides monitor.hdr)
     useWSI: true           # Enable Gamescope WSI layer
+    # allowHDRWithoutWSI: false  # Silence the warning for HDR with useWSI: false
+    # vrr: false           # Adaptive sync on/off (default: monitor's vrr; on needs a VRR monitor)
+    # immediateFlipsWithVRR: false  # Drop immediate-flips when adaptive-sync is on (true keeps it quietly)
+
+    # Touch input (handhelds/touchscreens)
+    # touchMode: 4         # 0=hover, 1=left, 2=right, 3=middle, 4=passthrough
+    # tablet: true         # Shorthand for touchMode: 4
+
+    # Rotation (vertical monitors, handhelds)
+    # rotation: 90                   # Degrees clockwise: 0, 90, 180, 270
+    # rotationSwapsResolution: true  # Swap output width/height for 90/270
+
+    # disableColorManagement: true   # Troubleshooting: disable gamescope color management
+    # quietChild: true               # Send the game's stdout/stderr to /dev/null
+    # notifyOnExit: true             # Desktop notification when the session ends
+    # logSessions: true              # Record launches for 'wayscope sessions'
+    # skipGamescope: true            # Only apply the environment (like run -s)
+    # wrapper: [gamemoderun]         # Prepended to the launch
+    # systemdScope: true            # Launch in a transient systemd-run --user --scope
+    # scopeProperties: {MemoryMax: 16G}  # Scope unit properties (imply systemdScope)
+    # rlimits: {nofile: 524288, memlock: unlimited}  # Resource limits (as in ulimit) for the session
+    # wrapperTarget: child           # Wrap only the game, not gamescope (default: gamescope)
+    # xwaylandCount: 2               # Number of Xwayland servers (1-4)
+    # drmMode: cvt                   # Generate a DRM mode (cvt, fixed); drm backend only
+    # supersample: 1.5               # Render at 1.5x the output size and downscale (1.0-2.0)
+    # filter: fsr                    # Upscale filter (linear, nearest, fsr, nis, pixel)
+    # windowMode: borderless         # fullscreen (default), borderless or windowed
+    # hideCursorDelay: 3000          # Hide the cursor after 3s of inactivity (ms)
+    # cursorScaleHeight: 1080        # Scale the cursor as if the output were this tall
+    # windowTitle: "${GAME} via wayscope"  # ${VAR} expands from the environment
+    # locale: ja_JP.UTF-8            # Sets LANG and LC_ALL for the game
+    # timezone: Asia/Tokyo           # Sets TZ for the game
+    # protonVersion: GE-Proton9-20   # Exported as PROTON_VERSION
+    # compatDataPath: ~/Games/prefixes/${GAME}  # STEAM_COMPAT_DATA_PATH (~ and ${VAR} expand)
+    # compatClientInstallPath: ~/.steam/steam   # STEAM_COMPAT_CLIENT_INSTALL_PATH
+    # gamescopeLog: debug            # gamescope's own log level (silent, error, warning, info, debug)
+    # preHook: "pkill -STOP picom"   # Shell command run before the launch
+    # postHook: "pkill -CONT picom"  # Shell command run after the session exits
+    # preTimeout: 10                 # Kill a hook running longer than this (seconds)
+    # hookFailure: warn              # abort (default) or warn on a failed or killed hook
+
+    # HDR brightness tuning (only with useHDR)
+    # sdrNits: 203                   # SDR content brightness (--hdr-sdr-content-nits)
+    # hdrMaxNits: 1000               # Inverse tone mapping peak (--hdr-itm-target-nits)
 
     # Gamescope command-line options
     # These override the defaults derived from your monitor config
     options:
-      # backend: sdl               # Display backend (sdl, wayland, drm)
-      # fullscreen: true           # Run in fullscreen mode
-      # borderless: false          # Borderless window mode
+      # backend: sdl               # Display backend (sdl, wayland, drm, auto, headless, openvr)
+      # fullscreen: true           # Run in fullscreen mode (prefer the profile's 'windowMode')
+      # borderless: false          # Borderless window mode (prefer the profile's 'windowMode')
       # grab: false                # Grab keyboard/mouse
       # force-grab-cursor: false   # Force cursor grab
 
@@ -84,9 +135,8 @@ profiles:
       # nested-height: 1080        # Internal render height
       # nested-refresh: 165        # Internal refresh rate (auto from monitor)
 
-      # Upscaling options
-      # filter: linear             # Upscale filter (linear, nearest, fsr, nis)
-      # fsr-sharpness: 2           # FSR sharpness (0-20, 0=max sharp)
+      # Upscaling options (pick the filter with the profile's 'filter')
+      # sharpness: 2               # fsr/nis sharpness (0-20, 0=max sharp)
       # nis-sharpness: 10          # NIS sharpness (0-20)
 
       # Performance options
@@ -97,18 +147,37 @@ profiles:
       # Visual options
       # fade-out-duration: 200     # Fade duration in ms when losing focus
 
+    # Options merged only when the resolved monitor supports HDR / VRR
+    # optionsWhenHdr:
+    #   hdr-sdr-content-nits: 203
+    # optionsWhenVrr:
+    #   framerate-limit: 0
+
     # Environment variables passed to games
     # These are in addition to wayscope's default environment
     environment:
       # MANGOHUD: 1                # Enable MangoHud overlay
       # DXVK_ASYNC: 1              # Enable DXVK async shader compilation
       # PROTON_USE_WINED3D: 1      # Use WineD3D instead of DXVK
+      # PROTON_ADD_CONFIG: "+mangohud"  # Leading '+' appends to wayscope's default ('++' for a literal '+')
+      # WINEPREFIX: "${HOME}/prefixes/game"  # ${VAR} reads this profile's variables, then the shell
+    # envPriority: base          # Let wayscope's defaults win over the values above (default: user)
+    # disableBaseEnv: [amd]      # Skip default groups: amd (RADV), wayland, proton
+
+    # Load KEY=VALUE lines from a dotenv file (inline environment wins)
+    # envFile: ~/games/common.env
 
     # Environment variables to remove (unset) from the child process
     # This removes variables from both wayscope defaults AND parent environment
     # unset:
     #   - SDL_VIDEODRIVER         # Remove forced Wayland driver
     #   - PROTON_ENABLE_WAYLAND   # Disable Proton Wayland
+    #   - DXVK_*                  # Globs: '*' matches any characters
+
+    # Variables for the game only; gamescope itself doesn't see them
+    # gameEnv:
+    #   MANGOHUD: 1
+    # gameUnset: [LD_PRELOAD]     # Exact names, removed for the game only
 
   # Example: HDR gaming profile (for games with native HDR support)
   # hdr:
@@ -129,11 +198,11 @@ profiles:
   # performance:
   #   useHDR: false
   #   useWSI: true
+  #   filter: fsr
   #   options:
   #     nested-width: 1920
   #     nested-height: 1080
-  #     filter: fsr
-  #     fsr-sharpness: 5
+  #     sharpness: 5
 
   # Example: Couch gaming on TV
   # couch:
@@ -148,13 +217,26 @@ profiles:
   #   binary: /nix/store/xxx-gamescope/bin/gamescope
   #   useHDR: true
   #   useWSI: true
+
+# Profile used when -p and $WAYSCOPE_PROFILE are not set (default: "default")
+# defaultProfile: default
+
+# Short names for profiles (alias -> profile name)
+# aliases:
+#   perf: performance
 "#;
 
-pub fn run(force: bool) -> Result<()> {
+pub fn run(force: bool, preview: bool) -> Result<()> {
     let config_dir = MonitorsConfig::config_dir();
     let monitors_path = config_dir.join("monitors.yaml");
     let profiles_path = config_dir.join("config.yaml");
 
+    if preview {
+        print_template(&monitors_path, DEFAULT_MONITORS);
+        print_template(&profiles_path, DEFAULT_CONFIG);
+        return Ok(());
+    }
+
     if !config_dir.exists() {
         fs::create_dir_all(&config_dir)
             .with_context(|| format!("Failed to create directory: {}", config_dir.display()))?;
@@ -172,6 +254,95 @@ pub fn run(force: bool) -> Result<()> {
     Ok(())
 }
 
+/// `reset`: backs up both config files and rewrites them with the defaults.
+pub fn reset(monitors_path: &Path, profiles_path: &Path, yes: bool) -> Result<()> {
+    let files = [
+        (monitors_path, DEFAULT_MONITORS),
+        (profiles_path, DEFAULT_CONFIG),
+    ];
+    if files
+        .iter()
+        .all(|(path, content)| fs::read_to_string(path).is_ok_and(|c| c == *content))
+    {
+        output::info("Both config files already hold the defaults; nothing to reset");
+        return Ok(());
+    }
+
+    if !yes {
+        let question = format!(
+            "Replace {} and {} with the defaults (backups are kept)?",
+            monitors_path.display(),
+            profiles_path.display()
+        );
+        if !output::confirm(&question).context("Pass --yes to reset without prompting")? {
+            output::info("Nothing was changed");
+            return Ok(());
+        }
+    }
+
+    let stamp = util::format_local_time(util::now(), c"%Y%m%d-%H%M%S");
+    for (path, content) in files {
+        reset_file(path, content, &stamp)?;
+    }
+    Ok(())
+}
+
+fn reset_file(path: &Path, content: &str, stamp: &str) -> Result<()> {
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+        config::write_atomic(path, content)?;
+        output::success(&format!("Created {}", path.display()));
+        return Ok(());
+    }
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        output::info(&format!("Unchanged {}", path.display()));
+        return Ok(());
+    }
+
+    // Copied rather than moved: the original stays in place until the
+    // defaults atomically replace it
+    let backup = backup_path(path, stamp);
+    fs::copy(path, &backup).with_context(|| {
+        format!(
+            "Failed to back up {} to {}",
+            path.display(),
+            backup.display()
+        )
+    })?;
+    config::write_atomic(path, content)?;
+    output::success(&format!(
+        "Reset {} (backup: {})",
+        path.display(),
+        backup.display()
+    ));
+    Ok(())
+}
+
+/// `config.yaml.<stamp>.bak`, numbered if that's taken (two resets a second).
+fn backup_path(path: &Path, stamp: &str) -> PathBuf {
+    let name = |suffix: String| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}{}.bak", stamp, suffix));
+        PathBuf::from(name)
+    };
+    let mut backup = name(String::new());
+    let mut n = 2;
+    while backup.exists() {
+        backup = name(format!("-{}", n));
+        n += 1;
+    }
+    backup
+}
+
+/// Prints a template under a `# ==> path <==` header (like `head` does for multiple files).
+fn print_template(path: &Path, content: &str) {
+    println!("# ==> {} <==", path.display());
+    println!("{}", content);
+}
+
 fn write_config_file(path: &Path, content: &str, force: bool) -> Result<()> {
     if path.exists() && !force {
         output::warn(&format!(
@@ -240,6 +411,44 @@ mod tests {
         assert_eq!(fs::read_to_string(&path).unwrap(), "original");
     }
 
+    #[test]
+    fn test_reset_backs_up() {
+        let dir = TempDir::new().unwrap();
+        let monitors = dir.path().join("monitors.yaml");
+        let profiles = dir.path().join("config.yaml");
+        fs::write(&profiles, "profiles: {broken").unwrap();
+
+        reset(&monitors, &profiles, true).unwrap();
+        assert_eq!(fs::read_to_string(&monitors).unwrap(), DEFAULT_MONITORS);
+        assert_eq!(fs::read_to_string(&profiles).unwrap(), DEFAULT_CONFIG);
+
+        let backups: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1, "{:?}", backups);
+        assert!(backups[0].starts_with("config.yaml."));
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&backups[0])).unwrap(),
+            "profiles: {broken"
+        );
+    }
+
+    #[test]
+    fn test_backup_path_is_free() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        let first = backup_path(&path, "20250101-120000");
+        assert_eq!(first, dir.path().join("config.yaml.20250101-120000.bak"));
+
+        fs::write(&first, "").unwrap();
+        assert_eq!(
+            backup_path(&path, "20250101-120000"),
+            dir.path().join("config.yaml.20250101-120000-2.bak")
+        );
+    }
+
     #[test]
     fn test_write_config_file_force_overwrites() {
         let dir = TempDir::new().unwrap();
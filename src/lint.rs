@@ -0,0 +1,350 @@
+//! `wayscope lint`: settings that load fine but do nothing.
+//!
+//! Validation rejects configs that can't work; lint points out ones that
+//! work but carry dead weight: options repeating the monitor defaults,
+//! `unset` entries with nothing to remove, `environment` entries repeating
+//! a base variable, profiles identical to another one, and profiles that
+//! differ on paper but launch exactly like another one.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+
+use crate::command;
+use crate::config::{base_options, Config, HookFailure, ProfileDef, RlimitValue};
+use crate::output;
+use crate::profile::ResolvedProfile;
+
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub profile: String,
+    pub message: String,
+    pub suggestion: String,
+}
+
+pub fn run(config: &Config) -> Result<()> {
+    let inherited: Vec<String> = std::env::vars_os()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .collect();
+    let findings = lint(config, &inherited)?;
+
+    output::header("wayscope lint");
+    for finding in &findings {
+        output::warn(&format!(
+            "Profile '{}': {}",
+            finding.profile, finding.message
+        ));
+        output::info(&format!("  {}", finding.suggestion));
+    }
+    match findings.len() {
+        0 => output::success("Nothing to clean up"),
+        n => output::info(&format!("{} finding{}", n, if n == 1 { "" } else { "s" })),
+    }
+    Ok(())
+}
+
+/// Lints every profile, in name order. `inherited` is the environment the
+/// launch would inherit, which `unset` entries may be aimed at.
+pub fn lint(config: &Config, inherited: &[String]) -> Result<Vec<Finding>> {
+    let mut names: Vec<_> = config.profiles.profiles.keys().collect();
+    names.sort();
+
+    let mut findings = Vec::new();
+    let mut seen: Vec<(&str, ProfileDef)> = Vec::new();
+    // The first profile (by name) with each launch represents its group
+    let mut launches: HashMap<Launch, &str> = HashMap::new();
+    for name in names {
+        let finding = |message: String, suggestion: String| Finding {
+            profile: name.clone(),
+            message,
+            suggestion,
+        };
+        let resolved = match config.resolve_profile(name) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                findings.push(finding(
+                    format!("doesn't resolve: {:#}", e),
+                    format!("Run 'wayscope show {}' for details", name),
+                ));
+                continue;
+            }
+        };
+
+        // Composed-in entries are reported on the profile that sets them
+        let own = &config.profiles.profiles[name];
+        for key in redundant_options(config, &resolved)? {
+            findings.push(finding(
+                format!(
+                    "options.{} repeats what monitor '{}' already gives it",
+                    key, resolved.monitor_name
+                ),
+                format!("Remove options.{}", key),
+            ));
+        }
+        let unused = resolved.unused_unsets(inherited);
+        for pattern in unused
+            .into_iter()
+            .filter(|p| own.unset.iter().any(|u| u == p))
+        {
+            findings.push(finding(
+                format!(
+                    "unset '{}' matches nothing in the environment it builds or in the shell lint runs from",
+                    pattern
+                ),
+                "Remove it, unless the variable is set where you launch from".to_string(),
+            ));
+        }
+        let matching = resolved.env_matching_base();
+        for (key, value) in matching
+            .into_iter()
+            .filter(|(k, _)| own.environment.contains_key(*k))
+        {
+            findings.push(finding(
+                format!(
+                    "environment.{} is '{}', which wayscope already sets",
+                    key, value
+                ),
+                format!("Remove environment.{}", key),
+            ));
+        }
+
+        // Compared after composing, so a profile that only composes one other is caught
+        let mut def = config.profiles.composed(name)?;
+        def.duplicate_keys.clear();
+        if let Some((other, _)) = seen.iter().find(|(_, seen)| *seen == def) {
+            findings.push(finding(
+                format!("is identical to '{}'", other),
+                format!(
+                    "Replace it with an alias: 'aliases: {{{}: {}}}'",
+                    name, other
+                ),
+            ));
+            continue;
+        }
+        seen.push((name, def));
+
+        match launches.entry(Launch::of(&resolved)) {
+            Entry::Occupied(other) => findings.push(finding(
+                format!("launches exactly like '{}'", other.get()),
+                format!(
+                    "Replace it with an alias ('aliases: {{{}: {}}}') unless the two are meant to diverge",
+                    name,
+                    other.get()
+                ),
+            )),
+            Entry::Vacant(slot) => {
+                slot.insert(name);
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// Everything launching a profile does, for finding profiles that launch
+/// alike.
+#[derive(PartialEq, Eq, Hash)]
+struct Launch {
+    skip_gamescope: bool,
+    /// With a placeholder child, so the child-side `env` and wrapper count.
+    command: String,
+    quiet_child: bool,
+    environment: Vec<(String, String)>,
+    /// The patterns rather than their expansion, which depends on this shell.
+    unset: Vec<String>,
+    rlimits: BTreeMap<String, RlimitValue>,
+    pre_hook: Option<String>,
+    post_hook: Option<String>,
+    pre_timeout: Option<u64>,
+    post_timeout: Option<u64>,
+    hook_failure: HookFailure,
+    log_sessions: bool,
+    notify_on_exit: bool,
+}
+
+impl Launch {
+    fn of(profile: &ResolvedProfile) -> Self {
+        let child = ["COMMAND".to_string()];
+        let command = if profile.skip_gamescope {
+            let argv = command::direct_argv(profile, &child);
+            command::quote_join(argv.iter().map(String::as_str))
+        } else {
+            command::build(profile, &child).display()
+        };
+        let mut unset = profile.unset_vars.clone();
+        unset.sort();
+        unset.dedup();
+        Self {
+            skip_gamescope: profile.skip_gamescope,
+            command,
+            quiet_child: profile.quiet_child,
+            environment: profile.environment(),
+            unset,
+            rlimits: profile.rlimits.clone(),
+            pre_hook: profile.pre_hook.clone(),
+            post_hook: profile.post_hook.clone(),
+            pre_timeout: profile.pre_timeout,
+            post_timeout: profile.post_timeout,
+            hook_failure: profile.hook_failure,
+            log_sessions: profile.log_sessions,
+            notify_on_exit: profile.notify_on_exit,
+        }
+    }
+}
+
+/// The profile's own options that equal the monitor-derived value and that
+/// resolve the same without them (so a rotation swap or a conditional block
+/// isn't mistaken for a repeat), sorted. The candidates are dropped
+/// together and the profile resolved once more to compare against.
+fn redundant_options(config: &Config, resolved: &ResolvedProfile) -> Result<Vec<String>> {
+    let name = resolved.name.as_str();
+    let profile = &config.profiles.profiles[name];
+    let defaults = base_options(config.monitors.get(&resolved.monitor_name)?);
+
+    let candidates: Vec<&String> = profile
+        .options
+        .iter()
+        .filter(|(key, value)| defaults.get(*key) == Some(*value))
+        .map(|(key, _)| key)
+        .collect();
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut without = config.clone();
+    if let Some(def) = without.profiles.profiles.get_mut(name) {
+        def.options.retain(|key, _| !candidates.contains(&key));
+    }
+    let base = without.resolve_profile(name)?;
+    let mut redundant: Vec<String> = candidates
+        .into_iter()
+        .filter(|key| base.options.get(*key) == resolved.options.get(*key))
+        .cloned()
+        .collect();
+    redundant.sort();
+    Ok(redundant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(profiles: &str) -> Config {
+        let monitors = serde_yaml::from_str(
+            r#"
+monitors:
+  main:
+    width: 2560
+    height: 1440
+    refreshRate: 165
+    primary: true
+"#,
+        )
+        .unwrap();
+        Config {
+            monitors,
+            profiles: serde_yaml::from_str(profiles).unwrap(),
+        }
+    }
+
+    fn messages(config: &Config, inherited: &[&str]) -> Vec<String> {
+        let inherited: Vec<_> = inherited.iter().map(|s| s.to_string()).collect();
+        lint(config, &inherited)
+            .unwrap()
+            .into_iter()
+            .map(|f| format!("{}: {}", f.profile, f.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean() {
+        let config = config(
+            r#"
+profiles:
+  default: {useWSI: true}
+  hdr: {useHDR: true, options: {output-width: 1920}}
+"#,
+        );
+        assert!(messages(&config, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_lint_findings() {
+        let config = config(
+            r#"
+profiles:
+  base:
+    options: {rt: true, nested-refresh: 165, fullscreen: false}
+    environment: {SDL_VIDEODRIVER: wayland, MANGOHUD: "1"}
+    unset: [DISPLAY, NOT_SET_ANYWHERE, "XDG_*"]
+  copy:
+    compose: [base]
+"#,
+        );
+        assert_eq!(
+            messages(&config, &["DISPLAY", "XDG_RUNTIME_DIR"]),
+            vec![
+                "base: options.nested-refresh repeats what monitor 'main' already gives it",
+                "base: options.rt repeats what monitor 'main' already gives it",
+                "base: unset 'NOT_SET_ANYWHERE' matches nothing in the environment it builds or in the shell lint runs from",
+                "base: environment.SDL_VIDEODRIVER is 'wayland', which wayscope already sets",
+                "copy: is identical to 'base'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_same_launch() {
+        // Written differently, but the typed field and the option agree
+        let config = config(
+            r#"
+profiles:
+  a: {useHDR: true, options: {filter: fsr}, unset: [DISPLAY, "SDL_*"]}
+  b: {useHDR: true, options: {filter: fsr}, unset: ["SDL_*", DISPLAY, DISPLAY]}
+  c: {options: {rt: false}, environment: {MANGOHUD: "1"}}
+  d: {options: {rt: false}, environment: {MANGOHUD: "0"}}
+  e: {options: {rt: false}, gameEnv: {MANGOHUD: "1"}}
+  f: {useHDR: true, filter: fsr, unset: [DISPLAY, "SDL_*"]}
+"#,
+        );
+        assert_eq!(
+            messages(&config, &["DISPLAY", "SDL_VIDEO_X11"]),
+            vec![
+                "b: launches exactly like 'a'",
+                "f: launches exactly like 'a'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_launch_side_effects_count() {
+        // Same command line, but each runs differently around the launch
+        let config = config(
+            r#"
+profiles:
+  a: {preHook: "true"}
+  b: {preHook: "true", hookFailure: warn}
+  c: {preHook: "true", preTimeout: 5}
+  d: {preHook: "true", logSessions: true}
+  e: {preHook: "true", notifyOnExit: true}
+  f: {preHook: "true", postHook: "true"}
+"#,
+        );
+        assert!(messages(&config, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_lint_rotation_swap_is_not_redundant() {
+        // The swap would turn output-width into 1440; pinning 2560 matters
+        let config = config(
+            r#"
+profiles:
+  vertical:
+    rotation: 90
+    rotationSwapsResolution: true
+    options: {output-width: 2560}
+"#,
+        );
+        assert!(messages(&config, &[]).is_empty());
+    }
+}
@@ -7,90 +7,867 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 
-use crate::cli::{Cli, Commands};
+use crate::cli::{Cli, Commands, ConfigPathMode};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
 use crate::config::{Config, MonitorsConfig, ProfilesConfig};
+use crate::profile::{EnvChange, ResolvedProfile};
 
 mod cli;
 mod command;
+mod completions;
 mod config;
+mod detect;
+mod diag;
+mod doctor;
+mod export;
+mod hooks;
+mod import;
 mod init;
+mod lint;
 mod output;
 mod profile;
+mod prune;
+mod sessions;
+mod state;
+mod util;
+mod version;
+mod watch;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    output::set_color(cli.color);
 
     match &cli.command {
-        Commands::Init { force } => init::run(*force),
+        Commands::Init { force, preview } => init::run(*force, *preview),
         Commands::Run(args) => run_gamescope(&cli, args),
-        Commands::List => list_profiles(&cli),
-        Commands::Show { profile } => show_profile(&cli, profile),
-        Commands::Monitors => list_monitors(&cli),
+        Commands::List {
+            skip_broken,
+            flat: true,
+        } => list_flat(&cli, *skip_broken),
+        Commands::List { skip_broken, .. } => list_profiles(&cli, *skip_broken),
+        Commands::Show {
+            profile,
+            dump_resolved: true,
+            ..
+        } => dump_resolved(&cli, profile),
+        Commands::Show {
+            profile,
+            command: true,
+            with_env,
+            ..
+        } => print_command(&cli, profile, *with_env),
+        Commands::Show {
+            profile,
+            explain_order: true,
+            ..
+        } => explain_env_order(&cli, profile),
+        Commands::Show {
+            profile,
+            flat: true,
+            ..
+        } => show_flat(&cli, profile),
+        Commands::Show {
+            profile,
+            explain_workaround,
+            env_inline,
+            print_unset,
+            group,
+            overrides_only,
+            ..
+        } => show_profile(
+            &cli,
+            profile,
+            *explain_workaround,
+            *env_inline,
+            *print_unset,
+            *group,
+            *overrides_only,
+        ),
+        Commands::Get { profile, key } => get_value(&cli, profile, key),
+        Commands::Prune { yes } => prune_profiles(&cli, *yes),
+        Commands::Reset { yes } => reset_config(&cli, *yes),
+        Commands::Import {
+            file,
+            profiles,
+            force,
+            rename,
+        } => import_profiles(&cli, file, profiles, *force, *rename),
+        Commands::Monitors { unused: true, .. } => list_unused_monitors(&cli),
+        Commands::Monitors {
+            diff,
+            dump_defaults,
+            changed,
+            ..
+        } => list_monitors(&cli, diff.as_deref(), dump_defaults.as_deref(), *changed),
+        Commands::Detect { output, yaml } => list_detected(output.as_deref(), *yaml),
+        Commands::Sessions { count } => list_sessions(*count),
+        Commands::ExportScript {
+            profile,
+            output,
+            force,
+            command,
+        } => export_script(&cli, profile, output.as_deref(), *force, command),
+        Commands::Lint => lint::run(&load_config(&cli)?),
+        Commands::Doctor => {
+            doctor::run(load_config(&cli));
+            Ok(())
+        }
+        Commands::Completions { shell } => completions::print(*shell),
+        Commands::Version { verbose } => {
+            version::print(*verbose);
+            Ok(())
+        }
+        Commands::CompleteProfiles => {
+            complete_profiles(&cli);
+            Ok(())
+        }
+        Commands::CompleteMonitors => {
+            complete_monitors(&cli);
+            Ok(())
+        }
     }
 }
 
 fn run_gamescope(cli: &Cli, args: &cli::RunArgs) -> Result<()> {
-    if std::env::var("GAMESCOPE_WAYLAND_DISPLAY").is_ok() {
-        output::warn("Already inside Gamescope, running command directly...");
-        return command::exec_direct(&args.command);
+    let last = if args.last {
+        Some(state::LastLaunch::load(&state::last_path()?)?)
+    } else {
+        None
+    };
+    let child = match &last {
+        Some(last) => last.command.clone(),
+        None => args.child_command(),
+    };
+
+    let nesting = command::nesting(
+        std::env::var(profile::SESSION_ENV).ok().as_deref(),
+        std::env::var_os("GAMESCOPE_WAYLAND_DISPLAY").is_some(),
+    );
+    // Launchers asking for the child run gamescope themselves
+    if nesting != command::Nesting::None && !args.force_nested && !args.print_child_only {
+        if args.echo_only {
+            println!(
+                "{}",
+                command::runnable_line(&[], &[], child.iter().map(String::as_str))
+            );
+            return Ok(());
+        }
+        let inside = match nesting {
+            command::Nesting::Wayscope => "a wayscope gamescope session",
+            _ => "Gamescope",
+        };
+        output::warn(&format!(
+            "Already inside {}, running command directly (--force-nested to nest)...",
+            inside
+        ));
+        if args.dry_run {
+            return check_executables(&child_executable(&child));
+        }
+        // No profile is resolved here, so its hooks don't run either
+        return command::exec_direct(&child);
+    }
+    if args.watch {
+        return watch_run(cli, args);
     }
 
-    let config = load_config(cli)?;
-    let profile = config
-        .resolve_profile(&args.profile)
-        .with_context(|| format!("Failed to resolve profile '{}'", args.profile))?;
+    let (mut profile, source) = match (last, &args.from_resolved) {
+        (Some(last), _) => (last.profile, None),
+        (None, Some(path)) => (load_resolved(path)?, None),
+        (None, None) => {
+            let (profile, source, _) = resolve_selected(cli, args, &mut None)?;
+            (profile, Some(source))
+        }
+    };
+    check_strict(cli, &profile)?;
+    let forced = apply_run_overrides(args, &mut profile);
+    let scope_missing = drop_missing_scope(&mut profile);
+
+    // Without gamescope the wrapper (if any) wraps the child directly
+    let direct = command::direct_argv(&profile, &child);
+
+    if args.print_child_only {
+        println!("{}", command::build(&profile, &child).child_line());
+        return Ok(());
+    }
+
+    // Only the runnable line: no decoration, so it can be piped or pasted
+    if args.echo_only {
+        let line = if profile.skip_gamescope {
+            command::runnable_line(
+                &profile.environment(),
+                &profile.unset_names(),
+                direct.iter().map(String::as_str),
+            )
+        } else {
+            command::build(&profile, &child).runnable_line()
+        };
+        println!("{}", line);
+        return Ok(());
+    }
 
     output::profile(&profile.name, &profile.monitor_name);
+    match (source, &args.from_resolved) {
+        (Some(config::ProfileSource::Flag), _) => {}
+        (Some(source), _) => output::info(&format!("Profile selected via {}", source)),
+        (None, Some(path)) => output::info(&format!("Profile loaded from {}", path.display())),
+        (None, None) => output::info("Replaying last launch"),
+    }
+    for note in forced {
+        output::info(note);
+    }
+    for warning in profile.warnings() {
+        output::warn(&warning);
+    }
+    if scope_missing {
+        warn_missing_scope();
+    }
     let env = profile.environment();
+    let unset = profile.unset_names();
     output::environment(&env);
 
-    if args.skip_gamescope {
-        output::warn("Skipping gamescope, running command directly with profile environment...");
-        return command::exec_direct_with_env(&args.command, &env, &profile.unset_vars);
+    if args.dry_run {
+        return dry_run(&profile, &child);
+    }
+
+    let diag = match &args.diag_bundle {
+        Some(dir) => {
+            let line = if profile.skip_gamescope {
+                command::runnable_line(&env, &unset, direct.iter().map(String::as_str))
+            } else {
+                command::build(&profile, &child).runnable_line()
+            };
+            let files = match &args.from_resolved {
+                Some(path) => vec![path.clone()],
+                // Not the envFile: its secrets would be copied verbatim
+                None => config_files(cli, None),
+            };
+            Some(diag::Bundle::new(dir, &profile, line, files)?)
+        }
+        None => None,
+    };
+
+    if profile.skip_gamescope {
+        let reason = if args.skip_gamescope_override().is_some() {
+            ""
+        } else {
+            " (profile sets skipGamescope)"
+        };
+        output::warn(&format!(
+            "Skipping gamescope{}, running command directly with profile environment...",
+            reason
+        ));
+        confirm_launch(args)?;
+        hooks::run(&profile, hooks::Stage::Pre)?;
+        record_launch(&profile, &child);
+        apply_rlimits(&profile);
+        let session = sessions::log_start(&profile, &child);
+        if args.detach {
+            // Quiet means the (only) process's output is dropped, log or not
+            let log = args.log.as_deref().filter(|_| !profile.quiet_child);
+            let command = command::direct_command(&direct, &env, &unset, profile.quiet_child)?;
+            return report_detached(command::detach(command, log)?, &profile);
+        }
+        if stays_resident(&profile, diag.as_ref()) {
+            let command = command::direct_command(&direct, &env, &unset, profile.quiet_child)?;
+            return spawn_and_report(command, &profile, &child, diag.as_ref(), session.as_ref());
+        }
+        return command::exec_direct_with_env(&direct, &env, &unset, profile.quiet_child);
     }
 
-    let cmd = command::build(&profile, &args.command);
+    let cmd = command::build(&profile, &child);
     output::exec_line(&cmd);
+    confirm_launch(args)?;
+    hooks::run(&profile, hooks::Stage::Pre)?;
+    record_launch(&profile, &child);
+    apply_rlimits(&profile);
+    let session = sessions::log_start(&profile, &child);
 
+    if args.detach {
+        let pid = command::detach(cmd.to_command(), args.log.as_deref())?;
+        return report_detached(pid, &profile);
+    }
+    if stays_resident(&profile, diag.as_ref()) {
+        return spawn_and_report(
+            cmd.to_command(),
+            &profile,
+            &child,
+            diag.as_ref(),
+            session.as_ref(),
+        );
+    }
     command::exec(cmd)
 }
 
-fn list_profiles(cli: &Cli) -> Result<()> {
+/// Whether wayscope has to outlive the session (to notify, write the
+/// diagnostic bundle or run `postHook`) and so spawns instead of exec'ing.
+fn stays_resident(profile: &ResolvedProfile, diag: Option<&diag::Bundle>) -> bool {
+    profile.notify_on_exit || profile.post_hook.is_some() || diag.is_some()
+}
+
+/// Loads the config and resolves the profile `run` selects (`-p`,
+/// `WAYSCOPE_PROFILE`, then the default).
+/// Loads the config and resolves the profile `run` selected. A monitor
+/// picked interactively is kept in `picked` and reused when it's set, so
+/// `--watch` asks only once.
+fn resolve_selected(
+    cli: &Cli,
+    args: &cli::RunArgs,
+    picked: &mut Option<String>,
+) -> Result<(ResolvedProfile, config::ProfileSource, Option<PathBuf>)> {
+    let config = load_config(cli)?;
+    let (profile_name, source) = config::select_profile(
+        args.profile.as_deref(),
+        std::env::var(config::PROFILE_ENV).ok(),
+        config.profiles.default_profile.as_deref(),
+    );
+    // Scripts keep the hard error; only a terminal gets the picker
+    let monitor = if args.pick_monitor
+        || (config.lacks_monitor(&profile_name) && std::io::stdin().is_terminal())
+    {
+        if picked.is_none() {
+            *picked = Some(pick_monitor(&config, args.pick_monitor)?);
+        }
+        picked.clone()
+    } else {
+        None
+    };
+    let profile = resolve(cli, &config, &profile_name, monitor.as_deref()).with_context(|| {
+        format!(
+            "Failed to resolve profile '{}' (selected via {})",
+            profile_name, source
+        )
+    })?;
+    let env_file = config.env_file(&profile.name);
+    Ok((profile, source, env_file))
+}
+
+/// Asks which configured monitor to run on, for `--pick-monitor` or when
+/// the profile has none to fall back to.
+fn pick_monitor(config: &Config, asked: bool) -> Result<String> {
+    let mut names: Vec<_> = config.monitors.monitors.keys().cloned().collect();
+    if names.is_empty() {
+        anyhow::bail!("No monitors configured; add one to monitors.yaml");
+    }
+    names.sort();
+    let choices: Vec<_> = names
+        .iter()
+        .map(|name| {
+            let m = &config.monitors.monitors[name];
+            format!("{} ({}x{}@{}Hz)", name, m.width, m.height, m.refreshRate)
+        })
+        .collect();
+    let question = if asked {
+        "Monitor for this run:"
+    } else {
+        "No monitor set for this profile and none is primary; monitor for this run:"
+    };
+    let index = output::choose(question, &choices)?;
+    if !asked {
+        output::info("Set 'primary: true' on a monitor in monitors.yaml to skip this");
+    }
+    Ok(names.swap_remove(index))
+}
+
+/// Applies `run`'s per-launch flags to the profile, returning notes for the
+/// ones that force a setting.
+fn apply_run_overrides(args: &cli::RunArgs, profile: &mut ResolvedProfile) -> Vec<&'static str> {
+    if args.windowed {
+        config::WindowMode::Windowed.apply(&mut profile.options);
+    }
+    profile.quiet_child |= args.quiet_child;
+    profile.notify_on_exit |= args.notify;
+    profile.log_sessions |= args.session_log;
+    profile.systemd_scope |= args.systemd_scope;
+    for group in &args.no_base_env {
+        profile.base_env.remove(group);
+    }
+    if let Some(skip) = args.skip_gamescope_override() {
+        profile.skip_gamescope = skip;
+    }
+    // HDR env vars and the WSI workaround are derived from these at launch
+    let mut forced = Vec::new();
+    if let Some(hdr) = args.hdr_override() {
+        profile.use_hdr = hdr;
+        forced.push(if hdr {
+            "HDR forced on (--hdr)"
+        } else {
+            "HDR forced off (--no-hdr)"
+        });
+    }
+    if let Some(wsi) = args.wsi_override() {
+        profile.use_wsi = wsi;
+        forced.push(if wsi {
+            "WSI forced on (--wsi)"
+        } else {
+            "WSI forced off (--no-wsi)"
+        });
+    }
+    forced
+}
+
+/// Turns off `systemdScope` when `systemd-run` isn't installed, returning
+/// whether it did: launching without the scope beats not launching at all.
+fn drop_missing_scope(profile: &mut ResolvedProfile) -> bool {
+    let missing = profile.systemd_scope && version::find_binary(command::SYSTEMD_RUN).is_none();
+    if missing {
+        profile.systemd_scope = false;
+    }
+    missing
+}
+
+fn warn_missing_scope() {
+    output::warn(&format!(
+        "{} not found; launching without a systemd scope",
+        command::SYSTEMD_RUN
+    ));
+}
+
+/// `run --watch`: launches in spawn mode and relaunches whenever a config
+/// change alters the resolved launch.
+fn watch_run(cli: &Cli, args: &cli::RunArgs) -> Result<()> {
+    let child = args.child_command();
+    let mut picked = None;
+    let resolve = || -> Result<watch::Launch> {
+        let (mut profile, _, env_file) = resolve_selected(cli, args, &mut picked)?;
+        check_strict(cli, &profile)?;
+        for note in apply_run_overrides(args, &mut profile) {
+            output::info(note);
+        }
+        let scope_missing = drop_missing_scope(&mut profile);
+        output::profile(&profile.name, &profile.monitor_name);
+        for warning in profile.warnings() {
+            output::warn(&warning);
+        }
+        if scope_missing {
+            warn_missing_scope();
+        }
+        if profile.post_hook.is_some() {
+            output::warn("postHook is ignored with --watch (sessions are restarted, not ended)");
+        }
+
+        let env = profile.environment();
+        let unset = profile.unset_names();
+        let (line, command) = if profile.skip_gamescope {
+            let direct = command::direct_argv(&profile, &child);
+            (
+                command::runnable_line(&env, &unset, direct.iter().map(String::as_str)),
+                command::direct_command(&direct, &env, &unset, profile.quiet_child)?,
+            )
+        } else {
+            let cmd = command::build(&profile, &child);
+            output::exec_line(&cmd);
+            (cmd.runnable_line(), cmd.to_command())
+        };
+        let session = sessions::record(&profile, &child);
+        let child = child.clone();
+        Ok(watch::Launch {
+            line,
+            files: config_files(cli, env_file.as_deref()),
+            command,
+            session,
+            prepare: Box::new(move || {
+                hooks::run(&profile, hooks::Stage::Pre)?;
+                record_launch(&profile, &child);
+                apply_rlimits(&profile);
+                command::set_process_name(&profile.name);
+                Ok(())
+            }),
+        })
+    };
+
+    output::info("Watching the config for changes (Ctrl-C to stop)");
+    watch::run(resolve)
+}
+
+fn report_detached(pid: u32, profile: &ResolvedProfile) -> Result<()> {
+    if profile.notify_on_exit {
+        output::warn("notifyOnExit is ignored with --detach (nothing waits for the session)");
+    }
+    if profile.post_hook.is_some() {
+        output::warn("postHook is ignored with --detach (nothing waits for the session)");
+    }
+    output::success(&format!("Detached, PID {}", pid));
+    Ok(())
+}
+
+/// Spawns the launch and waits, then notifies and/or writes the diagnostic
+/// bundle on failure before exiting with the child's status.
+fn spawn_and_report(
+    command: std::process::Command,
+    profile: &ResolvedProfile,
+    child: &[String],
+    diag: Option<&diag::Bundle>,
+    session: Option<&sessions::Record>,
+) -> Result<()> {
+    let write_bundle = |failure: &str| {
+        if let Some(bundle) = diag {
+            match bundle.write(failure) {
+                Ok(path) => output::warn(&format!("Wrote diagnostic bundle to {}", path.display())),
+                Err(e) => output::warn(&format!("Failed to write diagnostic bundle: {:#}", e)),
+            }
+        }
+    };
+
+    command::set_process_name(&profile.name);
+    let status = match command::spawn(command) {
+        Ok(status) => status,
+        Err(e) => {
+            write_bundle(&format!("{:#}", e));
+            return Err(e);
+        }
+    };
+    sessions::log_end(session, status);
+    if profile.notify_on_exit {
+        command::notify_exit(&profile.name, child, status);
+    }
+    if !status.success() {
+        write_bundle(&command::describe_exit(&profile.name, status));
+    }
+    hooks::run(profile, hooks::Stage::Post)?;
+    std::process::exit(command::exit_code(status));
+}
+
+/// Config files a launch reads, system layer first and `env_file` last;
+/// missing ones included.
+fn config_files(cli: &Cli, env_file: Option<&Path>) -> Vec<PathBuf> {
+    let mut files = vec![MonitorsConfig::system_path(), ProfilesConfig::system_path()];
+    for (path, default) in [
+        (
+            cli.monitors.as_ref(),
+            MonitorsConfig::default_path as fn() -> PathBuf,
+        ),
+        (cli.config.as_ref(), ProfilesConfig::default_path),
+    ] {
+        files.extend(config_layers(cli, path, default).unwrap_or_default());
+    }
+    files.extend(env_file.map(Path::to_path_buf));
+    files
+}
+
+/// Reads a `show --dump-resolved` file for `run --from-resolved`.
+fn load_resolved(path: &Path) -> Result<ResolvedProfile> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read: {}", path.display()))?;
+    ResolvedProfile::from_json(&json)
+        .with_context(|| format!("Failed to load resolved profile from {}", path.display()))
+}
+
+/// Sets the profile's rlimits on wayscope so the launch inherits them;
+/// limits that can't be applied only warn.
+fn apply_rlimits(profile: &ResolvedProfile) {
+    for warning in command::apply_rlimits(&profile.rlimits) {
+        output::warn(&warning);
+    }
+}
+
+/// Saves the launch for `run --last`; failures only warn.
+fn record_launch(profile: &ResolvedProfile, child: &[String]) {
+    let last = state::LastLaunch {
+        profile: profile.clone(),
+        command: child.to_vec(),
+    };
+    if let Err(e) = state::last_path().and_then(|path| last.save(&path)) {
+        output::warn(&format!("Failed to record launch for --last: {:#}", e));
+    }
+}
+
+/// `run --dry-run`: shows what would run and checks that the executables
+/// it needs exist, without launching.
+fn dry_run(profile: &ResolvedProfile, child: &[String]) -> Result<()> {
+    let mut binaries = Vec::new();
+    if let Some(wrapper) = profile.wrapper.first() {
+        binaries.push(("Wrapper", wrapper.as_str()));
+    }
+    if !profile.skip_gamescope {
+        output::exec_line(&command::build(profile, child));
+        binaries.push(("gamescope", profile.binary.as_str()));
+    }
+    binaries.extend(child_executable(child));
+    check_executables(&binaries)
+}
+
+fn child_executable(child: &[String]) -> Option<(&'static str, &str)> {
+    child
+        .first()
+        .map(|program| ("Child command", program.as_str()))
+}
+
+/// Reports where each (label, binary) resolves, failing if any doesn't.
+fn check_executables<'a>(binaries: impl IntoIterator<Item = &'a (&'a str, &'a str)>) -> Result<()> {
+    let mut missing = Vec::new();
+    for &(label, binary) in binaries {
+        match version::find_binary(binary) {
+            Some(path) => output::success(&format!("{}: {} -> {}", label, binary, path.display())),
+            None => {
+                output::warn(&format!(
+                    "{}: '{}' is not an executable in PATH or at that path",
+                    label, binary
+                ));
+                missing.push(binary);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        anyhow::bail!("Dry run failed: {} not found", missing.join(", "));
+    }
+    output::success("Dry run OK, nothing was launched");
+    Ok(())
+}
+
+/// Interactive gate for `run --confirm`; a no-op otherwise.
+fn confirm_launch(args: &cli::RunArgs) -> Result<()> {
+    if args.confirm && !output::confirm("Proceed?")? {
+        anyhow::bail!("Aborted by user");
+    }
+    Ok(())
+}
+
+fn list_profiles(cli: &Cli, skip_broken: bool) -> Result<()> {
     let config = load_config(cli)?;
 
     output::header("Available profiles:");
-    for (name, summary) in config.list_profiles() {
+    for (name, summary) in config.list_profiles(skip_broken) {
         output::profile_summary(&name, &summary);
     }
+
+    let aliases = config.list_aliases();
+    if !aliases.is_empty() {
+        output::header("Aliases:");
+        for (alias, target) in aliases {
+            output::profile_summary(alias, &format!("-> {}", target));
+        }
+    }
     Ok(())
 }
 
-fn show_profile(cli: &Cli, profile_name: &str) -> Result<()> {
+/// `list --flat`: every profile's flat keys, prefixed with its name.
+fn list_flat(cli: &Cli, skip_broken: bool) -> Result<()> {
     let config = load_config(cli)?;
-    let profile = config
-        .resolve_profile(profile_name)
+    for line in config.flat_profiles(skip_broken) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// `show --flat`: one `key=value` line per resolved value.
+fn show_flat(cli: &Cli, profile_name: &str) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
         .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
+    for (key, value) in profile.flatten() {
+        println!("{}={}", key, value);
+    }
+    Ok(())
+}
+
+fn dump_resolved(cli: &Cli, profile_name: &str) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
+        .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
+    println!("{}", profile.to_json()?);
+    Ok(())
+}
+
+/// `show --command`: the bare launch line, optionally env-prefixed.
+fn print_command(cli: &Cli, profile_name: &str, with_env: bool) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
+        .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
+    let cmd = command::build(&profile, &[]);
+    if with_env {
+        println!("{}", cmd.runnable_line());
+    } else {
+        println!("{}", cmd.display());
+    }
+    Ok(())
+}
+
+fn show_profile(
+    cli: &Cli,
+    profile_name: &str,
+    explain_workaround: bool,
+    env_inline: bool,
+    print_unset: bool,
+    group: bool,
+    overrides_only: bool,
+) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
+        .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
 
     output::header(&format!("Profile: {}", profile.name));
+    for warning in profile.warnings() {
+        output::warn(&warning);
+    }
     output::section("Settings:");
     output::key_value("  Monitor", &profile.monitor_name);
-    output::key_value("  Binary", &profile.binary);
+    output::key_value(
+        "  Binary",
+        &format!("{} (from {})", profile.binary, profile.binary_source),
+    );
     output::key_value("  HDR", &profile.use_hdr.to_string());
     output::key_value("  WSI", &profile.use_wsi.to_string());
+    let window_mode = match config::WindowMode::of(&profile.options) {
+        Some(mode) => mode.to_string(),
+        None => "fullscreen and borderless (conflicting)".to_string(),
+    };
+    output::key_value("  Window mode", &window_mode);
+    if let Some(rotation) = profile.rotation {
+        output::key_value("  Rotation", &format!("{} degrees", rotation));
+    }
+    if profile.disable_color_management {
+        output::key_value("  Color management", "disabled");
+    }
+    if let Some(path) = &profile.icc_profile {
+        output::key_value("  ICC profile", &format!("{} (not applied)", path));
+    }
+    output::key_value("  Quiet child", &profile.quiet_child.to_string());
+    output::key_value("  Notify on exit", &profile.notify_on_exit.to_string());
+    if profile.log_sessions {
+        output::key_value("  Log sessions", "true");
+    }
+    output::key_value("  Skip gamescope", &profile.skip_gamescope.to_string());
+    if !profile.wrapper.is_empty() {
+        let target = match profile.wrapper_target {
+            config::WrapperTarget::Gamescope => "wraps gamescope",
+            config::WrapperTarget::Child => "wraps child",
+        };
+        output::key_value(
+            "  Wrapper",
+            &format!("{} ({})", profile.wrapper.join(" "), target),
+        );
+    }
+    if profile.env_priority == config::EnvPriority::Base {
+        output::key_value("  Env priority", "base (wayscope's defaults win)");
+    }
+    if profile.base_env.len() < config::BaseEnvGroup::ALL.len() {
+        let groups: Vec<_> = profile.base_env.iter().map(|g| g.to_string()).collect();
+        let value = if groups.is_empty() {
+            "none".to_string()
+        } else {
+            groups.join(", ")
+        };
+        output::key_value("  Base env", &value);
+    }
+    for (label, hook, timeout) in [
+        ("  Pre hook", &profile.pre_hook, profile.pre_timeout),
+        ("  Post hook", &profile.post_hook, profile.post_timeout),
+    ] {
+        if let Some(hook) = hook {
+            let value = match timeout {
+                Some(secs) => format!(
+                    "{} (killed after {}s, {})",
+                    hook, secs, profile.hook_failure
+                ),
+                None => format!("{} ({} on failure)", hook, profile.hook_failure),
+            };
+            output::key_value(label, &value);
+        }
+    }
+    if profile.systemd_scope {
+        let properties: Vec<_> = profile
+            .scope_properties
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        let value = if properties.is_empty() {
+            "yes".to_string()
+        } else {
+            format!("yes ({})", properties.join(", "))
+        };
+        output::key_value("  Systemd scope", &value);
+    }
+    for (name, value) in &profile.rlimits {
+        output::key_value(&format!("  Limit {}", name), &value.to_string());
+    }
+    if let Some(filter) = profile.options.get(config::FILTER_OPTION) {
+        let value = match profile.options.get("sharpness") {
+            Some(sharpness) => format!("{} (sharpness {})", filter, sharpness),
+            None => filter.to_string(),
+        };
+        output::key_value("  Upscale filter", &value);
+    }
+    if let Some(mode) = profile.options.get(config::DRM_MODE_OPTION) {
+        output::key_value("  DRM mode", &mode.to_string());
+    }
+    if let Some(count) = profile.options.get("xwayland-count") {
+        output::key_value("  Xwayland servers", &count.to_string());
+    }
+    if let Some(delay) = profile.options.get("hide-cursor-delay") {
+        output::key_value("  Hide cursor delay", &format!("{} ms", delay));
+    }
+    if let Some(height) = profile.options.get("cursor-scale-height") {
+        output::key_value("  Cursor scale height", &height.to_string());
+    }
+    if let Some(nits) = profile.options.get(config::SDR_NITS_OPTION) {
+        output::key_value("  SDR nits", &nits.to_string());
+    }
+    if let Some(nits) = profile.options.get(config::HDR_MAX_NITS_OPTION) {
+        output::key_value("  HDR max nits", &nits.to_string());
+    }
+    if let Some(title) = profile.options.get(config::WINDOW_TITLE_OPTION) {
+        output::key_value("  Window title", &title.to_string());
+    }
+    if let Some(locale) = &profile.locale {
+        output::key_value("  Locale", locale);
+    }
+    if let Some(tz) = &profile.timezone {
+        output::key_value("  Timezone", tz);
+    }
+    if let Some(level) = &profile.gamescope_log {
+        output::key_value("  Gamescope log", level);
+    }
+    if let Some(version) = &profile.proton_version {
+        output::key_value("  Proton version", version);
+    }
+    if let Some(path) = &profile.compat_data_path {
+        output::key_value("  Compat data path", path);
+    }
+    if let Some(path) = &profile.compat_client_install_path {
+        output::key_value("  Compat client install path", path);
+    }
 
-    output::section("Options:");
-    let mut opts: Vec<_> = profile.options.iter().collect();
-    opts.sort_by(|a, b| a.0.cmp(b.0));
+    let options = if overrides_only {
+        output::section("Options (differing from monitor defaults):");
+        config.overridden_options(&profile)?
+    } else {
+        output::section("Options:");
+        profile.options.clone()
+    };
+    let mut opts: Vec<_> = options.iter().collect();
+    if group {
+        opts.sort_by_key(|(key, _)| (command::option_group(key), *key));
+    } else {
+        opts.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    let mut current_group = None;
     for (key, value) in opts {
+        let key_group = command::option_group(key);
+        if group && current_group != Some(key_group) {
+            output::subsection(&format!("{}:", key_group.label()));
+            current_group = Some(key_group);
+        }
         output::key_value(&format!("  --{}", key), &value.to_string());
     }
 
     output::section("Environment:");
-    for (key, value) in profile.environment() {
-        output::key_value(&format!("  {}", key), &value);
+    if env_inline {
+        println!("  {}", command::inline_env(&profile.environment()));
+    } else {
+        for (key, value) in profile.environment() {
+            output::key_value(&format!("  {}", key), &value);
+        }
     }
 
-    if !profile.unset_vars.is_empty() {
+    if print_unset && !profile.unset_vars.is_empty() {
+        print_inherited_unsets(&profile);
+    } else if !profile.unset_vars.is_empty() {
         output::section("Unset Variables:");
         let mut unset = profile.unset_vars.clone();
         unset.sort();
@@ -99,16 +876,219 @@ fn show_profile(cli: &Cli, profile_name: &str) -> Result<()> {
         }
     }
 
+    if !profile.game_env.is_empty() || !profile.game_unset.is_empty() {
+        output::section("Game Environment (child only):");
+        for (key, value) in &profile.game_env {
+            output::key_value(&format!("  {}", key), value);
+        }
+        for var in &profile.game_unset {
+            output::key_value("  -", var);
+        }
+    }
+
+    if explain_workaround {
+        output::section("HDR Workaround:");
+        for (condition, met) in profile.hdr_workaround_conditions() {
+            let status = if met { "met" } else { "not met" };
+            output::key_value(&format!("  {}", condition), status);
+        }
+        let verdict = if profile.needs_hdr_workaround() {
+            "DISABLE_HDR_WSI=1 will be injected for the child (all conditions met)"
+        } else {
+            "not applied (requires all three conditions)"
+        };
+        output::key_value("  Result", verdict);
+    }
+
+    Ok(())
+}
+
+/// Walks through the environment merge, then prints the result.
+fn explain_env_order(cli: &Cli, profile_name: &str) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
+        .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
+
+    output::header(&format!("Environment merge order: {}", profile.name));
+    for (i, stage) in profile.environment_stages().into_iter().enumerate() {
+        output::section(&format!("{}. {}:", i + 1, stage.label));
+        if stage.changes.is_empty() {
+            println!("  (no changes)");
+        }
+        for change in stage.changes {
+            match change {
+                EnvChange::Added(name, value) => {
+                    output::key_value(&format!("  + {}", name), &value)
+                }
+                EnvChange::Overridden(name, old, new) => {
+                    output::diff(&format!("~ {}", name), &old, &new)
+                }
+                EnvChange::Removed(name, old) => {
+                    output::key_value(&format!("  - {}", name), &format!("(was {})", old))
+                }
+            }
+        }
+    }
+
+    output::section("Result:");
+    for (key, value) in profile.environment() {
+        output::key_value(&format!("  {}", key), &value);
+    }
     Ok(())
 }
 
-fn list_monitors(cli: &Cli) -> Result<()> {
-    let path = cli
-        .monitors
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(MonitorsConfig::default_path);
-    let monitors = MonitorsConfig::load(&path)?;
+/// Lists the inherited variables each `unset` entry would strip right now.
+fn print_inherited_unsets(profile: &ResolvedProfile) {
+    output::section("Unset Variables (against current environment):");
+    let parent: Vec<_> = std::env::vars_os()
+        .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+        .collect();
+
+    for (entry, removed) in profile.inherited_unsets(&parent) {
+        if removed.is_empty() {
+            output::key_value(&format!("  - {}", entry), "(not set, nothing removed)");
+        }
+        for (name, value) in removed {
+            let value = if name == entry {
+                value
+            } else {
+                format!("{}  (via {})", value, entry)
+            };
+            output::key_value(&format!("  - {}", name), &value);
+        }
+    }
+}
+
+fn get_value(cli: &Cli, profile_name: &str, key: &str) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)
+        .with_context(|| format!("Failed to resolve profile '{}'", profile_name))?;
+    check_strict(cli, &profile)?;
+
+    let value = profile.get(key).with_context(|| {
+        format!(
+            "Key '{}' not found in profile '{}' (use options.<flag>, env.<VAR>, \
+             or setting.monitor|hdr|wsi|binary)",
+            key, profile.name
+        )
+    })?;
+    println!("{}", value);
+    Ok(())
+}
+
+/// `sessions`: the last `count` logged sessions, oldest first.
+fn list_sessions(count: usize) -> Result<()> {
+    let path = sessions::log_path()?;
+    let all = sessions::sessions(sessions::load(&path)?);
+    if all.is_empty() {
+        output::info(&format!(
+            "No sessions logged in {} (enable with 'logSessions: true' or 'run --session-log')",
+            path.display()
+        ));
+        return Ok(());
+    }
+    output::header(&format!("Sessions ({}):", path.display()));
+    for session in &all[all.len().saturating_sub(count)..] {
+        println!(
+            "  {}",
+            sessions::format_session(session, &util::local_time(session.start.start))
+        );
+    }
+    Ok(())
+}
+
+fn prune_profiles(cli: &Cli, yes: bool) -> Result<()> {
+    let profiles_path = config_path(cli, cli.config.as_ref(), ProfilesConfig::default_path)?;
+    prune::run(
+        &monitor_layers(cli)?,
+        &profile_layers(cli)?,
+        &profiles_path,
+        yes,
+    )
+}
+
+fn reset_config(cli: &Cli, yes: bool) -> Result<()> {
+    let monitors_path = config_path(cli, cli.monitors.as_ref(), MonitorsConfig::default_path)?;
+    let profiles_path = config_path(cli, cli.config.as_ref(), ProfilesConfig::default_path)?;
+    init::reset(&monitors_path, &profiles_path, yes)
+}
+
+fn export_script(
+    cli: &Cli,
+    profile_name: &str,
+    output: Option<&Path>,
+    force: bool,
+    child: &[String],
+) -> Result<()> {
+    let config = load_config(cli)?;
+    let profile = resolve(cli, &config, profile_name, None)?;
+    check_strict(cli, &profile)?;
+
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        // Profile names may hold '/' or '..'; the default stays in this directory
+        None => PathBuf::from(format!("{}.sh", util::file_name_part(&profile.name))),
+    };
+    export::write(&path, &export::script(&profile, child), force)?;
+    output::success(&format!(
+        "Wrote profile '{}' to {}",
+        profile.name,
+        path.display()
+    ));
+    if child.is_empty() {
+        output::info("No command given; the script starts gamescope on its own");
+    }
+    Ok(())
+}
+
+fn import_profiles(
+    cli: &Cli,
+    file: &Path,
+    names: &[String],
+    force: bool,
+    rename: bool,
+) -> Result<()> {
+    let conflict = match (force, rename) {
+        (true, _) => import::Conflict::Overwrite,
+        (_, true) => import::Conflict::Rename,
+        _ => import::Conflict::Refuse,
+    };
+    let profiles_path = config_path(cli, cli.config.as_ref(), ProfilesConfig::default_path)?;
+    import::run(
+        file,
+        &monitor_layers(cli)?,
+        &profile_layers(cli)?,
+        &profiles_path,
+        names,
+        conflict,
+    )
+}
+
+fn list_monitors(
+    cli: &Cli,
+    diff: Option<&[String]>,
+    dump_defaults: Option<&str>,
+    changed: bool,
+) -> Result<()> {
+    let layers = monitor_layers(cli)?;
+    let monitors = MonitorsConfig::load_layers(&layers).with_context(|| {
+        format!(
+            "Failed to load monitors from {}{}",
+            display_layers(&layers[1..]),
+            relative_hint(cli)
+        )
+    })?;
+
+    if let Some([a, b]) = diff {
+        return diff_monitors(&monitors, a, b);
+    }
+    if let Some(name) = dump_defaults {
+        return dump_monitor_defaults(&monitors, name);
+    }
+    if changed {
+        return list_changed_monitors(&monitors);
+    }
 
     output::header("Configured monitors:");
 
@@ -118,9 +1098,31 @@ fn list_monitors(cli: &Cli) -> Result<()> {
     for name in names {
         if let Some(mon) = monitors.monitors.get(name) {
             let primary_marker = if mon.primary { " (primary)" } else { "" };
+            let output = mon
+                .gamescope_output()
+                .map(|o| format!(" output={}", o))
+                .unwrap_or_default();
+            let icc = mon
+                .iccProfile
+                .as_ref()
+                .map(|path| format!(" icc={}", path))
+                .unwrap_or_default();
+            let model = mon
+                .model
+                .as_ref()
+                .map(|model| format!(" \"{}\"", model))
+                .unwrap_or_default();
             let summary = format!(
-                "{}x{}@{}Hz VRR={} HDR={}{}",
-                mon.width, mon.height, mon.refreshRate, mon.vrr, mon.hdr, primary_marker
+                "{}x{}@{}Hz VRR={} HDR={}{}{}{}{}",
+                mon.width,
+                mon.height,
+                mon.refreshRate,
+                mon.vrr,
+                mon.hdr,
+                output,
+                icc,
+                model,
+                primary_marker
             );
             output::profile_summary(name, &summary);
         }
@@ -128,23 +1130,347 @@ fn list_monitors(cli: &Cli) -> Result<()> {
     Ok(())
 }
 
+fn list_detected(name: Option<&str>, yaml: bool) -> Result<()> {
+    let backend = detect::backend();
+    let fmt_cap = |c: Option<bool>| c.map_or_else(|| "?".to_string(), |v| v.to_string());
+
+    if let Some(name) = name {
+        let caps = backend.capabilities(name)?;
+        output::header(&format!("Output: {}", name));
+        if let Some(model) = backend
+            .outputs()?
+            .into_iter()
+            .find(|o| o.name == name)
+            .and_then(|o| o.model)
+        {
+            output::key_value("  Model", &model);
+        }
+        output::key_value("  VRR", &fmt_cap(caps.vrr));
+        output::key_value("  HDR", &fmt_cap(caps.hdr));
+        return Ok(());
+    }
+
+    let outputs = backend.outputs()?;
+    let active = backend.active_output()?.map(|o| o.name);
+
+    if yaml {
+        let monitors = detect::monitor_defs(&outputs, active.as_deref());
+        print!(
+            "{}",
+            serde_yaml::to_string(&monitors).context("Failed to serialize monitors")?
+        );
+        return Ok(());
+    }
+
+    output::header(&format!("Detected outputs ({}):", backend.name()));
+    for out in outputs {
+        let active_marker = if active.as_deref() == Some(out.name.as_str()) {
+            " (active)"
+        } else {
+            ""
+        };
+        let model = out
+            .model
+            .as_ref()
+            .map(|model| format!(" \"{}\"", model))
+            .unwrap_or_default();
+        let summary = format!(
+            "{}x{}@{}Hz VRR={} HDR={}{}{}",
+            out.width,
+            out.height,
+            out.refresh_rate,
+            fmt_cap(out.capabilities.vrr),
+            fmt_cap(out.capabilities.hdr),
+            model,
+            active_marker
+        );
+        output::profile_summary(&out.name, &summary);
+    }
+    Ok(())
+}
+
+fn list_unused_monitors(cli: &Cli) -> Result<()> {
+    let config = load_config(cli)?;
+    let unused = config.unused_monitors();
+
+    if unused.is_empty() {
+        output::success("Every monitor is used by a profile or is the primary");
+        return Ok(());
+    }
+    output::header("Unused monitors:");
+    for name in unused {
+        output::profile_summary(name, "not targeted by any profile");
+    }
+    Ok(())
+}
+
+fn dump_monitor_defaults(monitors: &MonitorsConfig, name: &str) -> Result<()> {
+    let options = config::base_options(monitors.get(name)?);
+
+    output::header(&format!("Default options for monitor '{}':", name));
+    let mut opts: Vec<_> = options.iter().collect();
+    opts.sort_by(|a, b| a.0.cmp(b.0));
+    for (key, value) in opts {
+        output::key_value(&format!("  --{}", key), &value.to_string());
+    }
+    Ok(())
+}
+
+/// `monitors --changed`: each monitor's fields set away from a plain 60 Hz
+/// monitor, with the capabilities it turns on.
+fn list_changed_monitors(monitors: &MonitorsConfig) -> Result<()> {
+    output::header("Monitors (differing from a plain 60 Hz monitor):");
+
+    let mut names: Vec<_> = monitors.monitors.keys().collect();
+    names.sort();
+    for name in names {
+        let monitor = monitors.get(name)?;
+        let capabilities = monitor.enabled_capabilities();
+        let summary = if capabilities.is_empty() {
+            "no VRR or HDR enabled".to_string()
+        } else {
+            format!("enables {}", capabilities.join(", "))
+        };
+        output::profile_summary(name, &summary);
+
+        let changes = monitor.changed_from_defaults();
+        if changes.is_empty() {
+            output::info("  No differences");
+        }
+        for (field, default, value) in changes {
+            output::diff(&format!("  {}", field), &default, &value);
+        }
+    }
+    Ok(())
+}
+
+fn diff_monitors(monitors: &MonitorsConfig, a: &str, b: &str) -> Result<()> {
+    let (left, right) = (monitors.get(a)?, monitors.get(b)?);
+
+    output::header(&format!("Monitor diff: {} -> {}", a, b));
+    let changes = left.diff(right);
+    if changes.is_empty() {
+        output::info("  No differences");
+    }
+    for (field, l, r) in changes {
+        output::diff(field, &l, &r);
+    }
+
+    if left.primary && right.primary {
+        output::warn("Both monitors are marked primary; only one should be.");
+    }
+    Ok(())
+}
+
+/// Prints profile and alias names for shell completion.
+///
+/// Never fails: a missing or broken config prints nothing, so completion
+/// doesn't spew errors into the user's shell.
+fn complete_profiles(cli: &Cli) {
+    let Ok(layers) = profile_layers(cli) else {
+        return;
+    };
+    let Ok(profiles) = ProfilesConfig::load_layers(&layers) else {
+        return;
+    };
+    let mut names: Vec<_> = profiles
+        .profiles
+        .keys()
+        .chain(profiles.aliases.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+/// Prints monitor names for shell completion; never fails, like `complete_profiles`.
+fn complete_monitors(cli: &Cli) {
+    let Ok(layers) = monitor_layers(cli) else {
+        return;
+    };
+    let Ok(monitors) = MonitorsConfig::load_layers(&layers) else {
+        return;
+    };
+    let mut names: Vec<_> = monitors.monitors.keys().collect();
+    names.sort();
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+/// Resolves `name` (on `monitor` when given), writing the `--trace` log
+/// when one was asked for.
+fn resolve(
+    cli: &Cli,
+    config: &Config,
+    name: &str,
+    monitor: Option<&str>,
+) -> Result<ResolvedProfile> {
+    let Some(path) = &cli.trace else {
+        return config.resolve_profile_on(name, monitor);
+    };
+    let mut trace = config::Trace::on();
+    let resolved = config.resolve_profile_traced(name, monitor, &mut trace);
+    match &resolved {
+        Ok(profile) => {
+            profile.environment_traced(&mut trace);
+        }
+        Err(e) => trace.record("error", || format!("{:#}", e)),
+    }
+    let log = format!(
+        "# wayscope {} resolution trace for profile '{}'\n{}",
+        env!("CARGO_PKG_VERSION"),
+        name,
+        trace.render()
+    );
+    std::fs::write(path, log)
+        .with_context(|| format!("Failed to write trace to {}", path.display()))?;
+    resolved
+}
+
+/// Under `--strict`, fails if the profile definition produced warnings.
+fn check_strict(cli: &Cli, profile: &ResolvedProfile) -> Result<()> {
+    if cli.strict && !profile.config_warnings.is_empty() {
+        anyhow::bail!(
+            "Profile '{}' has config warnings (--strict):\n  {}",
+            profile.name,
+            profile.config_warnings.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
 fn load_config(cli: &Cli) -> Result<Config> {
-    let monitors_path = cli
-        .monitors
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(MonitorsConfig::default_path);
-    let profiles_path = cli
-        .config
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(ProfilesConfig::default_path);
-
-    Config::load(&monitors_path, &profiles_path).with_context(|| {
+    let monitors = monitor_layers(cli)?;
+    let profiles = profile_layers(cli)?;
+
+    Config::load_layers(&monitors, &profiles).with_context(|| {
         format!(
-            "Failed to load config from {} and {}",
-            monitors_path.display(),
-            profiles_path.display()
+            "Failed to load config from {} and {}{}",
+            display_layers(&monitors[1..]),
+            display_layers(&profiles[1..]),
+            relative_hint(cli)
         )
     })
 }
+
+/// Every monitors file, the system one first.
+fn monitor_layers(cli: &Cli) -> Result<Vec<PathBuf>> {
+    let mut layers = vec![MonitorsConfig::system_path()];
+    layers.extend(config_layers(
+        cli,
+        cli.monitors.as_ref(),
+        MonitorsConfig::default_path,
+    )?);
+    Ok(layers)
+}
+
+/// Every profiles file, the system one first.
+fn profile_layers(cli: &Cli) -> Result<Vec<PathBuf>> {
+    let mut layers = vec![ProfilesConfig::system_path()];
+    layers.extend(config_layers(
+        cli,
+        cli.config.as_ref(),
+        ProfilesConfig::default_path,
+    )?);
+    Ok(layers)
+}
+
+fn display_layers(layers: &[PathBuf]) -> String {
+    let paths: Vec<_> = layers.iter().map(|p| p.display().to_string()).collect();
+    paths.join(", ")
+}
+
+/// The files layered over the system file, lowest precedence first: the
+/// user files, the local `.wayscope/` file with `--local-config`, then an
+/// explicit `--monitors`/`--config`.
+fn config_layers(
+    cli: &Cli,
+    path: Option<&PathBuf>,
+    default: fn() -> PathBuf,
+) -> Result<Vec<PathBuf>> {
+    let explicit = match path {
+        Some(_) => Some(config_path(cli, path, default)?),
+        None => None,
+    };
+    let file_name = default()
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(config::stack_layers(
+        user_layers(cli, default),
+        cli.local_config
+            .then(|| Path::new(config::LOCAL_CONFIG_DIR)),
+        &file_name,
+        explicit,
+    ))
+}
+
+/// The user files, lowest precedence first.
+///
+/// The directories in `WAYSCOPE_CONFIG_PATH` are searched (see
+/// `--config-path-mode`), and the default config dir is used when none of
+/// them has the file.
+fn user_layers(cli: &Cli, default: fn() -> PathBuf) -> Vec<PathBuf> {
+    let default = default();
+    let found = match (std::env::var(config::CONFIG_PATH_ENV), default.file_name()) {
+        (Ok(search), Some(file_name)) => {
+            config::search_config_path(&search, &file_name.to_string_lossy())
+        }
+        _ => Vec::new(),
+    };
+    if found.is_empty() {
+        return vec![default];
+    }
+    match cli.config_path_mode {
+        ConfigPathMode::First => found.into_iter().take(1).collect(),
+        ConfigPathMode::Merge => found.into_iter().rev().collect(),
+    }
+}
+
+/// Resolves a `--monitors`/`--config` override, falling back to the
+/// highest-precedence user file (the one commands that edit the config
+/// write to; the local layer is never edited).
+///
+/// Relative paths resolve against the current directory, or against the
+/// config directory with `--config-relative-to-config-dir`. An explicit path
+/// must exist, so a typo can't silently fall back to the system-wide layer.
+fn config_path(cli: &Cli, path: Option<&PathBuf>, default: fn() -> PathBuf) -> Result<PathBuf> {
+    let Some(path) = path else {
+        return Ok(user_layers(cli, default).pop().unwrap_or_else(default));
+    };
+    let base = if cli.config_relative_to_config_dir {
+        MonitorsConfig::config_dir()
+    } else {
+        std::env::current_dir().context("Failed to read current directory")?
+    };
+    let resolved = config::resolve_config_path(path, &base);
+    if !resolved.exists() {
+        anyhow::bail!(
+            "Config file not found: {}{}",
+            resolved.display(),
+            relative_hint(cli)
+        );
+    }
+    Ok(resolved)
+}
+
+/// Explains how relative paths were resolved when the user passed one.
+fn relative_hint(cli: &Cli) -> String {
+    let any_relative = [&cli.monitors, &cli.config]
+        .into_iter()
+        .flatten()
+        .any(|p| p.is_relative());
+    match (any_relative, cli.config_relative_to_config_dir) {
+        (false, _) => String::new(),
+        (true, false) => "\n  (relative paths resolve against the current directory; \
+             use --config-relative-to-config-dir to resolve against the config directory)"
+            .to_string(),
+        (true, true) => format!(
+            "\n  (relative paths resolve against {})",
+            MonitorsConfig::config_dir().display()
+        ),
+    }
+}
@@ -1,36 +1,74 @@
 //! Colored terminal output helpers.
+//!
+//! Color follows `--color`: `auto` colors only on a TTY and honors
+//! `NO_COLOR`, while `always`/`never` force it either way.
 
-use owo_colors::OwoColorize;
+use std::fmt::Display;
+use std::io::{BufRead, IsTerminal, Write};
 
+use anyhow::{bail, Context, Result};
+use owo_colors::{OwoColorize, Stream, Style};
+
+use crate::cli::ColorWhen;
 use crate::command::GamescopeCommand;
 
 const PREFIX: &str = "[wayscope]";
 
+/// Applies the `--color` choice to all output.
+pub fn set_color(when: ColorWhen) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    match color_override(when, no_color) {
+        Some(enabled) => owo_colors::set_override(enabled),
+        None => owo_colors::unset_override(),
+    }
+}
+
+/// Forced color state, or `None` to detect from the terminal.
+fn color_override(when: ColorWhen, no_color: bool) -> Option<bool> {
+    match when {
+        ColorWhen::Always => Some(true),
+        ColorWhen::Never => Some(false),
+        ColorWhen::Auto if no_color => Some(false),
+        ColorWhen::Auto => None,
+    }
+}
+
+/// Styles `text` when color is enabled for stdout.
+fn paint<T: Display>(text: T, style: Style) -> String {
+    text.if_supports_color(Stream::Stdout, move |t| t.style(style))
+        .to_string()
+}
+
 pub fn profile(name: &str, monitor: &str) {
     println!(
         "{} Profile: {} (monitor: {})",
-        PREFIX.cyan().bold(),
-        name.green().bold(),
-        monitor.blue()
+        paint(PREFIX, Style::new().cyan().bold()),
+        paint(name, Style::new().green().bold()),
+        paint(monitor, Style::new().blue())
     );
 }
 
 pub fn header(text: &str) {
-    println!("{}", text.bold());
+    println!("{}", paint(text, Style::new().bold()));
 }
 
 pub fn section(text: &str) {
-    println!("{}", text.cyan());
+    println!("{}", paint(text, Style::new().cyan()));
+}
+
+/// A heading nested inside a section, indented to match its entries.
+pub fn subsection(text: &str) {
+    println!("  {}", paint(text, Style::new().dimmed().bold()));
 }
 
 pub fn key_value(key: &str, value: &str) {
-    println!("{}={}", key.yellow(), value);
+    println!("{}={}", paint(key, Style::new().yellow()), value);
 }
 
 pub fn environment(env: &[(String, String)]) {
-    println!("{} Environment:", PREFIX.cyan().bold());
+    println!("{} Environment:", paint(PREFIX, Style::new().cyan().bold()));
     for (key, value) in env {
-        println!("    {}={}", key.yellow(), value);
+        println!("    {}={}", paint(key, Style::new().yellow()), value);
     }
 }
 
@@ -38,25 +76,133 @@ pub fn exec_line(cmd: &GamescopeCommand) {
     if cmd.needs_workaround {
         println!(
             "{} HDR workaround: {} for child",
-            PREFIX.magenta().bold(),
-            "DISABLE_HDR_WSI=1".yellow()
+            paint(PREFIX, Style::new().magenta().bold()),
+            paint("DISABLE_HDR_WSI=1", Style::new().yellow())
         );
     }
-    println!("{} Exec: {}", PREFIX.cyan().bold(), cmd.display().dimmed());
+    println!(
+        "{} Exec: {}",
+        paint(PREFIX, Style::new().cyan().bold()),
+        paint(cmd.display(), Style::new().dimmed())
+    );
+}
+
+/// One changed field: `  key: left -> right`.
+pub fn diff(key: &str, left: &str, right: &str) {
+    println!(
+        "  {}: {} -> {}",
+        paint(key, Style::new().yellow()),
+        paint(left, Style::new().red()),
+        paint(right, Style::new().green())
+    );
 }
 
 pub fn profile_summary(name: &str, summary: &str) {
-    println!("  {}: {}", name.green(), summary.dimmed());
+    println!(
+        "  {}: {}",
+        paint(name, Style::new().green()),
+        paint(summary, Style::new().dimmed())
+    );
 }
 
 pub fn warn(msg: &str) {
-    println!("{} {}", PREFIX.yellow().bold(), msg);
+    println!("{} {}", paint(PREFIX, Style::new().yellow().bold()), msg);
 }
 
 pub fn success(msg: &str) {
-    println!("{} {}", PREFIX.green().bold(), msg);
+    println!("{} {}", paint(PREFIX, Style::new().green().bold()), msg);
 }
 
 pub fn info(msg: &str) {
-    println!("{}", msg.dimmed());
+    println!("{}", paint(msg, Style::new().dimmed()));
+}
+
+/// Asks a yes/no question on the terminal. Errors instead of hanging without a TTY.
+pub fn confirm(question: &str) -> Result<bool> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        bail!("Cannot prompt without an interactive terminal");
+    }
+
+    print!(
+        "{} {} [y/N] ",
+        paint(PREFIX, Style::new().cyan().bold()),
+        question
+    );
+    std::io::stdout()
+        .flush()
+        .context("Failed to write prompt")?;
+
+    let mut answer = String::new();
+    stdin
+        .lock()
+        .read_line(&mut answer)
+        .context("Failed to read answer")?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// Asks the user to pick one of `choices` by number, returning its index.
+/// Asks again on an invalid answer; an empty one (or EOF) cancels.
+pub fn choose(question: &str, choices: &[String]) -> Result<usize> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        bail!("Cannot prompt without an interactive terminal");
+    }
+
+    println!("{} {}", paint(PREFIX, Style::new().cyan().bold()), question);
+    for (i, choice) in choices.iter().enumerate() {
+        println!("  {}) {}", i + 1, choice);
+    }
+    loop {
+        print!("Choice [1-{}]: ", choices.len());
+        std::io::stdout()
+            .flush()
+            .context("Failed to write prompt")?;
+
+        let mut answer = String::new();
+        stdin
+            .lock()
+            .read_line(&mut answer)
+            .context("Failed to read answer")?;
+        if answer.trim().is_empty() {
+            bail!("Nothing chosen");
+        }
+        match parse_choice(&answer, choices.len()) {
+            Some(index) => return Ok(index),
+            None => warn(&format!("Enter a number from 1 to {}", choices.len())),
+        }
+    }
+}
+
+/// The index for a 1-based `answer`, if it's in range.
+fn parse_choice(answer: &str, count: usize) -> Option<usize> {
+    let n: usize = answer.trim().parse().ok()?;
+    (1..=count).contains(&n).then(|| n - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_override() {
+        assert_eq!(color_override(ColorWhen::Always, false), Some(true));
+        // NO_COLOR doesn't override an explicit 'always'
+        assert_eq!(color_override(ColorWhen::Always, true), Some(true));
+        assert_eq!(color_override(ColorWhen::Never, false), Some(false));
+        assert_eq!(color_override(ColorWhen::Auto, true), Some(false));
+        assert_eq!(color_override(ColorWhen::Auto, false), None);
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("1\n", 3), Some(0));
+        assert_eq!(parse_choice(" 3 ", 3), Some(2));
+        assert_eq!(parse_choice("0", 3), None);
+        assert_eq!(parse_choice("4", 3), None);
+        assert_eq!(parse_choice("tv", 3), None);
+    }
 }
@@ -7,56 +7,469 @@
 //! 4. Profile-specific environment (override/extend base)
 //! 5. Conditional HDR/WSI environment variables
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use crate::config::OptionValue;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 
-// Base environment variable definitions as static tuples to avoid runtime allocations
-const BASE_ENV: &[(&str, &str)] = &[
+use crate::config::{
+    is_valid_env_var_name, normalize_backend, vrr_with_immediate_flips, BaseEnvGroup, EnvPriority,
+    HookFailure, OptionValue, RlimitValue, Trace, WrapperTarget, GAMESCOPE_LOG_ENV,
+    HDR_MAX_NITS_OPTION, SDR_NITS_OPTION,
+};
+
+// Base environment variable definitions as static tuples to avoid runtime allocations,
+// grouped so a profile can leave a group out with `disableBaseEnv`
+const AMD_ENV: &[(&str, &str)] = &[
     ("AMD_VULKAN_ICD", "RADV"),
     ("DISABLE_LAYER_AMD_SWITCHABLE_GRAPHICS_1", "1"),
     ("DISABLE_LAYER_NV_OPTIMUS_1", "1"),
+    ("RADV_PERFTEST", "aco"),
+];
+const WAYLAND_ENV: &[(&str, &str)] = &[
     ("GAMESCOPE_WAYLAND_DISPLAY", "gamescope-0"),
+    ("SDL_VIDEODRIVER", "wayland"),
+];
+const PROTON_ENV: &[(&str, &str)] = &[
     ("PROTON_ADD_CONFIG", "sdlinput,wayland,hdr"),
     ("PROTON_ENABLE_WAYLAND", "1"),
-    ("RADV_PERFTEST", "aco"),
-    ("SDL_VIDEODRIVER", "wayland"),
 ];
 
+/// The base variables a group sets.
+fn base_env_group(group: BaseEnvGroup) -> &'static [(&'static str, &'static str)] {
+    match group {
+        BaseEnvGroup::Amd => AMD_ENV,
+        BaseEnvGroup::Wayland => WAYLAND_ENV,
+        BaseEnvGroup::Proton => PROTON_ENV,
+    }
+}
+
+/// Marker wayscope sets for its launches: `gamescope` inside a session it
+/// started, `direct` when gamescope was skipped. Lets a nested wayscope tell
+/// its own sessions from an unrelated gamescope (the base environment sets
+/// `GAMESCOPE_WAYLAND_DISPLAY` either way).
+pub const SESSION_ENV: &str = "WAYSCOPE_SESSION";
+pub const SESSION_GAMESCOPE: &str = "gamescope";
+pub const SESSION_DIRECT: &str = "direct";
+
+/// Steam compat variables set by the `protonVersion`, `compatDataPath`
+/// and `compatClientInstallPath` fields.
+pub const PROTON_VERSION_ENV: &str = "PROTON_VERSION";
+pub const COMPAT_DATA_PATH_ENV: &str = "STEAM_COMPAT_DATA_PATH";
+pub const COMPAT_CLIENT_INSTALL_PATH_ENV: &str = "STEAM_COMPAT_CLIENT_INSTALL_PATH";
+
+/// Leading marker on a profile env value meaning "append to the base value".
+/// Doubled, it stands for a literal leading `+`.
+const APPEND_PREFIX: char = '+';
+
+/// Where the resolved gamescope binary came from.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BinarySource {
+    Profile,
+    Environment,
+    Default,
+}
+
+impl std::fmt::Display for BinarySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Profile => write!(f, "profile"),
+            Self::Environment => write!(f, "{}", crate::config::GAMESCOPE_BIN_ENV),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
 /// A fully resolved profile ready for execution.
 ///
 /// Combines profile settings with monitor configuration into a complete
 /// set of options and environment variables for gamescope.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct ResolvedProfile {
     pub name: String,
     pub monitor_name: String,
     pub binary: String,
+    pub binary_source: BinarySource,
     pub use_hdr: bool,
     pub use_wsi: bool,
+    /// Silences the HDR-without-WSI warning (intentional for tone-mapped setups).
+    pub allow_hdr_without_wsi: bool,
+    /// Whether `immediate-flips` stays under VRR; `None` when the profile
+    /// turns both on itself without choosing, which warns.
+    pub immediate_flips_with_vrr: Option<bool>,
+    /// Display rotation in degrees clockwise, if set.
+    pub rotation: Option<u16>,
+    /// Whether gamescope color management is disabled.
+    pub disable_color_management: bool,
+    /// Silence the child's stdout/stderr.
+    pub quiet_child: bool,
+    /// Notify when the session exits (launches via spawn instead of exec).
+    pub notify_on_exit: bool,
+    /// Append start/end records to the session log.
+    pub log_sessions: bool,
+    /// Bypass gamescope and run the child directly with this environment.
+    pub skip_gamescope: bool,
+    /// Exported as `LANG` and `LC_ALL`.
+    pub locale: Option<String>,
+    /// Exported as `TZ`.
+    pub timezone: Option<String>,
+    /// gamescope's internal log level, exported as `GAMESCOPE_LOG`.
+    pub gamescope_log: Option<String>,
+    /// Shell command run before the launch.
+    pub pre_hook: Option<String>,
+    /// Shell command run after the session exits (launches via spawn).
+    pub post_hook: Option<String>,
+    /// Seconds each hook may run before it's killed.
+    pub pre_timeout: Option<u64>,
+    pub post_timeout: Option<u64>,
+    /// Whether a failed or timed-out hook aborts or only warns.
+    pub hook_failure: HookFailure,
+    /// Supersampling factor; nested above output size is intended when set.
+    pub supersample: Option<f32>,
+    /// Exported as `PROTON_VERSION`.
+    pub proton_version: Option<String>,
+    /// Expanded `compatDataPath`, exported as `STEAM_COMPAT_DATA_PATH`.
+    pub compat_data_path: Option<String>,
+    /// Expanded `compatClientInstallPath`, exported as
+    /// `STEAM_COMPAT_CLIENT_INSTALL_PATH`.
+    pub compat_client_install_path: Option<String>,
+    /// Expanded `iccProfile` of the monitor; shown, but not passed to gamescope.
+    pub icc_profile: Option<String>,
+    /// Resource limits set on wayscope before launch, inherited by the session.
+    pub rlimits: BTreeMap<String, RlimitValue>,
+    /// Command prepended to the launch (e.g. `gamemoderun`).
+    pub wrapper: Vec<String>,
+    /// Whether `wrapper` goes before gamescope or before the child.
+    pub wrapper_target: WrapperTarget,
+    /// Whether `user_env` or the base variables win on a shared name.
+    pub env_priority: EnvPriority,
+    /// Groups of base variables applied; all of them unless `disableBaseEnv`.
+    pub base_env: BTreeSet<BaseEnvGroup>,
+    /// Launch inside a transient `systemd-run --user --scope`.
+    pub systemd_scope: bool,
+    /// Unit properties for the scope, passed as `-p Key=Value`.
+    pub scope_properties: BTreeMap<String, String>,
+    /// Issues found while resolving (e.g. deprecated option names).
+    pub config_warnings: Vec<String>,
     /// Merged gamescope CLI options (monitor defaults + profile overrides).
     pub options: HashMap<String, OptionValue>,
     /// Profile-specific environment variables (merged with base env at runtime).
     pub user_env: HashMap<String, String>,
-    /// Environment variable names to unset (removes inherited or base variables).
+    /// Environment variable names or `*` globs to unset (removes inherited or base variables).
     pub unset_vars: Vec<String>,
+    /// Variables set only in the child's environment, `${VAR}` expanded.
+    pub game_env: BTreeMap<String, String>,
+    /// Variables removed only from the child's environment.
+    pub game_unset: Vec<String>,
+}
+
+/// Relative difference under which nested and output sizes count as the
+/// same shape (absorbs rounding like 1366x768 vs 16:9).
+const ASPECT_RATIO_TOLERANCE: f64 = 0.01;
+
+/// Names readable as `setting.<name>` with `get`, sorted.
+const SETTING_NAMES: &[&str] = &["binary", "hdr", "monitor", "wsi"];
+
+/// Version of the `show --dump-resolved` JSON; bumped on incompatible changes,
+/// including new fields every document must have.
+///
+/// 2 added the hook, session-log, wrapper, environment-stage and systemd
+/// scope fields among others.
+pub const RESOLVED_FORMAT_VERSION: u64 = 2;
+
+/// The `show --dump-resolved` document.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ResolvedDump {
+    format: u64,
+    profile: ResolvedProfile,
+}
+
+/// One step of the environment merge and what it changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvStage {
+    pub label: &'static str,
+    /// Sorted by variable name.
+    pub changes: Vec<EnvChange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvChange {
+    Added(String, String),
+    /// Name, previous value, new value.
+    Overridden(String, String, String),
+    /// Name and the value it had.
+    Removed(String, String),
+}
+
+/// What turned `before` into `after`.
+fn diff_env(before: &HashMap<String, String>, after: &HashMap<String, String>) -> Vec<EnvChange> {
+    let mut changes: Vec<_> = after
+        .iter()
+        .filter_map(|(key, value)| match before.get(key) {
+            None => Some(EnvChange::Added(key.clone(), value.clone())),
+            Some(old) if old != value => Some(EnvChange::Overridden(
+                key.clone(),
+                old.clone(),
+                value.clone(),
+            )),
+            Some(_) => None,
+        })
+        .chain(
+            before
+                .iter()
+                .filter(|(key, _)| !after.contains_key(*key))
+                .map(|(key, old)| EnvChange::Removed(key.clone(), old.clone())),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+impl EnvChange {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Added(name, _) | Self::Overridden(name, _, _) | Self::Removed(name, _) => name,
+        }
+    }
+}
+
+/// Matches `name` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(mut remaining) = name.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let suffix = parts.pop().unwrap_or("");
+    for part in parts {
+        match remaining.find(part) {
+            Some(at) => remaining = &remaining[at + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(suffix)
 }
 
 impl ResolvedProfile {
+    /// Serializes the profile for `run --from-resolved`.
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.to_value()?)
+            .context("Failed to serialize resolved profile")
+    }
+
+    /// The `show --dump-resolved` document as a JSON value.
+    pub fn to_value(&self) -> Result<serde_json::Value> {
+        let dump = ResolvedDump {
+            format: RESOLVED_FORMAT_VERSION,
+            profile: self.clone(),
+        };
+        serde_json::to_value(dump).context("Failed to serialize resolved profile")
+    }
+
+    /// Parses a `show --dump-resolved` document, rejecting other format
+    /// versions and unknown or missing fields.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::from_value(serde_json::from_str(json).context("Invalid JSON")?)
+    }
+
+    /// `from_json` for an already-parsed document.
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        match value.get("format").and_then(serde_json::Value::as_u64) {
+            Some(RESOLVED_FORMAT_VERSION) => {}
+            Some(format) => bail!(
+                "Unsupported resolved profile format {} (expected {}); re-run 'wayscope show <profile> --dump-resolved'",
+                format,
+                RESOLVED_FORMAT_VERSION
+            ),
+            None => bail!("Missing 'format' field; expected output of 'wayscope show <profile> --dump-resolved'"),
+        }
+        let dump: ResolvedDump = serde_json::from_value(value)
+            .context("Resolved profile does not match the expected schema")?;
+        dump.profile.validate_environment()?;
+        Ok(dump.profile)
+    }
+
+    /// Whether `name` matches any `unset` entry.
+    fn is_unset(&self, name: &str) -> bool {
+        self.unset_vars
+            .iter()
+            .any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Names to remove from the child's inherited environment.
+    ///
+    /// Plain entries pass through as-is; globs are expanded against a
+    /// snapshot of the current process environment.
+    pub fn unset_names(&self) -> Vec<String> {
+        self.expand_unset(std::env::vars_os().filter_map(|(k, _)| k.into_string().ok()))
+    }
+
+    /// For each `unset` entry, the inherited variables it will strip from
+    /// `parent` as (name, value) pairs, sorted.
+    pub fn inherited_unsets(
+        &self,
+        parent: &[(String, String)],
+    ) -> Vec<(String, Vec<(String, String)>)> {
+        let mut entries = self.unset_vars.clone();
+        entries.sort();
+        entries.dedup();
+        entries
+            .into_iter()
+            .map(|pattern| {
+                let mut matched: Vec<_> = parent
+                    .iter()
+                    .filter(|(name, _)| glob_match(&pattern, name))
+                    .cloned()
+                    .collect();
+                matched.sort();
+                (pattern, matched)
+            })
+            .collect()
+    }
+
+    fn expand_unset(&self, parent: impl IntoIterator<Item = String>) -> Vec<String> {
+        let (globs, mut names): (Vec<_>, Vec<_>) = self
+            .unset_vars
+            .iter()
+            .cloned()
+            .partition(|p| p.contains('*'));
+        names.extend(
+            parent
+                .into_iter()
+                .filter(|name| globs.iter().any(|pattern| glob_match(pattern, name))),
+        );
+        names
+    }
+
     /// Builds the complete environment: base vars + user vars + conditional HDR/WSI vars - unset vars.
     ///
     /// Environment variables are applied in this order:
-    /// 1. Base environment variables (BASE_ENV constants)
-    /// 2. User-defined environment from profile
-    /// 3. Conditional HDR/WSI environment variables
-    /// 4. Unset variables (removed from final environment)
+    /// 1. Base environment variables (the groups in `base_env`)
+    /// 2. User-defined environment from profile (a leading `+` appends to the
+    ///    base value, comma-joined, instead of replacing it; with no base
+    ///    value it's kept as is, and `++` is a literal `+`)
+    /// 3. Locale/timezone/log level fields, then conditional HDR/WSI environment variables
+    /// 4. The `WAYSCOPE_SESSION` nesting marker
+    /// 5. Unset variables (removed from final environment)
     pub fn environment(&self) -> Vec<(String, String)> {
-        let mut env: HashMap<String, String> = BASE_ENV
-            .iter()
-            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        self.environment_traced(&mut Trace::default())
+    }
+
+    /// `environment`, recording what each stage changes, what the `unset`
+    /// entries strip from the inherited environment and the child-only
+    /// variables in `trace` (`--trace`).
+    pub fn environment_traced(&self, trace: &mut Trace) -> Vec<(String, String)> {
+        let mut previous = HashMap::new();
+        let env = self.merge_environment(|label, env| {
+            if !trace.is_on() {
+                return;
+            }
+            for change in diff_env(&previous, env) {
+                trace.record("environment", || match change {
+                    EnvChange::Added(key, value) => format!("{}: {}={}", label, key, value),
+                    EnvChange::Overridden(key, old, new) => {
+                        format!("{}: {}={} (was {})", label, key, new, old)
+                    }
+                    EnvChange::Removed(key, old) => {
+                        format!("{}: {} removed (was {})", label, key, old)
+                    }
+                });
+            }
+            previous = env.clone();
+        });
+
+        if trace.is_on() {
+            let inherited: Vec<_> = std::env::vars().collect();
+            for (pattern, matched) in self.inherited_unsets(&inherited) {
+                let names: Vec<_> = matched.into_iter().map(|(name, _)| name).collect();
+                trace.record("unset", || match names.as_slice() {
+                    [] => format!("'{}' matches nothing inherited", pattern),
+                    names => format!("'{}' removes inherited {}", pattern, names.join(", ")),
+                });
+            }
+            for name in &self.game_unset {
+                trace.record("gameUnset", || format!("{} (child only)", name));
+            }
+            for (key, value) in &self.game_env {
+                trace.record("gameEnv", || format!("{}={} (child only)", key, value));
+            }
+        }
+
+        let mut sorted: Vec<_> = env.into_iter().collect();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        sorted
+    }
+
+    /// The merge behind `environment()`, one stage at a time, with the
+    /// variables each stage introduced, overrode or removed (`show --explain-order`).
+    pub fn environment_stages(&self) -> Vec<EnvStage> {
+        let mut stages = Vec::new();
+        let mut previous = HashMap::new();
+        self.merge_environment(|label, env| {
+            stages.push(EnvStage {
+                label,
+                changes: diff_env(&previous, env),
+            });
+            previous = env.clone();
+        });
+        stages
+    }
+
+    /// Runs the merge, calling `stage` with the environment after each step.
+    fn merge_environment(
+        &self,
+        mut stage: impl FnMut(&'static str, &HashMap<String, String>),
+    ) -> HashMap<String, String> {
+        let mut env: HashMap<String, String> = self
+            .base_env_vars()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
+        stage("base", &env);
+
+        for (key, value) in &self.user_env {
+            let value = match value.strip_prefix(APPEND_PREFIX) {
+                Some(literal) if literal.starts_with(APPEND_PREFIX) => literal.to_string(),
+                Some(extra) => match env.get(key) {
+                    Some(base) if !base.is_empty() => format!("{},{}", base, extra),
+                    Some(_) => extra.to_string(),
+                    // Nothing to append to, e.g. TZ=+05
+                    None => value.clone(),
+                },
+                None => value.clone(),
+            };
+            env.insert(key.clone(), value);
+        }
+        stage("environment", &env);
+
+        if self.env_priority == EnvPriority::Base {
+            env.extend(
+                self.base_env_vars()
+                    .map(|(k, v)| (k.to_string(), v.to_string())),
+            );
+            stage("base (envPriority: base)", &env);
+        }
 
-        env.extend(self.user_env.clone());
+        // Typed fields win over the same variables set in `environment`
+        if let Some(locale) = &self.locale {
+            env.insert("LANG".to_string(), locale.clone());
+            env.insert("LC_ALL".to_string(), locale.clone());
+        }
+        if let Some(tz) = &self.timezone {
+            env.insert("TZ".to_string(), tz.clone());
+        }
+        if let Some(level) = &self.gamescope_log {
+            env.insert(GAMESCOPE_LOG_ENV.to_string(), level.clone());
+        }
+        for (key, value) in self.steam_compat() {
+            env.insert(key.to_string(), value.to_string());
+        }
+        stage("typed fields", &env);
 
         if self.use_wsi {
             env.insert("ENABLE_GAMESCOPE_WSI".to_string(), "1".to_string());
@@ -67,25 +480,285 @@ impl ResolvedProfile {
             env.insert("ENABLE_HDR_WSI".to_string(), "1".to_string());
             env.insert("PROTON_ENABLE_HDR".to_string(), "1".to_string());
         }
+        stage("WSI/HDR", &env);
+
+        let session = if self.skip_gamescope {
+            SESSION_DIRECT
+        } else {
+            SESSION_GAMESCOPE
+        };
+        env.insert(SESSION_ENV.to_string(), session.to_string());
+        stage("session marker", &env);
 
         // Apply unset variables (remove specified variables from environment)
-        for var_name in &self.unset_vars {
-            env.remove(var_name);
-        }
+        env.retain(|key, _| !self.is_unset(key));
+        stage("unset", &env);
 
-        let mut sorted: Vec<_> = env.into_iter().collect();
-        sorted.sort_by(|a, b| a.0.cmp(&b.0));
-        sorted
+        env
+    }
+
+    /// The base variables of the enabled groups, as (name, value).
+    fn base_env_vars(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.base_env
+            .iter()
+            .flat_map(|group| base_env_group(*group).iter().copied())
+    }
+
+    /// `environment` entries that repeat a base variable's value, sorted.
+    pub fn env_matching_base(&self) -> Vec<(&'static str, &'static str)> {
+        let mut matching: Vec<_> = self
+            .base_env_vars()
+            .filter(|(k, v)| self.user_env.get(*k).is_some_and(|value| value == v))
+            .collect();
+        matching.sort();
+        matching
+    }
+
+    /// `unset` entries that match nothing: no variable the profile assembles
+    /// at any stage, and none of `parent` (the names it would inherit).
+    pub fn unused_unsets(&self, parent: &[String]) -> Vec<&str> {
+        let mut assembled = HashSet::new();
+        self.merge_environment(|_, env| assembled.extend(env.keys().cloned()));
+        self.unset_vars
+            .iter()
+            .filter(|pattern| {
+                !assembled
+                    .iter()
+                    .chain(parent)
+                    .any(|name| glob_match(pattern, name))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The Steam compat variables the typed fields set, as (name, value).
+    pub fn steam_compat(&self) -> Vec<(&'static str, &str)> {
+        [
+            (PROTON_VERSION_ENV, &self.proton_version),
+            (COMPAT_DATA_PATH_ENV, &self.compat_data_path),
+            (
+                COMPAT_CLIENT_INSTALL_PATH_ENV,
+                &self.compat_client_install_path,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+        .collect()
+    }
+
+    /// Final check that every resolved key is a valid name, so merging or
+    /// file-loaded values can't produce an environment that only fails at exec.
+    pub fn validate_environment(&self) -> Result<()> {
+        let invalid: Vec<_> = self
+            .environment()
+            .into_iter()
+            .filter(|(key, _)| !is_valid_env_var_name(key))
+            .map(|(key, _)| key)
+            .collect();
+        if !invalid.is_empty() {
+            bail!(
+                "Profile '{}': resolved environment has invalid names: {}",
+                self.name,
+                invalid.join(", ")
+            );
+        }
+        Ok(())
     }
 
     /// Wayland backend + WSI + HDR requires DISABLE_HDR_WSI=1 on the child process.
     pub fn needs_hdr_workaround(&self) -> bool {
+        self.hdr_workaround_conditions().iter().all(|(_, met)| *met)
+    }
+
+    /// Each condition of the HDR workaround and whether this profile meets it.
+    pub fn hdr_workaround_conditions(&self) -> [(&'static str, bool); 3] {
         let backend = self
             .options
             .get("backend")
-            .map(|v| v.to_string())
+            .map(|v| normalize_backend(&v.to_string()))
             .unwrap_or_default();
-        backend == "wayland" && self.use_wsi && self.use_hdr
+        [
+            ("backend == wayland", backend == "wayland"),
+            ("useWSI", self.use_wsi),
+            ("useHDR", self.use_hdr),
+        ]
+    }
+
+    /// Looks up a namespaced value: `options.<flag>`, `env.<VAR>`, or `setting.<name>`.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let (namespace, name) = key.split_once('.')?;
+        match namespace {
+            "options" => self.options.get(name).map(|v| v.to_string()),
+            "env" => self
+                .environment()
+                .into_iter()
+                .find_map(|(k, v)| (k == name).then_some(v)),
+            "setting" => self.setting(name),
+            _ => None,
+        }
+    }
+
+    fn setting(&self, name: &str) -> Option<String> {
+        match name {
+            "monitor" => Some(self.monitor_name.clone()),
+            "hdr" => Some(self.use_hdr.to_string()),
+            "wsi" => Some(self.use_wsi.to_string()),
+            "binary" => Some(self.binary.clone()),
+            _ => None,
+        }
+    }
+
+    /// Every value as a flat `(key, value)` pair for `show --flat`:
+    /// `setting.*`, `options.*` and `env.*` (each readable with `get`),
+    /// then `unset[N]` in config order. Sorted within each namespace.
+    pub fn flatten(&self) -> Vec<(String, String)> {
+        let settings = SETTING_NAMES
+            .iter()
+            .filter_map(|name| Some((format!("setting.{}", name), self.setting(name)?)));
+
+        let mut options: Vec<_> = self
+            .options
+            .iter()
+            .map(|(k, v)| (format!("options.{}", k), v.to_string()))
+            .collect();
+        options.sort();
+
+        let env = self
+            .environment()
+            .into_iter()
+            .map(|(k, v)| (format!("env.{}", k), v));
+        let unset = self
+            .unset_vars
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (format!("unset[{}]", i), name.clone()));
+
+        settings.chain(options).chain(env).chain(unset).collect()
+    }
+
+    fn int_option(&self, key: &str) -> Option<i64> {
+        match self.options.get(key) {
+            Some(OptionValue::Int(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Warns when the nested and output sizes differ in shape, so the game
+    /// is letterboxed (sometimes intended, e.g. 16:9 on an ultrawide).
+    fn aspect_ratio_mismatch(&self) -> Option<String> {
+        let ratio = |width: &str, height: &str| {
+            let (w, h) = (self.int_option(width)?, self.int_option(height)?);
+            (w > 0 && h > 0).then(|| (w, h, w as f64 / h as f64))
+        };
+        let (ow, oh, output) = ratio("output-width", "output-height")?;
+        let (nw, nh, nested) = ratio("nested-width", "nested-height")?;
+        if (output - nested).abs() / output <= ASPECT_RATIO_TOLERANCE {
+            return None;
+        }
+        Some(format!(
+            "Profile '{}' renders at {}x{} ({:.2}:1) into a {}x{} ({:.2}:1) output; the \
+             differing aspect ratios will letterbox the game.",
+            self.name, nw, nh, nested, ow, oh, output
+        ))
+    }
+
+    /// Non-fatal configuration issues worth surfacing before launch.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.config_warnings.clone();
+
+        if self.use_hdr && !self.use_wsi && !self.allow_hdr_without_wsi {
+            warnings.push(format!(
+                "Profile '{}' enables HDR without the Gamescope WSI layer; games may not \
+                 receive HDR output. Set 'allowHDRWithoutWSI: true' if this is intentional.",
+                self.name
+            ));
+        }
+
+        if self.env_priority == EnvPriority::Base {
+            let mut shadowed: Vec<_> = self
+                .base_env_vars()
+                .map(|(k, _)| k)
+                .filter(|k| self.user_env.contains_key(*k))
+                .collect();
+            shadowed.sort();
+            if !shadowed.is_empty() {
+                warnings.push(format!(
+                    "Profile '{}' sets envPriority: base, so wayscope's defaults replace its \
+                     environment values for {}.",
+                    self.name,
+                    shadowed.join(", ")
+                ));
+            }
+        }
+
+        if self.immediate_flips_with_vrr.is_none() && vrr_with_immediate_flips(&self.options) {
+            warnings.push(format!(
+                "Profile '{}' enables both adaptive-sync and immediate-flips, which can fight \
+                 on some setups. Set 'immediateFlipsWithVRR: false' to drop immediate-flips \
+                 under VRR, or 'true' to keep it and silence this warning.",
+                self.name
+            ));
+        }
+
+        // Supersampling renders above the output size on purpose
+        let size_pairs: &[_] = if self.supersample.is_some() {
+            &[]
+        } else {
+            &[
+                ("nested-width", "output-width"),
+                ("nested-height", "output-height"),
+            ]
+        };
+        for (nested, output) in size_pairs {
+            if let (Some(n), Some(o)) = (self.int_option(nested), self.int_option(output)) {
+                if n > o {
+                    warnings.push(format!(
+                        "Profile '{}' sets {} {} above {} {}; rendering above the output \
+                         size is usually a mistake.",
+                        self.name, nested, n, output, o
+                    ));
+                }
+            }
+        }
+
+        if let Some(warning) = self.aspect_ratio_mismatch() {
+            warnings.push(warning);
+        }
+
+        if !self.use_hdr {
+            for flag in [SDR_NITS_OPTION, HDR_MAX_NITS_OPTION] {
+                if self.options.contains_key(flag) {
+                    warnings.push(format!(
+                        "Profile '{}' sets --{} but HDR is disabled; it has no effect.",
+                        self.name, flag
+                    ));
+                }
+            }
+        }
+
+        for (field, path) in [
+            ("compatDataPath", &self.compat_data_path),
+            ("compatClientInstallPath", &self.compat_client_install_path),
+        ] {
+            if let Some(path) = path {
+                if !std::path::Path::new(path).is_dir() {
+                    warnings.push(format!(
+                        "Profile '{}' sets {} to '{}', which is not an existing directory.",
+                        self.name, field, path
+                    ));
+                }
+            }
+        }
+
+        if self.use_hdr && self.disable_color_management {
+            warnings.push(format!(
+                "Profile '{}' disables color management with HDR enabled; \
+                 HDR output will likely be incorrect.",
+                self.name
+            ));
+        }
+
+        warnings
     }
 }
 
@@ -104,14 +777,98 @@ mod tests {
             name: "test".to_string(),
             monitor_name: "main".to_string(),
             binary: "gamescope".to_string(),
+            binary_source: BinarySource::Default,
             use_hdr,
             use_wsi,
+            allow_hdr_without_wsi: false,
+            immediate_flips_with_vrr: None,
+            rotation: None,
+            disable_color_management: false,
+            quiet_child: false,
+            notify_on_exit: false,
+            log_sessions: false,
+            skip_gamescope: false,
+            locale: None,
+            timezone: None,
+            gamescope_log: None,
+            pre_hook: None,
+            post_hook: None,
+            pre_timeout: None,
+            post_timeout: None,
+            hook_failure: HookFailure::Abort,
+            supersample: None,
+            proton_version: None,
+            compat_data_path: None,
+            compat_client_install_path: None,
+            icc_profile: None,
+            rlimits: BTreeMap::new(),
+            wrapper: Vec::new(),
+            wrapper_target: WrapperTarget::Gamescope,
+            env_priority: EnvPriority::User,
+            base_env: BaseEnvGroup::ALL.into_iter().collect(),
+            systemd_scope: false,
+            scope_properties: BTreeMap::new(),
+            config_warnings: Vec::new(),
             options,
             user_env: HashMap::new(),
             unset_vars: Vec::new(),
+            game_env: BTreeMap::new(),
+            game_unset: Vec::new(),
         }
     }
 
+    #[test]
+    fn test_resolved_json_round_trip() {
+        let mut profile = mock_profile(true, true, "wayland");
+        profile.binary_source = BinarySource::Environment;
+        profile.rotation = Some(90);
+        profile.locale = Some("ja_JP.UTF-8".to_string());
+        profile
+            .options
+            .insert("fsr-sharpness".to_string(), OptionValue::Int(5));
+        profile
+            .user_env
+            .insert("DXVK_HUD".to_string(), "fps".to_string());
+        profile.unset_vars.push("SDL_*".to_string());
+
+        let json = profile.to_json().unwrap();
+        assert!(json.contains("\"format\": 2"));
+        assert!(json.contains("\"binarySource\": \"environment\""));
+        assert_eq!(ResolvedProfile::from_json(&json).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_resolved_json_schema_errors() {
+        let json = mock_profile(false, false, "sdl").to_json().unwrap();
+
+        // Dumps from before the current fields were added are refused
+        let err = ResolvedProfile::from_json(&json.replace("\"format\": 2", "\"format\": 1"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported resolved profile format 1 (expected 2)"));
+
+        let err = ResolvedProfile::from_json("{\"profile\": {}}").unwrap_err();
+        assert!(err.to_string().contains("Missing 'format' field"));
+
+        let err =
+            ResolvedProfile::from_json(&json.replace("\"useHdr\"", "\"useHDR\"")).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown field `useHDR`"));
+
+        assert!(ResolvedProfile::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_session_marker() {
+        let mut profile = mock_profile(false, false, "sdl");
+        let env: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env[SESSION_ENV], SESSION_GAMESCOPE);
+
+        profile.skip_gamescope = true;
+        let env: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env[SESSION_ENV], SESSION_DIRECT);
+    }
+
     #[test]
     fn test_base_environment_included() {
         let profile = mock_profile(false, false, "sdl");
@@ -157,6 +914,14 @@ mod tests {
         assert!(profile.needs_hdr_workaround());
     }
 
+    #[test]
+    fn test_hdr_workaround_backend_normalized() {
+        for backend in ["Wayland", " wayland ", "WAYLAND"] {
+            let profile = mock_profile(true, true, backend);
+            assert!(profile.needs_hdr_workaround(), "{:?}", backend);
+        }
+    }
+
     #[test]
     fn test_hdr_workaround_not_needed_sdl() {
         let profile = mock_profile(true, true, "sdl");
@@ -169,6 +934,183 @@ mod tests {
         assert!(!profile.needs_hdr_workaround());
     }
 
+    #[test]
+    fn test_warns_hdr_without_wsi() {
+        let profile = mock_profile(true, false, "sdl");
+        assert_eq!(profile.warnings().len(), 1);
+        assert!(profile.warnings()[0].contains("WSI"));
+    }
+
+    #[test]
+    fn test_no_warning_hdr_without_wsi_when_allowed() {
+        let mut profile = mock_profile(true, false, "sdl");
+        profile.allow_hdr_without_wsi = true;
+        assert!(profile.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_no_warning_hdr_with_wsi() {
+        assert!(mock_profile(true, true, "sdl").warnings().is_empty());
+        assert!(mock_profile(false, false, "sdl").warnings().is_empty());
+    }
+
+    #[test]
+    fn test_warns_hdr_without_color_management() {
+        let mut profile = mock_profile(true, true, "sdl");
+        profile.disable_color_management = true;
+        assert_eq!(profile.warnings().len(), 1);
+        assert!(profile.warnings()[0].contains("color management"));
+
+        profile.use_hdr = false;
+        assert!(profile.warnings().is_empty());
+    }
+
+    fn with_resolution(nested: (i64, i64), output: (i64, i64)) -> ResolvedProfile {
+        let mut profile = mock_profile(false, true, "sdl");
+        for (key, value) in [
+            ("nested-width", nested.0),
+            ("nested-height", nested.1),
+            ("output-width", output.0),
+            ("output-height", output.1),
+        ] {
+            profile
+                .options
+                .insert(key.to_string(), OptionValue::Int(value));
+        }
+        profile
+    }
+
+    #[test]
+    fn test_warns_nested_above_output() {
+        let warnings = with_resolution((3840, 2160), (2560, 1440)).warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("nested-width 3840 above output-width 2560"));
+        assert!(warnings[1].contains("nested-height 2160 above output-height 1440"));
+    }
+
+    #[test]
+    fn test_no_warning_nested_below_or_equal_output() {
+        assert!(with_resolution((1920, 1080), (2560, 1440))
+            .warnings()
+            .is_empty());
+        assert!(with_resolution((2560, 1440), (2560, 1440))
+            .warnings()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_warns_aspect_ratio_mismatch() {
+        let warnings = with_resolution((1920, 1200), (2560, 1440)).warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("1920x1200 (1.60:1) into a 2560x1440 (1.78:1) output"));
+
+        // Ultrawide output with 16:9 nested
+        assert_eq!(
+            with_resolution((2560, 1440), (3440, 1440)).warnings().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_no_warning_matching_aspect_ratio() {
+        assert!(with_resolution((1280, 720), (2560, 1440))
+            .warnings()
+            .is_empty());
+        // 1366x768 is only approximately 16:9
+        assert!(with_resolution((1366, 768), (1920, 1080))
+            .warnings()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_namespaced_values() {
+        let profile = mock_profile(true, true, "wayland");
+
+        assert_eq!(profile.get("options.backend").as_deref(), Some("wayland"));
+        assert_eq!(profile.get("env.DXVK_HDR").as_deref(), Some("1"));
+        assert_eq!(profile.get("setting.hdr").as_deref(), Some("true"));
+        assert_eq!(profile.get("setting.monitor").as_deref(), Some("main"));
+        assert_eq!(profile.get("setting.binary").as_deref(), Some("gamescope"));
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut profile = mock_profile(true, true, "wayland");
+        profile.unset_vars = vec!["SDL_VIDEODRIVER".to_string(), "DISPLAY".to_string()];
+        let flat = profile.flatten();
+
+        let keys: Vec<_> = flat.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(
+            keys[..5],
+            [
+                "setting.binary",
+                "setting.hdr",
+                "setting.monitor",
+                "setting.wsi",
+                "options.backend"
+            ]
+        );
+        assert!(keys.contains(&"env.DXVK_HDR"));
+        assert_eq!(keys[keys.len() - 2..], ["unset[0]", "unset[1]"]);
+        assert_eq!(flat[flat.len() - 2].1, "SDL_VIDEODRIVER");
+
+        // Every namespaced key reads back the same value through `get`
+        for (key, value) in flat.iter().filter(|(k, _)| !k.starts_with("unset[")) {
+            assert_eq!(profile.get(key).as_ref(), Some(value), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_get_missing_values() {
+        let profile = mock_profile(false, false, "sdl");
+
+        assert_eq!(profile.get("options.output-width"), None);
+        assert_eq!(profile.get("env.DXVK_HDR"), None);
+        assert_eq!(profile.get("setting.unknown"), None);
+        assert_eq!(profile.get("backend"), None);
+        assert_eq!(profile.get("other.backend"), None);
+    }
+
+    #[test]
+    fn test_hdr_workaround_conditions() {
+        let profile = mock_profile(true, false, "wayland");
+        assert_eq!(
+            profile.hdr_workaround_conditions(),
+            [
+                ("backend == wayland", true),
+                ("useWSI", false),
+                ("useHDR", true)
+            ]
+        );
+        assert!(!profile.needs_hdr_workaround());
+    }
+
+    #[test]
+    fn test_warns_nits_without_hdr() {
+        let mut profile = mock_profile(false, true, "sdl");
+        profile
+            .options
+            .insert(SDR_NITS_OPTION.to_string(), OptionValue::Int(203));
+        let warnings = profile.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("--hdr-sdr-content-nits"));
+
+        profile.use_hdr = true;
+        assert!(profile.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_validate_environment() {
+        let mut profile = mock_profile(true, true, "sdl");
+        assert!(profile.validate_environment().is_ok());
+
+        profile
+            .user_env
+            .insert("BAD-NAME".to_string(), "1".to_string());
+        let err = profile.validate_environment().unwrap_err().to_string();
+        assert!(err.contains("BAD-NAME"));
+    }
+
     #[test]
     fn test_unset_basic_variable() {
         let mut profile = mock_profile(false, false, "sdl");
@@ -194,6 +1136,119 @@ mod tests {
         assert!(env_map.contains_key("AMD_VULKAN_ICD"));
     }
 
+    #[test]
+    fn test_env_append_to_base() {
+        let mut profile = mock_profile(false, false, "sdl");
+        profile
+            .user_env
+            .insert("PROTON_ADD_CONFIG".to_string(), "+mangohud".to_string());
+        profile
+            .user_env
+            .insert("RADV_PERFTEST".to_string(), "gpl".to_string());
+        profile
+            .user_env
+            .insert("NOT_IN_BASE".to_string(), "+solo".to_string());
+
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        // Append joins onto the base value
+        assert_eq!(
+            env_map["PROTON_ADD_CONFIG"],
+            "sdlinput,wayland,hdr,mangohud"
+        );
+        // Plain values still replace
+        assert_eq!(env_map["RADV_PERFTEST"], "gpl");
+        // With no base value the '+' is part of the value
+        assert_eq!(env_map["NOT_IN_BASE"], "+solo");
+    }
+
+    #[test]
+    fn test_env_append_escape() {
+        let mut profile = mock_profile(false, false, "sdl");
+        profile
+            .user_env
+            .insert("PROTON_ADD_CONFIG".to_string(), "++literal".to_string());
+        profile
+            .user_env
+            .insert("NOT_IN_BASE".to_string(), "++1".to_string());
+
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env_map["PROTON_ADD_CONFIG"], "+literal");
+        assert_eq!(env_map["NOT_IN_BASE"], "+1");
+    }
+
+    #[test]
+    fn test_base_env_groups() {
+        let mut profile = mock_profile(false, false, "sdl");
+        // Every combination of enabled groups
+        for mask in 0..1 << BaseEnvGroup::ALL.len() {
+            profile.base_env = BaseEnvGroup::ALL
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, group)| group)
+                .collect();
+            let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+            for group in BaseEnvGroup::ALL {
+                let enabled = profile.base_env.contains(&group);
+                for (key, value) in base_env_group(group) {
+                    assert_eq!(
+                        env_map.get(*key).map(String::as_str),
+                        enabled.then_some(*value),
+                        "{} with groups {:?}",
+                        key,
+                        profile.base_env
+                    );
+                }
+            }
+            // The session marker doesn't belong to any group
+            assert!(env_map.contains_key(SESSION_ENV));
+        }
+    }
+
+    #[test]
+    fn test_env_priority() {
+        let mut profile = mock_profile(false, false, "sdl");
+        profile
+            .user_env
+            .insert("RADV_PERFTEST".to_string(), "gpl".to_string());
+        profile
+            .user_env
+            .insert("PROTON_ADD_CONFIG".to_string(), "+mangohud".to_string());
+        profile
+            .user_env
+            .insert("MANGOHUD".to_string(), "1".to_string());
+
+        // Default: the profile wins
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env_map["RADV_PERFTEST"], "gpl");
+        assert!(profile
+            .warnings()
+            .iter()
+            .all(|w| !w.contains("envPriority")));
+
+        // Base: wayscope's value wins, appends included; other variables still apply
+        profile.env_priority = EnvPriority::Base;
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env_map["RADV_PERFTEST"], "aco");
+        assert_eq!(env_map["PROTON_ADD_CONFIG"], "sdlinput,wayland,hdr");
+        assert_eq!(env_map["MANGOHUD"], "1");
+        assert!(profile.warnings().iter().any(|w| w.contains(
+            "envPriority: base, so wayscope's defaults replace its environment values for \
+             PROTON_ADD_CONFIG, RADV_PERFTEST."
+        )));
+
+        // Typed fields and unset still come after the base values
+        profile.locale = Some("C.UTF-8".to_string());
+        profile.unset_vars = vec!["RADV_PERFTEST".to_string()];
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert_eq!(env_map["LANG"], "C.UTF-8");
+        assert!(!env_map.contains_key("RADV_PERFTEST"));
+        assert!(profile
+            .environment_stages()
+            .iter()
+            .any(|s| s.label == "base (envPriority: base)"));
+    }
+
     #[test]
     fn test_unset_overrides_user_env() {
         let mut profile = mock_profile(false, false, "sdl");
@@ -229,4 +1284,180 @@ mod tests {
         // But ENABLE_HDR_WSI should still be there (only those two unset)
         assert_eq!(env_map.get("ENABLE_HDR_WSI"), Some(&"1".to_string()));
     }
+
+    #[test]
+    fn test_environment_stages() {
+        let mut profile = mock_profile(true, false, "sdl");
+        profile
+            .user_env
+            .insert("RADV_PERFTEST".to_string(), "gpl".to_string());
+        profile.user_env.insert("LANG".to_string(), "C".to_string());
+        profile.locale = Some("ja_JP.UTF-8".to_string());
+        profile.unset_vars = vec!["SDL_VIDEODRIVER".to_string()];
+
+        let stages = profile.environment_stages();
+        let labels: Vec<_> = stages.iter().map(|s| s.label).collect();
+        assert_eq!(
+            labels,
+            [
+                "base",
+                "environment",
+                "typed fields",
+                "WSI/HDR",
+                "session marker",
+                "unset"
+            ]
+        );
+        assert_eq!(
+            stages[0].changes.len(),
+            AMD_ENV.len() + WAYLAND_ENV.len() + PROTON_ENV.len()
+        );
+        assert_eq!(
+            stages[1].changes,
+            vec![
+                EnvChange::Added("LANG".to_string(), "C".to_string()),
+                EnvChange::Overridden(
+                    "RADV_PERFTEST".to_string(),
+                    "aco".to_string(),
+                    "gpl".to_string()
+                ),
+            ]
+        );
+        assert!(stages[2].changes.contains(&EnvChange::Overridden(
+            "LANG".to_string(),
+            "C".to_string(),
+            "ja_JP.UTF-8".to_string()
+        )));
+        assert_eq!(stages[3].changes.len(), 3);
+        assert_eq!(
+            stages[5].changes,
+            vec![EnvChange::Removed(
+                "SDL_VIDEODRIVER".to_string(),
+                "wayland".to_string()
+            )]
+        );
+
+        // Replaying the stages gives exactly `environment()`
+        let mut replayed = HashMap::new();
+        for change in stages.into_iter().flat_map(|s| s.changes) {
+            match change {
+                EnvChange::Added(k, v) | EnvChange::Overridden(k, _, v) => {
+                    replayed.insert(k, v);
+                }
+                EnvChange::Removed(k, _) => {
+                    replayed.remove(&k);
+                }
+            }
+        }
+        let mut replayed: Vec<_> = replayed.into_iter().collect();
+        replayed.sort();
+        assert_eq!(replayed, profile.environment());
+    }
+
+    #[test]
+    fn test_environment_traced() {
+        let mut profile = mock_profile(false, false, "sdl");
+        profile
+            .user_env
+            .insert("RADV_PERFTEST".to_string(), "gpl".to_string());
+        profile.unset_vars = vec!["SDL_VIDEODRIVER".to_string()];
+        profile
+            .game_env
+            .insert("DXVK_ASYNC".to_string(), "1".to_string());
+
+        let mut trace = Trace::on();
+        assert_eq!(
+            profile.environment_traced(&mut trace),
+            profile.environment()
+        );
+        let steps: Vec<String> = trace
+            .steps()
+            .iter()
+            .map(|(stage, message)| format!("{}: {}", stage, message))
+            .collect();
+        for expected in [
+            "environment: environment: RADV_PERFTEST=gpl (was aco)",
+            "environment: session marker: WAYSCOPE_SESSION=gamescope",
+            "environment: unset: SDL_VIDEODRIVER removed (was wayland)",
+            "gameEnv: DXVK_ASYNC=1 (child only)",
+        ] {
+            assert!(
+                steps.iter().any(|s| s == expected),
+                "missing {:?} in {:#?}",
+                expected,
+                steps
+            );
+        }
+        assert!(steps
+            .iter()
+            .any(|s| s.starts_with("unset: 'SDL_VIDEODRIVER' ")));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("SDL_*", "SDL_VIDEODRIVER"));
+        assert!(glob_match("SDL_*", "SDL_"));
+        assert!(!glob_match("SDL_*", "XSDL_VIDEODRIVER"));
+        assert!(glob_match("*_HDR", "DXVK_HDR"));
+        assert!(glob_match("DXVK_*_LOG*", "DXVK_HUD_LOG_LEVEL"));
+        assert!(!glob_match("A*A", "A"));
+        assert!(glob_match("EXACT", "EXACT"));
+        assert!(!glob_match("EXACT", "EXACT_NOT"));
+    }
+
+    #[test]
+    fn test_unset_glob_removes_family() {
+        let mut profile = mock_profile(true, true, "sdl");
+        profile.unset_vars = vec!["*_HDR".to_string(), "PROTON_*".to_string()];
+
+        let env_map: HashMap<_, _> = profile.environment().into_iter().collect();
+        assert!(!env_map.contains_key("DXVK_HDR"));
+        assert!(!env_map.contains_key("PROTON_ENABLE_HDR"));
+        assert!(!env_map.contains_key("PROTON_ENABLE_WAYLAND"));
+        assert_eq!(env_map.get("ENABLE_HDR_WSI"), Some(&"1".to_string()));
+    }
+
+    #[test]
+    fn test_inherited_unsets() {
+        let mut profile = mock_profile(false, true, "sdl");
+        profile.unset_vars = vec![
+            "SDL_*".to_string(),
+            "DISPLAY".to_string(),
+            "GONE".to_string(),
+        ];
+
+        let parent = [
+            ("SDL_AUDIODRIVER", "pulse"),
+            ("DISPLAY", ":0"),
+            ("HOME", "/home/me"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let effects = profile.inherited_unsets(&parent);
+
+        assert_eq!(
+            effects,
+            [
+                (
+                    "DISPLAY".to_string(),
+                    vec![("DISPLAY".to_string(), ":0".to_string())]
+                ),
+                ("GONE".to_string(), vec![]),
+                (
+                    "SDL_*".to_string(),
+                    vec![("SDL_AUDIODRIVER".to_string(), "pulse".to_string())]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_unset_against_parent_env() {
+        let mut profile = mock_profile(false, true, "sdl");
+        profile.unset_vars = vec!["SDL_*".to_string(), "PLAIN".to_string()];
+
+        let parent = ["SDL_AUDIODRIVER", "HOME", "SDL_VIDEODRIVER"].map(String::from);
+        let mut names = profile.expand_unset(parent);
+        names.sort();
+        assert_eq!(names, ["PLAIN", "SDL_AUDIODRIVER", "SDL_VIDEODRIVER"]);
+    }
 }
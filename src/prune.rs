@@ -0,0 +1,330 @@
+//! Removes or repairs profiles that reference monitors no longer defined.
+
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::config::{MonitorsConfig, ProfilesConfig};
+use crate::output;
+
+/// What to do with a profile whose monitor no longer exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Remove,
+    /// Clear `monitor` so the profile falls back to the primary monitor.
+    UsePrimary,
+    Keep,
+}
+
+pub fn run(
+    monitor_layers: &[PathBuf],
+    profile_layers: &[PathBuf],
+    profiles_path: &Path,
+    yes: bool,
+) -> Result<()> {
+    // Loaded separately: Config::load rejects exactly the profiles we're fixing.
+    // Monitors come from every layer so profiles using monitors defined
+    // elsewhere aren't flagged, and the profiles below the user's file are
+    // loaded for their compose fragments; only the user's file is rewritten.
+    let monitors = MonitorsConfig::load_layers(monitor_layers)?;
+    let lower = ProfilesConfig::load_below(profile_layers, profiles_path)?;
+    let mut profiles = ProfilesConfig::load(profiles_path)?;
+
+    let mut fallback: Vec<_> = profiles
+        .profiles
+        .iter()
+        .filter(|(_, p)| p.monitor.is_none())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !fallback.is_empty() {
+        fallback.sort();
+        output::info(&format!(
+            "Using the primary monitor (no 'monitor' set): {}",
+            fallback.join(", ")
+        ));
+    }
+
+    let changed = prune(&mut profiles, &lower, &monitors, |name, monitor, own| {
+        if own {
+            output::warn(&format!(
+                "Profile '{}' references missing monitor '{}'",
+                name, monitor
+            ));
+        } else {
+            output::warn(&format!(
+                "Profile '{}' gets missing monitor '{}' from a compose fragment",
+                name, monitor
+            ));
+        }
+        if yes || output::confirm(&format!("Remove profile '{}'?", name))? {
+            Ok(Action::Remove)
+        } else if own && output::confirm("Use the primary monitor instead?")? {
+            Ok(Action::UsePrimary)
+        } else {
+            Ok(Action::Keep)
+        }
+    })?;
+
+    if changed.is_empty() {
+        output::success("Nothing to prune");
+        return Ok(());
+    }
+
+    profiles.save(profiles_path)?;
+    for line in changed {
+        output::success(&line);
+    }
+    Ok(())
+}
+
+/// Applies `decide` to each broken profile in `profiles` and returns a
+/// description of each change. `decide` gets the profile, its missing
+/// monitor and whether the profile sets it itself rather than through a
+/// `compose` fragment from `profiles` or `lower`.
+///
+/// Profiles that compose others are decided first, and a profile another
+/// one still composes is never removed. Aliases pointing at removed
+/// profiles are dropped as well.
+fn prune(
+    profiles: &mut ProfilesConfig,
+    lower: &ProfilesConfig,
+    monitors: &MonitorsConfig,
+    mut decide: impl FnMut(&str, &str, bool) -> Result<Action>,
+) -> Result<Vec<String>> {
+    let merged = lower.overlaid(profiles);
+    let mut missing: Vec<_> = merged
+        .dead_monitor_refs(monitors)
+        .into_iter()
+        .filter(|(name, _, _)| profiles.profiles.contains_key(name))
+        .collect();
+    missing.sort_by_key(|(name, _, _)| Reverse(compose_depth(&merged, name)));
+
+    let mut changed = Vec::new();
+    for (name, monitor, own) in missing {
+        match decide(&name, &monitor, own)? {
+            Action::Remove => {
+                // A lower layer's profile of the same name takes over for
+                // its users; otherwise they'd fail to compose
+                let users = match lower.canonical_name(&name) {
+                    Ok(_) => Vec::new(),
+                    Err(_) => composed_by(&lower.overlaid(profiles), &name),
+                };
+                if !users.is_empty() {
+                    output::warn(&format!(
+                        "Kept profile '{}': {} still composes it",
+                        name,
+                        users.join(", ")
+                    ));
+                    continue;
+                }
+                profiles.profiles.remove(&name);
+                changed.push(format!("Removed profile '{}'", name));
+            }
+            Action::UsePrimary if own => {
+                if let Some(profile) = profiles.profiles.get_mut(&name) {
+                    profile.monitor = None;
+                }
+                changed.push(format!("Profile '{}' now uses the primary monitor", name));
+            }
+            // Clearing `monitor` can't help a profile that inherits it
+            Action::UsePrimary | Action::Keep => {}
+        }
+    }
+
+    let mut dangling: Vec<String> = profiles
+        .aliases
+        .keys()
+        .filter(|alias| profiles.canonical_name(alias).is_err())
+        .cloned()
+        .collect();
+    dangling.sort();
+    for alias in dangling {
+        profiles.aliases.remove(&alias);
+        changed.push(format!("Removed alias '{}'", alias));
+    }
+
+    Ok(changed)
+}
+
+/// How many `compose` levels sit below `name`: 0 for a profile that
+/// composes nothing.
+fn compose_depth(profiles: &ProfilesConfig, name: &str) -> usize {
+    let Some(profile) = profiles
+        .canonical_name(name)
+        .ok()
+        .and_then(|name| profiles.profiles.get(name))
+    else {
+        return 0;
+    };
+    profile
+        .compose
+        .iter()
+        .map(|fragment| 1 + compose_depth(profiles, fragment))
+        .max()
+        .unwrap_or(0)
+}
+
+/// The profiles in `merged` that compose `name` directly, sorted.
+fn composed_by(merged: &ProfilesConfig, name: &str) -> Vec<String> {
+    let mut users: Vec<_> = merged
+        .profiles
+        .iter()
+        .filter(|(_, profile)| {
+            profile
+                .compose
+                .iter()
+                .any(|fragment| merged.canonical_name(fragment).ok() == Some(name))
+        })
+        .map(|(user, _)| user.clone())
+        .collect();
+    users.sort();
+    users
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (MonitorsConfig, ProfilesConfig) {
+        let monitors = serde_yaml::from_str(
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        )
+        .unwrap();
+        let profiles = serde_yaml::from_str(
+            r#"
+profiles:
+  default: {}
+  couch: { monitor: tv }
+  portable: { monitor: deck }
+aliases:
+  c: couch
+"#,
+        )
+        .unwrap();
+        (monitors, profiles)
+    }
+
+    fn lower() -> ProfilesConfig {
+        ProfilesConfig::default()
+    }
+
+    #[test]
+    fn test_prune_remove_and_fix() {
+        let (monitors, mut profiles) = fixtures();
+        let changed = prune(&mut profiles, &lower(), &monitors, |name, _, _| {
+            Ok(if name == "couch" {
+                Action::Remove
+            } else {
+                Action::UsePrimary
+            })
+        })
+        .unwrap();
+
+        assert_eq!(
+            changed,
+            vec![
+                "Removed profile 'couch'",
+                "Profile 'portable' now uses the primary monitor",
+                "Removed alias 'c'",
+            ]
+        );
+        assert!(!profiles.profiles.contains_key("couch"));
+        assert_eq!(profiles.profiles["portable"].monitor, None);
+        assert!(profiles.missing_monitor_refs(&monitors).is_empty());
+    }
+
+    #[test]
+    fn test_prune_follows_compose() {
+        let (monitors, _) = fixtures();
+        let lower: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  tvbase: { monitor: tv }").unwrap();
+        let mut profiles: ProfilesConfig = serde_yaml::from_str(
+            r#"
+profiles:
+  couch: { compose: [tvbase] }
+  desk: { compose: [hdr, tvbase], monitor: main }
+  hdr: { monitor: hdr-tv }
+  portable: { compose: [couch] }
+"#,
+        )
+        .unwrap();
+
+        // Composites come first, and only profiles that set the monitor
+        // themselves are offered the primary monitor
+        let mut asked = Vec::new();
+        let changed = prune(&mut profiles, &lower, &monitors, |name, monitor, own| {
+            asked.push((name.to_string(), monitor.to_string(), own));
+            Ok(if own {
+                Action::Remove
+            } else {
+                Action::UsePrimary
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            asked,
+            vec![
+                ("portable".to_string(), "tv".to_string(), false),
+                ("couch".to_string(), "tv".to_string(), false),
+                ("hdr".to_string(), "hdr-tv".to_string(), true),
+            ]
+        );
+        // 'desk' still composes 'hdr', so it stays
+        assert!(changed.is_empty());
+        assert_eq!(profiles.profiles.len(), 4);
+
+        let changed = prune(&mut profiles, &lower, &monitors, |_, _, _| {
+            Ok(Action::Remove)
+        })
+        .unwrap();
+        assert_eq!(
+            changed,
+            vec!["Removed profile 'portable'", "Removed profile 'couch'"]
+        );
+        assert!(profiles.profiles.contains_key("hdr"));
+    }
+
+    #[test]
+    fn test_prune_sees_monitors_from_every_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.yaml");
+        let upper = dir.path().join("upper.yaml");
+        let profiles_path = dir.path().join("profiles.yaml");
+        std::fs::write(
+            &base,
+            "monitors:\n  main: { width: 1920, height: 1080, refreshRate: 60, primary: true }",
+        )
+        .unwrap();
+        std::fs::write(
+            &upper,
+            "monitors:\n  tv: { width: 3840, height: 2160, refreshRate: 60 }",
+        )
+        .unwrap();
+        std::fs::write(&profiles_path, "profiles:\n  couch: { monitor: tv }\n").unwrap();
+
+        run(
+            &[base, upper],
+            std::slice::from_ref(&profiles_path),
+            &profiles_path,
+            true,
+        )
+        .unwrap();
+
+        let profiles = ProfilesConfig::load(&profiles_path).unwrap();
+        assert_eq!(profiles.profiles["couch"].monitor.as_deref(), Some("tv"));
+    }
+
+    #[test]
+    fn test_prune_keep_changes_nothing() {
+        let (monitors, mut profiles) = fixtures();
+        let changed = prune(&mut profiles, &lower(), &monitors, |_, _, _| {
+            Ok(Action::Keep)
+        })
+        .unwrap();
+
+        assert!(changed.is_empty());
+        assert_eq!(profiles.profiles.len(), 3);
+        assert!(profiles.aliases.contains_key("c"));
+    }
+}
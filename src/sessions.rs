@@ -0,0 +1,406 @@
+//! Session log for `logSessions` / `run --session-log`, read by `sessions`.
+//!
+//! Launches append JSON lines to `$XDG_STATE_HOME/wayscope/sessions.jsonl`.
+//! A `start` record is written just before launching. When wayscope waits
+//! for the session (`--notify`, `--diag-bundle`, `--watch`), an `end` record
+//! follows when it exits; an exec launch replaces wayscope, so it only has
+//! the start record. Fields:
+//! - `event` - `start` or `end`
+//! - `profile`, `monitor` - the resolved profile and its monitor
+//! - `binary` - the gamescope binary, `null` when gamescope was skipped
+//! - `command` - the child command (empty for `--no-child`)
+//! - `id` - unique per session; pairs an end record with its start
+//! - `pid` - wayscope's PID
+//! - `start` - launch time in Unix seconds
+//! - `end` - exit time in Unix seconds (end records only)
+//! - `exitCode`, or `signal` if the session was killed by one (end records only)
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::output;
+use crate::profile::ResolvedProfile;
+use crate::util;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Start,
+    End,
+}
+
+/// One line of the session log.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Record {
+    pub event: Event,
+    /// Missing from records written before ids were added; those pair by
+    /// `pid` and `start`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub profile: String,
+    pub monitor: String,
+    pub binary: Option<String>,
+    pub command: Vec<String>,
+    pub pid: u32,
+    pub start: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+}
+
+/// A start record and, if wayscope waited for the session, its end record.
+#[derive(Debug, PartialEq)]
+pub struct Session {
+    pub start: Record,
+    pub end: Option<Record>,
+}
+
+/// Default location of the session log.
+pub fn log_path() -> Result<PathBuf> {
+    let dir = dirs::state_dir()
+        .context("Can't find the state directory for the session log (is HOME set?)")?;
+    Ok(dir.join("wayscope").join("sessions.jsonl"))
+}
+
+/// A session id no other launch shares: a `--watch` run starts several
+/// sessions from one PID, possibly within a second.
+fn session_id() -> String {
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!(
+        "{}-{:x}-{}",
+        std::process::id(),
+        nanos,
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+impl Record {
+    /// The start record for launching `profile` with `command`, timed now.
+    pub fn start(profile: &ResolvedProfile, command: &[String]) -> Self {
+        Self {
+            event: Event::Start,
+            id: Some(session_id()),
+            profile: profile.name.clone(),
+            monitor: profile.monitor_name.clone(),
+            binary: (!profile.skip_gamescope).then(|| profile.binary.clone()),
+            command: command.to_vec(),
+            pid: std::process::id(),
+            start: util::now(),
+            end: None,
+            exit_code: None,
+            signal: None,
+        }
+    }
+
+    /// The end record for this session, exiting with `status` at `end`.
+    pub fn finish(&self, status: ExitStatus, end: u64) -> Self {
+        Self {
+            event: Event::End,
+            end: Some(end),
+            exit_code: status.code(),
+            signal: status.signal(),
+            ..self.clone()
+        }
+    }
+
+    /// What pairs this record with the other one of its session.
+    fn session_key(&self) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => format!("{}@{}", self.pid, self.start),
+        }
+    }
+
+    /// Appends the record to the log at `path` as one JSON line.
+    pub fn append(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create: {}", dir.display()))?;
+        }
+        let mut line = serde_json::to_string(self).context("Failed to serialize session")?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write: {}", path.display()))
+    }
+}
+
+/// The start record for a launch, if the profile logs sessions.
+pub fn record(profile: &ResolvedProfile, command: &[String]) -> Option<Record> {
+    profile
+        .log_sessions
+        .then(|| Record::start(profile, command))
+}
+
+/// Logs the start of a launch when the profile asks for it, returning the
+/// record to finish later.
+pub fn log_start(profile: &ResolvedProfile, command: &[String]) -> Option<Record> {
+    let record = record(profile, command)?;
+    log(&record);
+    Some(record)
+}
+
+/// Logs the end of a session started with `log_start`.
+pub fn log_end(start: Option<&Record>, status: ExitStatus) {
+    if let Some(start) = start {
+        log(&start.finish(status, util::now()));
+    }
+}
+
+/// Appends `record` to the session log; failures only warn.
+pub fn log(record: &Record) {
+    if let Err(e) = log_path().and_then(|path| record.append(&path)) {
+        output::warn(&format!("Failed to log session: {:#}", e));
+    }
+}
+
+/// Reads the log at `path`; a missing log has no records. Lines that don't
+/// parse (e.g. one cut short by a crash) are skipped with a warning.
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let mut records = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => output::warn(&format!(
+                "Skipping invalid session record: {}:{}: {}",
+                path.display(),
+                i + 1,
+                e
+            )),
+        }
+    }
+    Ok(records)
+}
+
+/// Pairs end records with their start records, in start order. An end
+/// record without a start (e.g. a truncated log) is dropped.
+pub fn sessions(records: Vec<Record>) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut open = std::collections::HashMap::new();
+    for record in records {
+        match record.event {
+            Event::Start => {
+                open.insert(record.session_key(), sessions.len());
+                sessions.push(Session {
+                    start: record,
+                    end: None,
+                });
+            }
+            Event::End => {
+                if let Some(i) = open.remove(&record.session_key()) {
+                    sessions[i].end = Some(record);
+                }
+            }
+        }
+    }
+    sessions
+}
+
+/// `1h 02m`, `12m 05s` or `45s`.
+fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}h {:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m {:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// One `sessions` line; `started` is the start time as the user reads it.
+pub fn format_session(session: &Session, started: &str) -> String {
+    let start = &session.start;
+    let outcome = match &session.end {
+        Some(end) => {
+            let duration = format_duration(end.end.unwrap_or(end.start).saturating_sub(end.start));
+            match (end.exit_code, end.signal) {
+                (Some(code), _) => format!("{}, exit {}", duration, code),
+                (None, Some(signal)) => format!("{}, signal {}", duration, signal),
+                (None, None) => duration,
+            }
+        }
+        None => "no end record".to_string(),
+    };
+    let command = if start.command.is_empty() {
+        "(no child)".to_string()
+    } else {
+        start.command.join(" ")
+    };
+    let skipped = if start.binary.is_none() {
+        ", no gamescope"
+    } else {
+        ""
+    };
+    format!(
+        "{}  {} ({}{})  {}  {}",
+        started, start.profile, start.monitor, skipped, outcome, command
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Record {
+        Record {
+            event: Event::Start,
+            id: Some("4242-1-0".to_string()),
+            profile: "hdr".to_string(),
+            monitor: "main".to_string(),
+            binary: Some("gamescope".to_string()),
+            command: vec!["steam".to_string(), "-gamepadui".to_string()],
+            pid: 4242,
+            start: 1_700_000_000,
+            end: None,
+            exit_code: None,
+            signal: None,
+        }
+    }
+
+    #[test]
+    fn test_record_json() {
+        let start = record();
+        assert_eq!(
+            serde_json::to_string(&start).unwrap(),
+            r#"{"event":"start","id":"4242-1-0","profile":"hdr","monitor":"main","binary":"gamescope","command":["steam","-gamepadui"],"pid":4242,"start":1700000000}"#
+        );
+
+        let end = start.finish(ExitStatus::from_raw(3 << 8), 1_700_003_725);
+        assert_eq!(
+            serde_json::to_string(&end).unwrap(),
+            r#"{"event":"end","id":"4242-1-0","profile":"hdr","monitor":"main","binary":"gamescope","command":["steam","-gamepadui"],"pid":4242,"start":1700000000,"end":1700003725,"exitCode":3}"#
+        );
+        let killed = start.finish(ExitStatus::from_raw(libc::SIGKILL), 1_700_000_001);
+        assert_eq!(killed.exit_code, None);
+        assert_eq!(killed.signal, Some(libc::SIGKILL));
+    }
+
+    #[test]
+    fn test_append_and_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("sessions.jsonl");
+        assert!(load(&path).unwrap().is_empty());
+
+        let first = record();
+        let exec = Record {
+            id: Some("4242-2-1".to_string()),
+            start: first.start + 10,
+            ..record()
+        };
+        let stray_end = Record {
+            id: Some("4242-3-2".to_string()),
+            ..first.finish(ExitStatus::from_raw(0), 2)
+        };
+        for r in [
+            &first,
+            &exec,
+            &first.finish(ExitStatus::from_raw(0), first.start + 60),
+            &stray_end,
+        ] {
+            r.append(&path).unwrap();
+        }
+
+        let sessions = sessions(load(&path).unwrap());
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].start, first);
+        assert_eq!(
+            sessions[0].end.as_ref().unwrap().end,
+            Some(first.start + 60)
+        );
+        assert_eq!(sessions[1].start, exec);
+        assert!(sessions[1].end.is_none());
+
+        // A broken line doesn't hide the rest of the log
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.insert_str(0, "{\"event\": \"sta\n");
+        fs::write(&path, content).unwrap();
+        assert_eq!(load(&path).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_pair_by_session_id() {
+        // Two --watch launches from one PID within the same second
+        let first = record();
+        let second = Record {
+            id: Some("4242-1-1".to_string()),
+            ..record()
+        };
+        let records = vec![
+            first.clone(),
+            first.finish(ExitStatus::from_raw(0), first.start),
+            second.clone(),
+            second.finish(ExitStatus::from_raw(1 << 8), second.start + 5),
+        ];
+        let paired = sessions(records);
+        assert_eq!(paired.len(), 2);
+        assert_eq!(paired[0].end.as_ref().unwrap().exit_code, Some(0));
+        assert_eq!(paired[1].end.as_ref().unwrap().exit_code, Some(1));
+
+        // Records without ids still pair by pid and start
+        let legacy = Record {
+            id: None,
+            ..record()
+        };
+        let paired = sessions(vec![
+            legacy.clone(),
+            legacy.finish(ExitStatus::from_raw(0), legacy.start + 1),
+        ]);
+        assert!(paired[0].end.is_some());
+    }
+
+    #[test]
+    fn test_format_session() {
+        let start = record();
+        let session = Session {
+            end: Some(start.finish(ExitStatus::from_raw(0), start.start + 3725)),
+            start,
+        };
+        assert_eq!(
+            format_session(&session, "2023-11-14 22:13"),
+            "2023-11-14 22:13  hdr (main)  1h 02m, exit 0  steam -gamepadui"
+        );
+
+        let session = Session {
+            start: Record {
+                binary: None,
+                command: Vec::new(),
+                ..record()
+            },
+            end: None,
+        };
+        assert_eq!(
+            format_session(&session, "t"),
+            "t  hdr (main, no gamescope)  no end record  (no child)"
+        );
+
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(725), "12m 05s");
+    }
+}
@@ -0,0 +1,115 @@
+//! Last-launch persistence for `run --last`.
+//!
+//! Just before launching, `run` records the resolved profile (with any
+//! command-line overrides applied) and the child command in
+//! `$XDG_STATE_HOME/wayscope/last.json`. `run --last` replays it without
+//! touching the config.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::profile::ResolvedProfile;
+
+/// A recorded launch.
+#[derive(Debug)]
+pub struct LastLaunch {
+    pub profile: ResolvedProfile,
+    /// Child command as given to gamescope; empty for `--no-child`.
+    pub command: Vec<String>,
+}
+
+/// On-disk form; the profile reuses the `show --dump-resolved` document.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct LastFile {
+    command: Vec<String>,
+    resolved: serde_json::Value,
+}
+
+/// Default location of the last-launch record.
+pub fn last_path() -> Result<PathBuf> {
+    let dir = dirs::state_dir()
+        .context("Can't find the state directory for the last launch (is HOME set?)")?;
+    Ok(dir.join("wayscope").join("last.json"))
+}
+
+impl LastLaunch {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = LastFile {
+            command: self.command.clone(),
+            resolved: self.profile.to_value()?,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create: {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize launch")?;
+        fs::write(path, json).with_context(|| format!("Failed to write: {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            anyhow::bail!(
+                "No previous launch recorded ({} doesn't exist); run a profile first",
+                path.display()
+            );
+        }
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read: {}", path.display()))?;
+        let file: LastFile = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid last-launch record: {}", path.display()))?;
+        let profile = ResolvedProfile::from_value(file.resolved)
+            .with_context(|| format!("Invalid last-launch record: {}", path.display()))?;
+        Ok(Self {
+            profile,
+            command: file.command,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, MonitorsConfig, ProfilesConfig};
+
+    fn resolved() -> ResolvedProfile {
+        let monitors: MonitorsConfig = serde_yaml::from_str(
+            "monitors:\n  main: {width: 1920, height: 1080, refreshRate: 60, primary: true}\n",
+        )
+        .unwrap();
+        let profiles: ProfilesConfig =
+            serde_yaml::from_str("profiles:\n  hdr: {useHDR: true}\n").unwrap();
+        Config { monitors, profiles }
+            .resolve_profile("hdr")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_last_launch_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("last.json");
+        let last = LastLaunch {
+            profile: resolved(),
+            command: vec!["steam".to_string(), "-gamepadui".to_string()],
+        };
+        last.save(&path).unwrap();
+
+        let loaded = LastLaunch::load(&path).unwrap();
+        assert_eq!(loaded.profile, last.profile);
+        assert_eq!(loaded.command, last.command);
+    }
+
+    #[test]
+    fn test_last_launch_missing_or_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("last.json");
+        let err = LastLaunch::load(&path).unwrap_err();
+        assert!(err.to_string().contains("No previous launch recorded"));
+
+        std::fs::write(&path, "{\"command\": []}").unwrap();
+        assert!(LastLaunch::load(&path).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Small helpers shared by several commands: wall-clock time and its local
+//! formatting, and profile names made safe for file names.
+
+use std::ffi::CStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The current time in Unix seconds (0 if the clock is before the epoch).
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `secs` as local `YYYY-MM-DD HH:MM`.
+pub fn local_time(secs: u64) -> String {
+    format_local_time(secs, c"%Y-%m-%d %H:%M")
+}
+
+/// `secs` as local time in strftime `format`, or as plain seconds if it
+/// can't be converted.
+pub fn format_local_time(secs: u64, format: &CStr) -> String {
+    let Ok(time) = libc::time_t::try_from(secs) else {
+        return secs.to_string();
+    };
+    let mut buf = [0u8; 32];
+    // SAFETY: `tm` is plain data that localtime_r fills in, and strftime
+    // writes at most `buf.len()` bytes
+    let len = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return secs.to_string();
+        }
+        libc::strftime(buf.as_mut_ptr().cast(), buf.len(), format.as_ptr(), &tm)
+    };
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+/// `name` made safe as part of a file name: anything but ASCII letters,
+/// digits, `-`, `_` and `.` becomes `_`.
+pub fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_part() {
+        assert_eq!(file_name_part("hdr-2"), "hdr-2");
+        assert_eq!(file_name_part("../tv/hdr"), ".._tv_hdr");
+        assert_eq!(file_name_part("ゲーム"), "___");
+    }
+}
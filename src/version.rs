@@ -0,0 +1,179 @@
+//! Version and environment report for bug reports.
+//!
+//! `wayscope version --verbose` gathers what a reporter would otherwise
+//! collect by hand: the gamescope build in use, the session type, and where
+//! config is read from.
+
+use std::ffi::OsStr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{self, MonitorsConfig};
+use crate::output;
+
+/// Graphical session wayscope was started from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Tty,
+    Unknown,
+}
+
+impl std::fmt::Display for SessionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wayland => write!(f, "Wayland"),
+            Self::X11 => write!(f, "X11"),
+            Self::Tty => write!(f, "tty"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Detects the session from the process environment.
+pub fn session_type() -> SessionType {
+    classify_session(
+        std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        std::env::var_os("DISPLAY").is_some(),
+    )
+}
+
+/// `XDG_SESSION_TYPE` wins; otherwise the display sockets decide.
+fn classify_session(xdg: Option<&str>, wayland: bool, x11: bool) -> SessionType {
+    match xdg {
+        Some("wayland") => SessionType::Wayland,
+        Some("x11") => SessionType::X11,
+        Some("tty") => SessionType::Tty,
+        _ if wayland => SessionType::Wayland,
+        _ if x11 => SessionType::X11,
+        _ => SessionType::Unknown,
+    }
+}
+
+/// Locates `binary` the way `exec` would: as a path if it has a slash,
+/// otherwise the first executable match in `PATH`.
+pub fn find_binary(binary: &str) -> Option<PathBuf> {
+    find_in_path(binary, std::env::var_os("PATH").as_deref())
+}
+
+fn find_in_path(binary: &str, search: Option<&OsStr>) -> Option<PathBuf> {
+    if binary.contains('/') {
+        let path = PathBuf::from(binary);
+        return is_executable(&path).then_some(path);
+    }
+    std::env::split_paths(search?)
+        .map(|dir| dir.join(binary))
+        .find(|path| is_executable(path))
+}
+
+/// A regular file with an execute bit set; exec skips anything else.
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// Asks gamescope for its version. gamescope writes the banner to stderr,
+/// so both streams are checked.
+pub fn gamescope_version(binary: &Path) -> Option<String> {
+    let output = Command::new(binary).arg("--version").output().ok()?;
+    parse_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| parse_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Pulls the version out of a banner like `gamescope version 3.14.2 (gcc ...)`.
+fn parse_version(banner: &str) -> Option<String> {
+    banner.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        words.find(|w| *w == "version")?;
+        words.next().map(str::to_string)
+    })
+}
+
+/// Diagnostic details as (label, value) pairs, starting with wayscope's version.
+pub fn report() -> Vec<(&'static str, String)> {
+    let mut lines = vec![("wayscope", env!("CARGO_PKG_VERSION").to_string())];
+
+    // Profiles may pin their own binary; this is what the default resolves to.
+    let (binary, source) =
+        config::resolve_binary(None, std::env::var(config::GAMESCOPE_BIN_ENV).ok());
+    match find_binary(&binary) {
+        Some(path) => {
+            let version = gamescope_version(&path).unwrap_or_else(|| "unknown".to_string());
+            lines.push(("gamescope", version));
+            lines.push((
+                "gamescope path",
+                format!("{} (from {})", path.display(), source),
+            ));
+        }
+        None => lines.push(("gamescope", format!("'{}' not found", binary))),
+    }
+    lines.push(("session", session_type().to_string()));
+    lines.push((
+        "config dir",
+        MonitorsConfig::config_dir().display().to_string(),
+    ));
+    lines.push(("system config dir", config::SYSTEM_CONFIG_DIR.to_string()));
+    lines
+}
+
+pub fn print(verbose: bool) {
+    println!("wayscope {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return;
+    }
+    for (label, value) in report().into_iter().skip(1) {
+        output::key_value(label, &value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("[gamescope] [Info]  console: gamescope version 3.14.2 (gcc 13.2.0)"),
+            Some("3.14.2".to_string())
+        );
+        assert_eq!(
+            parse_version("wlserver: init\ngamescope version 3.15.9\n"),
+            Some("3.15.9".to_string())
+        );
+        assert_eq!(parse_version("usage: gamescope [options...]"), None);
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("launcher");
+        let data = dir.path().join("readme");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::write(&data, "").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let search = Some(dir.path().as_os_str());
+
+        assert_eq!(find_in_path("launcher", search), Some(script.clone()));
+        assert_eq!(
+            find_in_path(script.to_str().unwrap(), None),
+            Some(script.clone())
+        );
+        // Not executable, missing, or a directory
+        assert_eq!(find_in_path("readme", search), None);
+        assert_eq!(find_in_path(data.to_str().unwrap(), None), None);
+        assert_eq!(find_in_path("lanucher", search), None);
+        assert_eq!(find_in_path(dir.path().to_str().unwrap(), None), None);
+        assert_eq!(find_in_path("launcher", None), None);
+    }
+
+    #[test]
+    fn test_classify_session() {
+        assert_eq!(classify_session(Some("x11"), true, true), SessionType::X11);
+        assert_eq!(classify_session(None, true, true), SessionType::Wayland);
+        assert_eq!(classify_session(Some(""), false, true), SessionType::X11);
+        assert_eq!(classify_session(None, false, false), SessionType::Unknown);
+    }
+}
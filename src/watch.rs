@@ -0,0 +1,279 @@
+//! `run --watch`: relaunch the session whenever the config changes.
+//!
+//! The config files, and the profile's `envFile` if it has one, are polled
+//! for changes (no inotify dependency). After a change settles, the profile is resolved again; if the launch it produces
+//! differs from the running one, the session is stopped and relaunched.
+//! Ctrl-C stops the session and exits.
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result};
+
+use crate::command;
+use crate::output;
+use crate::sessions;
+
+/// How often the config files and the session are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// A change only counts once the files have been quiet this long, so an
+/// editor's burst of writes causes one relaunch.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+/// How long a session gets to exit after SIGTERM before it's killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// A launch as resolved from the current config: a line identifying it
+/// (compared to decide whether to relaunch), the files it was resolved from
+/// (watched for the next change), the command to spawn, its session log
+/// record if the profile logs sessions, and what runs right before it
+/// (`preHook`, `--last`, resource limits), only when the session is
+/// actually (re)started.
+pub struct Launch {
+    pub line: String,
+    pub files: Vec<PathBuf>,
+    pub command: Command,
+    pub session: Option<sessions::Record>,
+    pub prepare: Box<dyn FnOnce() -> Result<()>>,
+}
+
+/// The running session and its session log record.
+struct Running {
+    child: Child,
+    session: Option<sessions::Record>,
+}
+
+/// Modification state of a set of files; a missing file is a state too.
+pub struct Snapshot {
+    files: Vec<PathBuf>,
+    stamps: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl Snapshot {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        let stamps = files.iter().map(|path| stamp(path)).collect();
+        Self { files, stamps }
+    }
+
+    /// Watches `files` from now on, if they aren't the ones already watched.
+    pub fn watch(&mut self, files: &[PathBuf]) {
+        if self.files != files {
+            *self = Self::new(files.to_vec());
+        }
+    }
+
+    /// Whether any file changed since the last call (or `new`).
+    pub fn changed(&mut self) -> bool {
+        let stamps: Vec<_> = self.files.iter().map(|path| stamp(path)).collect();
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Runs the launch from `resolve`, relaunching when its files change the
+/// resolved launch, until interrupted.
+pub fn run(mut resolve: impl FnMut() -> Result<Launch>) -> Result<()> {
+    let handler = on_interrupt as extern "C" fn(libc::c_int) as *const () as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+
+    let launch = resolve()?;
+    let mut snapshot = Snapshot::new(launch.files.clone());
+    let mut line = launch.line.clone();
+    let mut session = Some(start(launch)?);
+
+    while !INTERRUPTED.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL);
+
+        if let Some(running) = &mut session {
+            if let Some(status) = running
+                .child
+                .try_wait()
+                .context("Failed to check session")?
+            {
+                sessions::log_end(running.session.as_ref(), status);
+                report_exit(status);
+                session = None;
+            }
+        }
+
+        if !snapshot.changed() {
+            continue;
+        }
+        while !INTERRUPTED.load(Ordering::SeqCst) {
+            thread::sleep(DEBOUNCE);
+            if !snapshot.changed() {
+                break;
+            }
+        }
+
+        let launch = match resolve() {
+            Ok(launch) => launch,
+            Err(e) => {
+                output::warn(&format!("Config changed but failed to load: {:#}", e));
+                continue;
+            }
+        };
+        // A changed envFile path moves the watch to the new file
+        snapshot.watch(&launch.files);
+        if launch.line == line && session.is_some() {
+            output::info("Config changed; the launch is the same, keeping the session");
+            continue;
+        }
+        if let Some(running) = session.take() {
+            output::info("Config changed; relaunching");
+            stop(running)?;
+        }
+        line = launch.line.clone();
+        session = Some(start(launch)?);
+    }
+
+    if let Some(running) = session {
+        output::info("Stopping session");
+        stop(running)?;
+    }
+    Ok(())
+}
+
+fn start(launch: Launch) -> Result<Running> {
+    let Launch {
+        mut command,
+        session,
+        prepare,
+        ..
+    } = launch;
+    prepare()?;
+    if let Some(record) = &session {
+        sessions::log(record);
+    }
+    let child = command.spawn().context("Failed to execute command")?;
+    Ok(Running { child, session })
+}
+
+fn report_exit(status: ExitStatus) {
+    let how = match status.code() {
+        Some(code) => format!("exited with code {}", code),
+        None => "was terminated by a signal".to_string(),
+    };
+    output::info(&format!(
+        "Session {}; waiting for a config change (Ctrl-C to quit)",
+        how
+    ));
+}
+
+/// Asks the session to exit with SIGTERM, killing it after `STOP_TIMEOUT`.
+fn stop(running: Running) -> Result<()> {
+    let status = terminate(running.child)?;
+    sessions::log_end(running.session.as_ref(), status);
+    Ok(())
+}
+
+fn terminate(mut child: Child) -> Result<ExitStatus> {
+    command::terminate(&child);
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait().context("Failed to stop session")? {
+            return Ok(status);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    output::warn("Session didn't exit after SIGTERM; killing it");
+    child.kill().context("Failed to kill session")?;
+    child.wait().context("Failed to stop session")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.yaml");
+        let missing = dir.path().join("monitors.yaml");
+        std::fs::write(&config, "profiles: {}\n").unwrap();
+
+        let mut snapshot = Snapshot::new(vec![config.clone(), missing.clone()]);
+        assert!(!snapshot.changed());
+
+        std::fs::write(&config, "profiles: {a: {}}\n").unwrap();
+        assert!(snapshot.changed());
+        assert!(!snapshot.changed());
+
+        // A file appearing (or disappearing) counts
+        std::fs::write(&missing, "monitors: {}\n").unwrap();
+        assert!(snapshot.changed());
+        std::fs::remove_file(&missing).unwrap();
+        assert!(snapshot.changed());
+    }
+
+    #[test]
+    fn test_snapshot_follows_new_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.env");
+        let new = dir.path().join("new.env");
+        let mut snapshot = Snapshot::new(vec![old.clone()]);
+
+        // The same files keep their stamps, so a pending change isn't lost
+        std::fs::write(&old, "A=1\n").unwrap();
+        snapshot.watch(std::slice::from_ref(&old));
+        assert!(snapshot.changed());
+
+        snapshot.watch(std::slice::from_ref(&new));
+        std::fs::write(&old, "A=2\n").unwrap();
+        assert!(!snapshot.changed());
+        std::fs::write(&new, "B=1\n").unwrap();
+        assert!(snapshot.changed());
+    }
+
+    #[test]
+    fn test_start_prepares_the_launch() {
+        use std::sync::Arc;
+
+        let prepared = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&prepared);
+        let launch = Launch {
+            line: "true".to_string(),
+            files: Vec::new(),
+            command: Command::new("true"),
+            session: None,
+            prepare: Box::new(move || {
+                flag.store(true, Ordering::SeqCst);
+                Ok(())
+            }),
+        };
+        // Resolving alone has no side effects; starting runs them
+        assert!(!prepared.load(Ordering::SeqCst));
+        let mut running = start(launch).unwrap();
+        assert!(prepared.load(Ordering::SeqCst));
+        assert!(running.child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_terminate_session() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let started = Instant::now();
+        let status = terminate(child).unwrap();
+        assert!(started.elapsed() < STOP_TIMEOUT);
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+}